@@ -0,0 +1,21 @@
+//! Inputs shared by the benchmarks
+
+/// Buffer sizes in bytes, from typed input up to large pastes
+pub const SIZES: [usize; 3] = [1_000, 100_000, 4_000_000];
+
+/// JSON of about `size` bytes, pretty printed over many lines or on a single line
+pub fn json(size: usize, pretty: bool) -> String {
+    let separator = if pretty { "\n" } else { " " };
+    let mut json = String::from("[");
+    let mut id = 0;
+    while json.len() < size {
+        json.push_str(separator);
+        json.push_str(&format!(
+            r#"{{"id": {id}, "name": "entry number {id}", "tags": ["ä", "漢字"]}},"#
+        ));
+        id += 1;
+    }
+    json.push_str(separator);
+    json.push(']');
+    json
+}
@@ -0,0 +1,54 @@
+//! Per-keystroke cost of editing through [`Reedline`], including recording the undo steps
+//!
+//! The buffers imitate pasted JSON like in the `line_buffer` benchmark, with the cursor at
+//! the end to leave out moving the text behind it. Only the changed text is kept for undo,
+//! so the timings should not grow with the size of the buffer.
+//!
+//! Run with `cargo bench --bench editor`
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use reedline::{EditCommand, Reedline};
+
+mod common;
+use common::{json, SIZES};
+
+fn reedline_with(buffer: String) -> Reedline {
+    let mut reedline = Reedline::create();
+    reedline.run_edit_commands(&[EditCommand::InsertString(buffer)]);
+    reedline
+}
+
+fn bench_keystrokes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("editor keystroke");
+    for size in SIZES {
+        let buffer = json(size, true);
+
+        // Every word typed is a new undo step
+        group.bench_with_input(BenchmarkId::new("type", size), &size, |b, _| {
+            let mut reedline = reedline_with(buffer.clone());
+            b.iter(|| {
+                reedline.run_edit_commands(&[EditCommand::InsertChar('x')]);
+                reedline.run_edit_commands(&[EditCommand::InsertChar(' ')]);
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("backspace", size), &size, |b, _| {
+            let mut reedline = reedline_with(buffer.clone());
+            b.iter(|| {
+                reedline.run_edit_commands(&[EditCommand::InsertChar('x')]);
+                reedline.run_edit_commands(&[EditCommand::Backspace]);
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("undo_redo", size), &size, |b, _| {
+            let mut reedline = reedline_with(buffer.clone());
+            reedline.run_edit_commands(&[EditCommand::InsertString(" word".into())]);
+            b.iter(|| {
+                reedline.run_edit_commands(&[EditCommand::Undo]);
+                reedline.run_edit_commands(&[EditCommand::Redo]);
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_keystrokes);
+criterion_main!(benches);
@@ -0,0 +1,82 @@
+//! Per-keystroke cost of [`LineBuffer`] operations for growing buffer sizes
+//!
+//! The buffers imitate pasted JSON, once pretty printed over many lines and once as a
+//! single line. The cursor sits in the middle of the buffer, the timings should not grow
+//! with the size of the buffer. The exception is `insert`, which moves the text behind the
+//! cursor in memory, as the buffer is kept contiguous for painting.
+//!
+//! Run with `cargo bench --bench line_buffer`
+
+use criterion::black_box;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use reedline::LineBuffer;
+
+mod common;
+use common::{json, SIZES};
+
+fn line_buffer_with_cursor_in_middle(size: usize, pretty: bool) -> LineBuffer {
+    let mut line_buffer = LineBuffer::from(json(size, pretty).as_str());
+    line_buffer.set_insertion_point(line_buffer.len() / 2);
+    line_buffer.move_word_right();
+    line_buffer
+}
+
+fn bench_keystrokes(c: &mut Criterion) {
+    for (pretty, layout) in [(true, "multiline"), (false, "single line")] {
+        let mut group = c.benchmark_group(format!("keystroke {layout}"));
+        for size in SIZES {
+            let line_buffer = line_buffer_with_cursor_in_middle(size, pretty);
+
+            group.bench_with_input(BenchmarkId::new("insert", size), &size, |b, _| {
+                let mut line_buffer = line_buffer.clone();
+                b.iter(|| {
+                    line_buffer.insert_char(black_box('x'));
+                    line_buffer.delete_left_grapheme();
+                });
+            });
+            group.bench_with_input(BenchmarkId::new("move_char", size), &size, |b, _| {
+                let mut line_buffer = line_buffer.clone();
+                b.iter(|| {
+                    line_buffer.move_right();
+                    line_buffer.move_left();
+                });
+            });
+            group.bench_with_input(BenchmarkId::new("move_word", size), &size, |b, _| {
+                let mut line_buffer = line_buffer.clone();
+                b.iter(|| {
+                    line_buffer.move_word_right();
+                    line_buffer.move_word_left();
+                });
+            });
+            group.bench_with_input(BenchmarkId::new("move_line", size), &size, |b, _| {
+                let mut line_buffer = line_buffer.clone();
+                b.iter(|| {
+                    line_buffer.move_line_up();
+                    line_buffer.move_line_down();
+                });
+            });
+            group.bench_with_input(BenchmarkId::new("line_queries", size), &size, |b, _| {
+                b.iter(|| {
+                    black_box(line_buffer.line());
+                    black_box(line_buffer.num_lines());
+                    black_box(line_buffer.current_line_range());
+                    black_box(line_buffer.is_cursor_at_first_line());
+                    black_box(line_buffer.is_cursor_at_last_line());
+                });
+            });
+            group.bench_with_input(BenchmarkId::new("line_edges", size), &size, |b, _| {
+                let mut line_buffer = line_buffer.clone();
+                let start = line_buffer.insertion_point();
+                b.iter(|| {
+                    line_buffer.move_to_line_start();
+                    line_buffer.move_to_line_end();
+                    line_buffer.set_insertion_point(start);
+                });
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_keystrokes);
+criterion_main!(benches);
@@ -0,0 +1,144 @@
+/// Rule pairing a single opening character with its closer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoPairRule {
+    /// The character that inserts the pair when typed
+    pub open: char,
+    /// The character inserted behind the cursor
+    pub close: char,
+    /// Don't pair when typed directly behind an alphanumeric character,
+    /// e.g. for the apostrophe in `don't`
+    pub skip_after_word: bool,
+}
+
+impl AutoPairRule {
+    /// Rule that pairs `open` with `close` everywhere
+    pub const fn new(open: char, close: char) -> Self {
+        Self {
+            open,
+            close,
+            skip_after_word: false,
+        }
+    }
+}
+
+/// Policy to insert the matching closer while typing brackets and quotes
+///
+/// - Typing an opening character inserts its closer behind the cursor, as long as the
+///   cursor is at the end of the buffer or in front of whitespace or another closer.
+/// - Typing a closer right in front of the same closer moves over it.
+/// - Backspace between an empty pair deletes both characters.
+///
+/// Text inserted at once, like a bracketed paste, is never paired.
+/// The default pairs `()`, `[]`, `{}`, `""` and `''`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoPair {
+    rules: Vec<AutoPairRule>,
+}
+
+impl Default for AutoPair {
+    fn default() -> Self {
+        Self {
+            rules: vec![
+                AutoPairRule::new('(', ')'),
+                AutoPairRule::new('[', ']'),
+                AutoPairRule::new('{', '}'),
+                AutoPairRule {
+                    skip_after_word: true,
+                    ..AutoPairRule::new('"', '"')
+                },
+                AutoPairRule {
+                    skip_after_word: true,
+                    ..AutoPairRule::new('\'', '\'')
+                },
+            ],
+        }
+    }
+}
+
+impl AutoPair {
+    /// Add a rule, replacing any existing rule for the same opening character
+    #[must_use]
+    pub fn with_rule(mut self, rule: AutoPairRule) -> Self {
+        self = self.without_rule(rule.open);
+        self.rules.push(rule);
+        self
+    }
+
+    /// Remove the rule for the given opening character
+    #[must_use]
+    pub fn without_rule(mut self, open: char) -> Self {
+        self.rules.retain(|rule| rule.open != open);
+        self
+    }
+
+    /// The rules of the policy
+    pub fn rules(&self) -> &[AutoPairRule] {
+        &self.rules
+    }
+
+    fn is_closer(&self, c: char) -> bool {
+        self.rules.iter().any(|rule| rule.close == c)
+    }
+
+    /// Whether typing `c` in front of `next` should move over `next` instead
+    pub(crate) fn skips_over(&self, c: char, next: Option<char>) -> bool {
+        next == Some(c) && self.is_closer(c)
+    }
+
+    /// The closer to insert together with the typed `c`, if any
+    pub(crate) fn closer_for(
+        &self,
+        c: char,
+        previous: Option<char>,
+        next: Option<char>,
+    ) -> Option<char> {
+        let rule = self.rules.iter().find(|rule| rule.open == c)?;
+        let fits_next = next.map_or(true, |next| next.is_whitespace() || self.is_closer(next));
+        let fits_previous = !rule.skip_after_word || !previous.map_or(false, char::is_alphanumeric);
+        (fits_next && fits_previous).then_some(rule.close)
+    }
+
+    /// Whether the cursor sits between an empty pair
+    pub(crate) fn is_empty_pair(&self, previous: Option<char>, next: Option<char>) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| previous == Some(rule.open) && next == Some(rule.close))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case('(', None, None, Some(')'))]
+    #[case('(', Some('a'), Some(' '), Some(')'))]
+    #[case('(', None, Some(']'), Some(')'))]
+    #[case('(', None, Some('a'), None)]
+    #[case('"', Some(' '), None, Some('"'))]
+    #[case('\'', Some('n'), None, None)]
+    #[case('a', None, None, None)]
+    fn closer_for_default_rules(
+        #[case] c: char,
+        #[case] previous: Option<char>,
+        #[case] next: Option<char>,
+        #[case] expected: Option<char>,
+    ) {
+        assert_eq!(AutoPair::default().closer_for(c, previous, next), expected);
+    }
+
+    #[test]
+    fn with_rule_replaces_existing_rule() {
+        let auto_pair = AutoPair::default()
+            .without_rule('\'')
+            .with_rule(AutoPairRule::new('<', '>'))
+            .with_rule(AutoPairRule::new('(', ']'));
+
+        assert_eq!(auto_pair.closer_for('\'', None, None), None);
+        assert_eq!(auto_pair.closer_for('<', None, None), Some('>'));
+        assert_eq!(auto_pair.closer_for('(', None, None), Some(']'));
+        assert_eq!(auto_pair.rules().len(), 5);
+    }
+}
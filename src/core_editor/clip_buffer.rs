@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 /// Defines an interface to interact with a Clipboard for cut and paste.
 ///
 /// Mutable reference requirements are stricter than always necessary, but the currently used system clipboard API demands them for exclusive access.
@@ -25,38 +27,101 @@ pub enum ClipboardMode {
     Normal,
     /// As new lines below or above
     Lines,
+    /// As a rectangle with one line of content per line of the buffer,
+    /// starting at the column of the cursor
+    Block,
+}
+
+/// Default number of entries kept by the [`LocalClipboard`] kill ring
+pub const DEFAULT_KILL_RING_SIZE: usize = 16;
+
+/// Determines how a kill directly following another kill is joined with the newest entry
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum KillDirection {
+    /// Text was killed to the right of the cursor and is appended
+    Forward,
+    /// Text was killed to the left of the cursor and is prepended
+    Backward,
 }
 
 /// Simple buffer that provides a clipboard only usable within the application/library.
-#[derive(Default)]
+///
+/// Behaves like an Emacs style kill ring: every cut is stored as a new entry
+/// (up to a fixed capacity) and [`LocalClipboard::rotate`] cycles through the older entries.
 pub struct LocalClipboard {
-    content: String,
-    mode: ClipboardMode,
+    entries: VecDeque<(String, ClipboardMode)>,
+    capacity: usize,
+    yank_index: usize,
+    accumulate: Option<KillDirection>,
+}
+
+impl Default for LocalClipboard {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_KILL_RING_SIZE)
+    }
 }
 
 impl LocalClipboard {
-    #[allow(dead_code)]
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Create a kill ring keeping at most `capacity` entries (at least one)
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity: capacity.max(1),
+            yank_index: 0,
+            accumulate: None,
+        }
+    }
+
+    /// Change the number of entries kept, dropping the oldest ones if necessary
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        self.entries.truncate(self.capacity);
+        if self.yank_index >= self.entries.len() {
+            self.yank_index = 0;
+        }
+    }
+
+    /// If `Some`, the next [`Clipboard::set`] is joined with the newest entry
+    /// instead of creating a new one.
+    pub fn set_accumulate(&mut self, direction: Option<KillDirection>) {
+        self.accumulate = direction;
+    }
+
+    /// Move the yank position to the next older entry (wrapping around) and return it
+    pub fn rotate(&mut self) -> Option<(String, ClipboardMode)> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        self.yank_index = (self.yank_index + 1) % self.entries.len();
+        self.entries.get(self.yank_index).cloned()
+    }
 }
 
 impl Clipboard for LocalClipboard {
     fn set(&mut self, content: &str, mode: ClipboardMode) {
-        content.clone_into(&mut self.content);
-        self.mode = mode;
+        match (self.accumulate.take(), self.entries.front_mut()) {
+            (Some(KillDirection::Forward), Some((newest, _))) => newest.push_str(content),
+            (Some(KillDirection::Backward), Some((newest, _))) => newest.insert_str(0, content),
+            _ => {
+                self.entries.push_front((content.to_owned(), mode));
+                self.entries.truncate(self.capacity);
+            }
+        }
+        self.yank_index = 0;
     }
 
     fn get(&mut self) -> (String, ClipboardMode) {
-        (self.content.clone(), self.mode)
+        self.entries
+            .get(self.yank_index)
+            .cloned()
+            .unwrap_or_default()
     }
 }
 
-/// Creates a local clipboard
-pub fn get_local_clipboard() -> Box<dyn Clipboard> {
-    Box::new(LocalClipboard::new())
-}
-
 #[cfg(feature = "system_clipboard")]
 pub use system_clipboard::SystemClipboard;
 
@@ -117,10 +182,10 @@ mod system_clipboard {
 mod tests {
     #[cfg(feature = "system_clipboard")]
     use super::get_system_clipboard;
-    use super::{get_local_clipboard, ClipboardMode};
+    use super::{Clipboard, ClipboardMode, KillDirection, LocalClipboard};
     #[test]
     fn reads_back_local() {
-        let mut cb = get_local_clipboard();
+        let mut cb = LocalClipboard::new();
         // If the system clipboard is used we want to persist it for the user
         let previous_state = cb.get().0;
 
@@ -154,4 +219,33 @@ mod tests {
 
         cb.set(&previous_state, ClipboardMode::Normal);
     }
+
+    #[test]
+    fn kill_ring_keeps_older_entries() {
+        let mut cb = LocalClipboard::with_capacity(2);
+        cb.set("one", ClipboardMode::Normal);
+        cb.set("two", ClipboardMode::Normal);
+        cb.set("three", ClipboardMode::Normal);
+
+        assert_eq!(cb.get().0, "three");
+        assert_eq!(cb.rotate().map(|(s, _)| s), Some("two".to_string()));
+        assert_eq!(cb.get().0, "two");
+        assert_eq!(cb.rotate().map(|(s, _)| s), Some("three".to_string()));
+    }
+
+    #[test]
+    fn kill_ring_accumulates_consecutive_kills() {
+        let mut cb = LocalClipboard::new();
+        cb.set("world", ClipboardMode::Normal);
+        cb.set_accumulate(Some(KillDirection::Backward));
+        cb.set("hello ", ClipboardMode::Normal);
+        cb.set_accumulate(Some(KillDirection::Forward));
+        cb.set("!", ClipboardMode::Normal);
+
+        assert_eq!(cb.get().0, "hello world!");
+        assert_eq!(
+            cb.rotate().map(|(s, _)| s),
+            Some("hello world!".to_string())
+        );
+    }
 }
@@ -1,9 +1,16 @@
-use super::{edit_stack::EditStack, Clipboard, ClipboardMode, LineBuffer};
+use super::{
+    undo_tree::{UndoStateId, UndoStep, UndoTree},
+    AutoPair, Clipboard, ClipboardMode, KillDirection, LineBuffer, LocalClipboard, WordDefinition,
+};
 #[cfg(feature = "system_clipboard")]
 use crate::core_editor::get_system_clipboard;
-use crate::enums::{EditType, UndoBehavior};
-use crate::{core_editor::get_local_clipboard, EditCommand};
+use crate::enums::{EditType, ReedlineRawEvent, UndoBehavior};
+use crate::{DefaultIndenter, EditCommand, Indenter, TextObject, TextObjectKind, TextObjectScope};
+#[cfg(feature = "system_clipboard")]
 use std::ops::DerefMut;
+use std::{collections::HashMap, ops::Range, time::Duration};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 /// Stateful editor executing changes to the underlying [`LineBuffer`]
 ///
@@ -11,24 +18,133 @@ use std::ops::DerefMut;
 /// the undo/redo history and has facilities for cut/copy/yank/paste
 pub struct Editor {
     line_buffer: LineBuffer,
-    cut_buffer: Box<dyn Clipboard>,
+    cut_buffer: LocalClipboard,
     #[cfg(feature = "system_clipboard")]
     system_clipboard: Box<dyn Clipboard>,
-    edit_stack: EditStack<LineBuffer>,
+    undo_tree: UndoTree<UndoStep>,
     last_undo_behavior: UndoBehavior,
     selection_anchor: Option<usize>,
+    // The selection spans a rectangle of display columns instead of a range of text
+    block_selection: bool,
+    // The selection covers the whole lines between the selection anchor and the cursor
+    line_selection: bool,
+    // Whether consecutive cuts are joined in the kill ring
+    join_kills: bool,
+    // Whether the previous edit command cut text into the kill ring
+    last_edit_was_kill: bool,
+    // Text inserted by the last yank, replaced on a following `YankPop`
+    last_yank: Option<Range<usize>>,
+    // Cursors besides the one of the `line_buffer`, kept sorted and without duplicates
+    secondary_cursors: Vec<SecondaryCursor>,
+    auto_pair: Option<AutoPair>,
+    indenter: Option<Box<dyn Indenter>>,
+    // Named registers, used instead of the `cut_buffer` when selected
+    registers: HashMap<char, (String, ClipboardMode)>,
+    // Register selected for the next edit command
+    selected_register: Option<char>,
+    // Raw events of the named macros
+    macros: HashMap<char, Vec<ReedlineRawEvent>>,
+    // Graphemes replaced by the consecutive `OverwriteChar` commands so far, `None` where
+    // the character was appended
+    overwritten: Vec<Option<String>>,
+    // Positions of the named marks, moved along with the edits of the text
+    marks: HashMap<char, usize>,
+    // Positions jumped away from, and the index of the current one in them
+    jump_list: Vec<usize>,
+    jump_index: usize,
+    // Pattern and direction of the last buffer search, for `RepeatBufferSearch`
+    last_search: Option<(String, bool)>,
+    // Whether the last edit command was a buffer search, whose matches get highlighted
+    search_highlight: bool,
+}
+
+/// The number of positions kept in the jump list
+const JUMP_LIST_SIZE: usize = 100;
+
+/// An additional cursor used for multi-cursor editing
+///
+/// Every secondary cursor carries its own selection, just like the primary cursor of the
+/// [`Editor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SecondaryCursor {
+    pub(crate) insertion_point: usize,
+    pub(crate) selection_anchor: Option<usize>,
+}
+
+impl SecondaryCursor {
+    /// The selected range, guaranteed to be ascending
+    pub(crate) fn selection(&self) -> Option<(usize, usize)> {
+        self.selection_anchor.map(|selection_anchor| {
+            (
+                selection_anchor.min(self.insertion_point),
+                selection_anchor.max(self.insertion_point),
+            )
+        })
+    }
+
+    fn start(&self) -> usize {
+        self.selection()
+            .map_or(self.insertion_point, |(start, _)| start)
+    }
+
+    fn end(&self) -> usize {
+        self.selection()
+            .map_or(self.insertion_point, |(_, end)| end)
+    }
+
+    fn shift(&mut self, delta: isize) {
+        self.insertion_point = shift_position(self.insertion_point, delta);
+        self.selection_anchor = self
+            .selection_anchor
+            .map(|anchor| shift_position(anchor, delta));
+    }
+}
+
+/// Length of the leading spaces and tabs of `line`
+fn indentation_len(line: &str) -> usize {
+    line.bytes()
+        .take_while(|b| *b == b' ' || *b == b'\t')
+        .count()
+}
+
+fn shift_position(position: usize, delta: isize) -> usize {
+    if delta >= 0 {
+        position + delta as usize
+    } else {
+        position.saturating_sub(delta.unsigned_abs())
+    }
 }
 
 impl Default for Editor {
     fn default() -> Self {
+        let mut line_buffer = LineBuffer::new();
+        line_buffer.track_edits();
+        line_buffer.track_change();
         Editor {
-            line_buffer: LineBuffer::new(),
-            cut_buffer: get_local_clipboard(),
+            line_buffer,
+            cut_buffer: LocalClipboard::new(),
             #[cfg(feature = "system_clipboard")]
             system_clipboard: get_system_clipboard(),
-            edit_stack: EditStack::new(),
+            undo_tree: UndoTree::new(),
             last_undo_behavior: UndoBehavior::CreateUndoPoint,
             selection_anchor: None,
+            block_selection: false,
+            line_selection: false,
+            join_kills: false,
+            last_edit_was_kill: false,
+            last_yank: None,
+            secondary_cursors: Vec::new(),
+            auto_pair: None,
+            indenter: None,
+            registers: HashMap::new(),
+            selected_register: None,
+            macros: HashMap::new(),
+            overwritten: Vec::new(),
+            marks: HashMap::new(),
+            jump_list: Vec::new(),
+            jump_index: 0,
+            last_search: None,
+            search_highlight: false,
         }
     }
 }
@@ -40,13 +156,169 @@ impl Editor {
     }
 
     /// Set the current [`LineBuffer`].
-    /// [`UndoBehavior`] specifies how this change should be reflected on the undo stack.
+    /// [`UndoBehavior`] specifies how this change should be reflected on the undo tree.
     pub(crate) fn set_line_buffer(&mut self, line_buffer: LineBuffer, undo_behavior: UndoBehavior) {
-        self.line_buffer = line_buffer;
+        self.replace_line_buffer(line_buffer);
+        self.secondary_cursors.clear();
         self.update_undo_state(undo_behavior);
     }
 
+    /// Take over the text and cursor of another [`LineBuffer`] while keeping the configured
+    /// [`WordDefinition`]
+    ///
+    /// Only the changed part of the text is replaced, so marks stay on their text.
+    fn replace_line_buffer(&mut self, line_buffer: LineBuffer) {
+        let new = line_buffer.get_buffer();
+        let (range, len) = changed_range(self.line_buffer.get_buffer(), new);
+        let start = range.start;
+        self.line_buffer
+            .replace_range(range, &new[start..start + len]);
+        self.line_buffer
+            .set_insertion_point(line_buffer.insertion_point());
+        self.follow_edits();
+    }
+
+    /// Set which characters separate words for the word motions and cuts
+    pub(crate) fn set_word_definition(&mut self, word_definition: WordDefinition) {
+        self.line_buffer.set_word_definition(word_definition);
+    }
+
+    /// Set the policy for pairing brackets and quotes while typing, `None` disables it
+    pub(crate) fn set_auto_pair(&mut self, auto_pair: Option<AutoPair>) {
+        self.auto_pair = auto_pair;
+    }
+
+    /// Set the indenter for new lines and typed closing tokens, `None` disables indentation
+    pub(crate) fn set_indenter(&mut self, indenter: Option<Box<dyn Indenter>>) {
+        self.indenter = indenter;
+    }
+
+    /// Set the maximum number of entries kept in the kill ring
+    pub(crate) fn set_kill_ring_size(&mut self, size: usize) {
+        self.cut_buffer.set_capacity(size);
+    }
+
+    /// Set whether consecutive cuts are joined into one entry of the kill ring
+    pub(crate) fn set_join_kills(&mut self, join_kills: bool) {
+        self.join_kills = join_kills;
+    }
+
+    /// Content of the named register and how it is pasted
+    pub(crate) fn register(&mut self, name: char) -> Option<(String, ClipboardMode)> {
+        #[cfg(feature = "system_clipboard")]
+        if name == '+' {
+            return Some(self.system_clipboard.get());
+        }
+        self.registers.get(&name).cloned()
+    }
+
+    pub(crate) fn set_register(&mut self, name: char, content: &str, mode: ClipboardMode) {
+        #[cfg(feature = "system_clipboard")]
+        if name == '+' {
+            self.system_clipboard.set(content, mode);
+            return;
+        }
+        self.registers.insert(name, (content.to_owned(), mode));
+    }
+
+    pub(crate) fn macro_events(&self, name: char) -> Option<&[ReedlineRawEvent]> {
+        self.macros.get(&name).map(Vec::as_slice)
+    }
+
+    pub(crate) fn set_macro(&mut self, name: char, events: Vec<ReedlineRawEvent>) {
+        self.macros.insert(name, events);
+    }
+
     pub(crate) fn run_edit_command(&mut self, command: &EditCommand) {
+        if let EditCommand::SelectRegister(name) = command {
+            self.selected_register = Some(*name);
+            return;
+        }
+        if let Some(name) = self.selected_register.take() {
+            self.run_edit_command_with_register(command, name);
+            return;
+        }
+        // Catch up with edits made outside of edit commands
+        self.follow_edits();
+
+        let kill_direction = match command {
+            EditCommand::KillWholeLine => Some(self.whole_line_kill().1),
+            _ => kill_direction(command),
+        };
+        self.cut_buffer
+            .set_accumulate(if self.join_kills && self.last_edit_was_kill {
+                kill_direction
+            } else {
+                None
+            });
+        if !matches!(command, EditCommand::YankPop) {
+            self.last_yank = None;
+        }
+        if !matches!(
+            command,
+            EditCommand::OverwriteChar(_) | EditCommand::BackspaceOverwrite
+        ) {
+            self.overwritten.clear();
+        }
+
+        let deleted_char = match command {
+            EditCommand::Delete => self.line_buffer.grapheme_right().chars().next(),
+            EditCommand::Backspace => self.line_buffer.grapheme_left().chars().next(),
+            _ => None,
+        };
+        if self.secondary_cursors.is_empty() || !applies_at_every_cursor(command) {
+            if !is_secondary_cursor_command(command) {
+                self.secondary_cursors.clear();
+            }
+            self.apply_edit_command(command);
+        } else {
+            self.apply_at_every_cursor(command);
+        }
+        self.follow_edits();
+        self.last_edit_was_kill = kill_direction.is_some();
+        self.search_highlight = matches!(
+            command,
+            EditCommand::SearchBuffer { .. } | EditCommand::RepeatBufferSearch { .. }
+        );
+
+        let new_undo_behavior = match (command, command.edit_type()) {
+            (_, EditType::MoveCursor { .. }) => UndoBehavior::MoveCursor,
+            (EditCommand::InsertChar(c) | EditCommand::OverwriteChar(c), EditType::EditText) => {
+                UndoBehavior::InsertCharacter(*c)
+            }
+            (EditCommand::Delete, EditType::EditText) => UndoBehavior::Delete(deleted_char),
+            (EditCommand::Backspace, EditType::EditText) => UndoBehavior::Backspace(deleted_char),
+            (_, EditType::UndoRedo) => UndoBehavior::UndoRedo,
+            (_, _) => UndoBehavior::CreateUndoPoint,
+        };
+
+        self.update_undo_state(new_undo_behavior);
+    }
+
+    /// Run the command with the register standing in for the cut buffer
+    fn run_edit_command_with_register(&mut self, command: &EditCommand, name: char) {
+        let previous = self.register(name);
+        let mut register = LocalClipboard::with_capacity(1);
+        if let Some((content, mode)) = &previous {
+            register.set(content, *mode);
+        }
+
+        std::mem::swap(&mut self.cut_buffer, &mut register);
+        self.last_edit_was_kill = false;
+        self.run_edit_command(command);
+        std::mem::swap(&mut self.cut_buffer, &mut register);
+
+        let (content, mode) = register.get();
+        let changed = match &previous {
+            Some((previous, _)) => *previous != content,
+            None => !content.is_empty(),
+        };
+        if changed {
+            self.set_register(name, &content, mode);
+        }
+    }
+
+    fn apply_edit_command(&mut self, command: &EditCommand) {
         match command {
             EditCommand::MoveToStart { select } => self.move_to_start(*select),
             EditCommand::MoveToLineStart { select } => self.move_to_line_start(*select),
@@ -57,6 +329,14 @@ impl Editor {
             }
             EditCommand::MoveLeft { select } => self.move_left(*select),
             EditCommand::MoveRight { select } => self.move_right(*select),
+            EditCommand::MoveLineUp { select } => {
+                self.update_selection_anchor(*select);
+                self.line_buffer.move_line_up();
+            }
+            EditCommand::MoveLineDown { select } => {
+                self.update_selection_anchor(*select);
+                self.line_buffer.move_line_down();
+            }
             EditCommand::MoveWordLeft { select } => self.move_word_left(*select),
             EditCommand::MoveBigWordLeft { select } => self.move_big_word_left(*select),
             EditCommand::MoveWordRight { select } => self.move_word_right(*select),
@@ -72,6 +352,8 @@ impl Editor {
             EditCommand::InsertNewline => self.insert_newline(),
             EditCommand::ReplaceChar(chr) => self.replace_char(*chr),
             EditCommand::ReplaceChars(n_chars, str) => self.replace_chars(*n_chars, str),
+            EditCommand::OverwriteChar(c) => self.overwrite_char(*c),
+            EditCommand::BackspaceOverwrite => self.backspace_overwrite(),
             EditCommand::Backspace => self.backspace(),
             EditCommand::Delete => self.delete(),
             EditCommand::CutChar => self.cut_char(),
@@ -80,6 +362,7 @@ impl Editor {
             EditCommand::Clear => self.line_buffer.clear(),
             EditCommand::ClearToLineEnd => self.line_buffer.clear_to_line_end(),
             EditCommand::CutCurrentLine => self.cut_current_line(),
+            EditCommand::KillWholeLine => self.kill_whole_line(),
             EditCommand::CutFromStart => self.cut_from_start(),
             EditCommand::CutFromLineStart => self.cut_from_line_start(),
             EditCommand::CutToEnd => self.cut_from_end(),
@@ -92,14 +375,80 @@ impl Editor {
             EditCommand::CutBigWordRightToNext => self.cut_big_word_right_to_next(),
             EditCommand::PasteCutBufferBefore => self.insert_cut_buffer_before(),
             EditCommand::PasteCutBufferAfter => self.insert_cut_buffer_after(),
+            EditCommand::YankPop => self.yank_pop(),
             EditCommand::UppercaseWord => self.line_buffer.uppercase_word(),
             EditCommand::LowercaseWord => self.line_buffer.lowercase_word(),
             EditCommand::SwitchcaseChar => self.line_buffer.switchcase_char(),
+            EditCommand::UppercaseSelection => self.transform_selection(str::to_uppercase),
+            EditCommand::LowercaseSelection => self.transform_selection(str::to_lowercase),
+            EditCommand::SwitchcaseSelection => self.transform_selection(switch_case),
+            EditCommand::ReplaceSelectedChars(c) => self.transform_selection(|text| {
+                text.graphemes(true)
+                    .map(|grapheme| match grapheme {
+                        "\n" | "\r\n" => grapheme.to_string(),
+                        _ => c.to_string(),
+                    })
+                    .collect()
+            }),
+            EditCommand::JoinLines => self.join_lines(),
+            EditCommand::Substitute {
+                pattern,
+                replacement,
+                all_lines,
+                global,
+            } => self.substitute(pattern, replacement, *all_lines, *global),
             EditCommand::CapitalizeChar => self.line_buffer.capitalize_char(),
+            EditCommand::CapitalizeWord => self.line_buffer.capitalize_word(),
             EditCommand::SwapWords => self.line_buffer.swap_words(),
             EditCommand::SwapGraphemes => self.line_buffer.swap_graphemes(),
+            EditCommand::TransposeLines => self.line_buffer.transpose_lines(),
+            EditCommand::DeleteHorizontalSpace => self.line_buffer.delete_horizontal_space(),
+            EditCommand::JustOneSpace => self.line_buffer.just_one_space(),
+            EditCommand::DeleteBlankLines => self.line_buffer.delete_blank_lines(),
+            EditCommand::MoveToMatchingBracket { select } => self.move_to_matching_bracket(*select),
+            EditCommand::CutToMatchingBracket => self.cut_to_matching_bracket(),
+            EditCommand::SetMark(name) => {
+                self.marks.insert(*name, self.insertion_point());
+            }
+            EditCommand::MoveToMark { name, line, select } => {
+                self.move_to_mark(*name, *line, *select)
+            }
+            EditCommand::MoveToOlderJump => self.move_to_older_jump(),
+            EditCommand::MoveToNewerJump => self.move_to_newer_jump(),
+            EditCommand::SearchBuffer {
+                pattern,
+                backward,
+                select,
+            } => self.search_buffer(pattern, *backward, *select),
+            EditCommand::RepeatBufferSearch { reverse, select } => {
+                if let Some((pattern, backward)) = self.last_search.clone() {
+                    self.move_to_search_match(&pattern, backward != *reverse, *select);
+                }
+            }
+            EditCommand::CutTextObject(text_object) => self.cut_text_object(*text_object),
+            EditCommand::CopyTextObject(text_object) => {
+                self.copy_text_object(*text_object);
+            }
+            EditCommand::SelectTextObject(text_object) => self.select_text_object(*text_object),
+            EditCommand::SurroundSelection { left, right } => {
+                self.surround_selection(*left, *right)
+            }
+            EditCommand::DeleteSurrounding(kind) => self.change_surrounding(*kind, None),
+            EditCommand::ChangeSurrounding { kind, left, right } => {
+                self.change_surrounding(*kind, Some((*left, *right)))
+            }
+            EditCommand::Indent => self.change_indentation(false),
+            EditCommand::Dedent => self.change_indentation(true),
             EditCommand::Undo => self.undo(),
             EditCommand::Redo => self.redo(),
+            EditCommand::UndoEarlier => self.undo_earlier(),
+            EditCommand::UndoLater => self.undo_later(),
+            EditCommand::UndoEarlierBySeconds(seconds) => {
+                self.undo_earlier_by(Duration::from_secs(*seconds))
+            }
+            EditCommand::UndoLaterBySeconds(seconds) => {
+                self.undo_later_by(Duration::from_secs(*seconds))
+            }
             EditCommand::CutRightUntil(c) => self.cut_right_until_char(*c, false, true),
             EditCommand::CutRightBefore(c) => self.cut_right_until_char(*c, true, true),
             EditCommand::MoveRightUntil { c, select } => {
@@ -117,8 +466,18 @@ impl Editor {
                 self.move_left_until_char(*c, true, true, *select)
             }
             EditCommand::SelectAll => self.select_all(),
+            EditCommand::ToggleBlockSelection => self.toggle_block_selection(),
+            EditCommand::ToggleLineSelection => self.toggle_line_selection(),
+            EditCommand::SwapSelectionEnds => self.swap_selection_ends(),
+            EditCommand::AddCursorAbove => self.add_cursor_vertically(true),
+            EditCommand::AddCursorBelow => self.add_cursor_vertically(false),
+            EditCommand::AddCursorAtNextMatch => self.add_cursor_at_next_match(),
+            EditCommand::ClearSecondaryCursors => self.secondary_cursors.clear(),
             EditCommand::CutSelection => self.cut_selection_to_cut_buffer(),
             EditCommand::CopySelection => self.copy_selection_to_cut_buffer(),
+            EditCommand::CopySelectionAndCollapse => self.copy_selection_and_collapse(),
+            EditCommand::CopyCurrentLine => self.copy_current_line(),
+            EditCommand::SelectRegister(_) => {}
             EditCommand::Paste => self.paste_cut_buffer(),
             #[cfg(feature = "system_clipboard")]
             EditCommand::CutSelectionSystem => self.cut_selection_to_system(),
@@ -130,25 +489,176 @@ impl Editor {
         if !matches!(command.edit_type(), EditType::MoveCursor { select: true }) {
             self.selection_anchor = None;
         }
-        if let EditType::MoveCursor { select: true } = command.edit_type() {}
+        if self.selection_anchor.is_none() {
+            self.block_selection = false;
+            self.line_selection = false;
+        }
+    }
 
-        let new_undo_behavior = match (command, command.edit_type()) {
-            (_, EditType::MoveCursor { .. }) => UndoBehavior::MoveCursor,
-            (EditCommand::InsertChar(c), EditType::EditText) => UndoBehavior::InsertCharacter(*c),
-            (EditCommand::Delete, EditType::EditText) => {
-                let deleted_char = self.edit_stack.current().grapheme_right().chars().next();
-                UndoBehavior::Delete(deleted_char)
+    /// Run the command at the primary and every secondary cursor
+    ///
+    /// Cursors are visited from the end of the buffer, so an edit only moves the cursors
+    /// that were already visited and those just need to be shifted by the change in length.
+    fn apply_at_every_cursor(&mut self, command: &EditCommand) {
+        let mut cursors = std::mem::take(&mut self.secondary_cursors);
+        cursors.push(SecondaryCursor {
+            insertion_point: self.insertion_point(),
+            selection_anchor: self.selection_anchor,
+        });
+        let primary = cursors.len() - 1;
+
+        if matches!(
+            command,
+            EditCommand::CutSelection | EditCommand::CopySelection
+        ) {
+            let mut selections: Vec<_> = cursors.iter().filter_map(|c| c.selection()).collect();
+            selections.sort_unstable();
+            let selected = selections
+                .into_iter()
+                .map(|(start, end)| &self.line_buffer.get_buffer()[start..end])
+                .collect::<Vec<_>>()
+                .join("\n");
+            if !selected.is_empty() {
+                self.cut_buffer.set(&selected, ClipboardMode::Normal);
             }
-            (EditCommand::Backspace, EditType::EditText) => {
-                let deleted_char = self.edit_stack.current().grapheme_left().chars().next();
-                UndoBehavior::Backspace(deleted_char)
+        }
+
+        let mut order: Vec<usize> = (0..cursors.len()).collect();
+        order.sort_by_key(|&index| std::cmp::Reverse(cursors[index].start()));
+        for (visited, &index) in order.iter().enumerate() {
+            self.line_buffer
+                .set_insertion_point(cursors[index].insertion_point);
+            self.selection_anchor = cursors[index].selection_anchor;
+            let previous_len = self.line_buffer.len() as isize;
+            match command {
+                EditCommand::CutSelection => self.delete_cut_selection(),
+                EditCommand::CopySelection => self.selection_anchor = None,
+                _ => self.apply_edit_command(command),
+            }
+            let delta = self.line_buffer.len() as isize - previous_len;
+            for &moved in &order[..visited] {
+                cursors[moved].shift(delta);
+            }
+            cursors[index] = SecondaryCursor {
+                insertion_point: self.insertion_point(),
+                selection_anchor: self.selection_anchor,
+            };
+        }
+
+        let primary = cursors.remove(primary);
+        self.line_buffer
+            .set_insertion_point(primary.insertion_point);
+        self.selection_anchor = primary.selection_anchor;
+        self.set_secondary_cursors(cursors);
+    }
+
+    /// Keep the secondary cursors sorted, inside the buffer and apart from the primary cursor
+    fn set_secondary_cursors(&mut self, mut cursors: Vec<SecondaryCursor>) {
+        let len = self.line_buffer.len();
+        for cursor in &mut cursors {
+            cursor.insertion_point = cursor.insertion_point.min(len);
+            cursor.selection_anchor = cursor.selection_anchor.map(|anchor| anchor.min(len));
+        }
+        let primary = self.insertion_point();
+        cursors.retain(|cursor| cursor.insertion_point != primary);
+        cursors.sort_by_key(|cursor| cursor.insertion_point);
+        cursors.dedup_by_key(|cursor| cursor.insertion_point);
+        self.secondary_cursors = cursors;
+    }
+
+    /// Cursors besides the primary cursor of the [`LineBuffer`]
+    pub(crate) fn secondary_cursors(&self) -> &[SecondaryCursor] {
+        &self.secondary_cursors
+    }
+
+    /// Drop all secondary cursors, leaving only the primary cursor
+    pub(crate) fn clear_secondary_cursors(&mut self) {
+        self.secondary_cursors.clear();
+    }
+
+    /// Add a cursor on the line above the topmost or below the bottommost cursor
+    fn add_cursor_vertically(&mut self, up: bool) {
+        let primary = self.insertion_point();
+        let positions = self
+            .secondary_cursors
+            .iter()
+            .map(|cursor| cursor.insertion_point)
+            .chain(std::iter::once(primary));
+        let edge = if up { positions.min() } else { positions.max() };
+
+        self.line_buffer
+            .set_insertion_point(edge.unwrap_or(primary));
+        let at_edge = if up {
+            self.line_buffer.is_cursor_at_first_line()
+        } else {
+            self.line_buffer.is_cursor_at_last_line()
+        };
+        if !at_edge {
+            if up {
+                self.line_buffer.move_line_up();
+            } else {
+                self.line_buffer.move_line_down();
+            }
+            let mut cursors = self.secondary_cursors.clone();
+            cursors.push(SecondaryCursor {
+                insertion_point: self.insertion_point(),
+                selection_anchor: None,
+            });
+            self.line_buffer.set_insertion_point(primary);
+            self.set_secondary_cursors(cursors);
+        } else {
+            self.line_buffer.set_insertion_point(primary);
+        }
+    }
+
+    /// Add a cursor selecting the next occurrence of the selected text
+    ///
+    /// Without a selection the word under the cursor gets selected first.
+    fn add_cursor_at_next_match(&mut self) {
+        let (start, end) = match self.get_selection() {
+            Some(selection) => selection,
+            None => {
+                let word = self.line_buffer.current_word_range();
+                if !word.is_empty() {
+                    self.selection_anchor = Some(word.start);
+                    self.line_buffer.set_insertion_point(word.end);
+                }
+                return;
             }
-            (_, EditType::UndoRedo) => UndoBehavior::UndoRedo,
-            (_, _) => UndoBehavior::CreateUndoPoint,
         };
+        if start == end {
+            return;
+        }
 
-        self.update_undo_state(new_undo_behavior);
+        let buffer = self.line_buffer.get_buffer();
+        let needle = &buffer[start..end];
+        let search_from = self
+            .secondary_cursors
+            .iter()
+            .map(SecondaryCursor::end)
+            .fold(end, usize::max);
+        let found = buffer[search_from..]
+            .find(needle)
+            .map(|offset| offset + search_from)
+            .or_else(|| buffer.find(needle));
+
+        if let Some(match_start) = found {
+            let taken = match_start == start
+                || self
+                    .secondary_cursors
+                    .iter()
+                    .any(|cursor| cursor.start() == match_start);
+            if !taken {
+                let mut cursors = self.secondary_cursors.clone();
+                cursors.push(SecondaryCursor {
+                    insertion_point: match_start + needle.len(),
+                    selection_anchor: Some(match_start),
+                });
+                self.set_secondary_cursors(cursors);
+            }
+        }
     }
+
     fn update_selection_anchor(&mut self, select: bool) {
         self.selection_anchor = if select {
             self.selection_anchor
@@ -157,17 +667,26 @@ impl Editor {
             None
         };
     }
+    fn move_to_matching_bracket(&mut self, select: bool) {
+        if let Some(index) = self.line_buffer.matching_bracket_index() {
+            self.push_jump();
+            self.move_to_position(index, select);
+        }
+    }
+
     fn move_to_position(&mut self, position: usize, select: bool) {
         self.update_selection_anchor(select);
         self.line_buffer.set_insertion_point(position)
     }
 
     pub(crate) fn move_line_up(&mut self) {
+        self.secondary_cursors.clear();
         self.line_buffer.move_line_up();
         self.update_undo_state(UndoBehavior::MoveCursor);
     }
 
     pub(crate) fn move_line_down(&mut self) {
+        self.secondary_cursors.clear();
         self.line_buffer.move_line_down();
         self.update_undo_state(UndoBehavior::MoveCursor);
     }
@@ -178,18 +697,22 @@ impl Editor {
     }
 
     /// Edit the [`LineBuffer`] in an undo-safe manner.
+    ///
+    /// The resulting buffer is recorded in the undo tree according to `undo_behavior`.
     pub fn edit_buffer<F>(&mut self, func: F, undo_behavior: UndoBehavior)
     where
         F: FnOnce(&mut LineBuffer),
     {
-        self.update_undo_state(undo_behavior);
         func(&mut self.line_buffer);
+        self.secondary_cursors.clear();
+        self.update_undo_state(undo_behavior);
     }
 
     /// Set the text of the current [`LineBuffer`] given the specified [`UndoBehavior`]
     /// Insertion point update to the end of the buffer.
     pub(crate) fn set_buffer(&mut self, buffer: String, undo_behavior: UndoBehavior) {
         self.line_buffer.set_buffer(buffer);
+        self.secondary_cursors.clear();
         self.update_undo_state(undo_behavior);
     }
 
@@ -214,7 +737,8 @@ impl Editor {
     }
 
     pub(crate) fn reset_undo_stack(&mut self) {
-        self.edit_stack.reset();
+        self.undo_tree.reset();
+        self.line_buffer.take_change();
     }
 
     pub(crate) fn move_to_start(&mut self, select: bool) {
@@ -238,13 +762,71 @@ impl Editor {
     }
 
     fn undo(&mut self) {
-        let val = self.edit_stack.undo();
-        self.line_buffer = val.clone();
+        let from = self.undo_tree.current_id();
+        self.undo_tree.undo();
+        self.restore_undo_state(from);
     }
 
     fn redo(&mut self) {
-        let val = self.edit_stack.redo();
-        self.line_buffer = val.clone();
+        let from = self.undo_tree.current_id();
+        self.undo_tree.redo();
+        self.restore_undo_state(from);
+    }
+
+    fn undo_earlier(&mut self) {
+        let from = self.undo_tree.current_id();
+        self.undo_tree.earlier();
+        self.restore_undo_state(from);
+    }
+
+    fn undo_later(&mut self) {
+        let from = self.undo_tree.current_id();
+        self.undo_tree.later();
+        self.restore_undo_state(from);
+    }
+
+    fn undo_earlier_by(&mut self, duration: Duration) {
+        let from = self.undo_tree.current_id();
+        self.undo_tree.earlier_by(duration);
+        self.restore_undo_state(from);
+    }
+
+    fn undo_later_by(&mut self, duration: Duration) {
+        let from = self.undo_tree.current_id();
+        self.undo_tree.later_by(duration);
+        self.restore_undo_state(from);
+    }
+
+    /// Bring the text from the undo state `from` to the current one of the undo tree
+    fn restore_undo_state(&mut self, from: UndoStateId) {
+        // Edits not recorded yet are dropped along with the state they were made in
+        if let Some((range, removed)) = self.line_buffer.take_change() {
+            self.line_buffer.replace_range(range, &removed);
+        }
+        let (up, down) = self.undo_tree.path(from, self.undo_tree.current_id());
+        for id in up {
+            if let Some(node) = self.undo_tree.node(id) {
+                let step = node.value();
+                let range = step.start()..step.start() + step.inserted().len();
+                self.line_buffer.replace_range(range, step.removed());
+            }
+        }
+        for id in down {
+            if let Some(node) = self.undo_tree.node(id) {
+                let step = node.value();
+                let range = step.start()..step.start() + step.removed().len();
+                self.line_buffer.replace_range(range, step.inserted());
+            }
+        }
+        self.line_buffer
+            .set_insertion_point(self.undo_tree.current().insertion_point());
+        self.line_buffer.take_change();
+        self.follow_edits();
+    }
+
+    /// Get the tree of undo states recorded for the current [`LineBuffer`]
+    pub fn undo_tree(&self) -> &UndoTree<UndoStep> {
+        &self.undo_tree
     }
 
     pub(crate) fn update_undo_state(&mut self, undo_behavior: UndoBehavior) {
@@ -252,10 +834,29 @@ impl Editor {
             self.last_undo_behavior = UndoBehavior::UndoRedo;
             return;
         }
-        if !undo_behavior.create_undo_point_after(&self.last_undo_behavior) {
-            self.edit_stack.undo();
+        let change = self.line_buffer.take_change();
+        let insertion_point = self.line_buffer.insertion_point();
+        // The steps of the states derived from the current one start from its text
+        if undo_behavior.create_undo_point_after(&self.last_undo_behavior)
+            || (change.is_some() && self.undo_tree.current_has_children())
+        {
+            let step = match change {
+                Some((range, removed)) => {
+                    let inserted = self.line_buffer.get_buffer()[range.clone()].to_owned();
+                    UndoStep::new(range.start, removed, inserted, insertion_point)
+                }
+                None => UndoStep::new(0, String::new(), String::new(), insertion_point),
+            };
+            self.undo_tree.insert(step);
+        } else {
+            let is_initial_state = self.undo_tree.current_id() == 0;
+            let step = self.undo_tree.amend();
+            // The initial state has no parent to step from
+            if let (Some((range, removed)), false) = (change, is_initial_state) {
+                step.compose(self.line_buffer.get_buffer(), range, &removed);
+            }
+            step.set_insertion_point(insertion_point);
         }
-        self.edit_stack.insert(self.line_buffer.clone());
         self.last_undo_behavior = undo_behavior;
     }
 
@@ -270,6 +871,46 @@ impl Editor {
         }
     }
 
+    /// The range killed by [`EditCommand::KillWholeLine`] and the direction it kills in
+    ///
+    /// The last line takes the line ending in front of it along, leaving no empty line, and
+    /// so kills backward.
+    fn whole_line_kill(&self) -> (Range<usize>, KillDirection) {
+        let mut range = self.line_buffer.current_line_range();
+        let buffer = self.line_buffer.get_buffer();
+        if range.start > 0 && !buffer[range.clone()].ends_with('\n') {
+            range.start -= if buffer[..range.start].ends_with("\r\n") {
+                2
+            } else {
+                1
+            };
+            (range, KillDirection::Backward)
+        } else {
+            (range, KillDirection::Forward)
+        }
+    }
+
+    fn kill_whole_line(&mut self) {
+        let (range, _) = self.whole_line_kill();
+        let buffer = self.line_buffer.get_buffer();
+
+        if !range.is_empty() {
+            self.cut_buffer
+                .set(&buffer[range.clone()], ClipboardMode::Normal);
+            self.line_buffer.clear_range(range.clone());
+            self.line_buffer.set_insertion_point(range.start);
+            self.line_buffer.move_to_line_start();
+        }
+    }
+
+    fn copy_current_line(&mut self) {
+        let range = self.line_buffer.current_line_range();
+        let line = &self.line_buffer.get_buffer()[range];
+        if !line.is_empty() {
+            self.cut_buffer.set(line, ClipboardMode::Lines);
+        }
+    }
+
     fn cut_from_start(&mut self) {
         let insertion_offset = self.line_buffer.insertion_point();
         if insertion_offset > 0 {
@@ -404,7 +1045,30 @@ impl Editor {
 
     fn insert_cut_buffer_before(&mut self) {
         self.delete_selection();
-        insert_clipboard_content_before(&mut self.line_buffer, self.cut_buffer.deref_mut())
+        let previous_len = self.line_buffer.len();
+        let is_block = matches!(self.cut_buffer.get().1, ClipboardMode::Block);
+        insert_clipboard_content_before(&mut self.line_buffer, &mut self.cut_buffer);
+        // A block is spread over several lines and can't be replaced by `YankPop`
+        if !is_block {
+            let yank_end = self.line_buffer.insertion_point();
+            let yank_start = yank_end - (self.line_buffer.len() - previous_len);
+            self.last_yank = Some(yank_start..yank_end);
+        }
+    }
+
+    /// Replace the text inserted by the directly preceding yank with the next older kill ring entry
+    fn yank_pop(&mut self) {
+        if let Some(yanked) = self.last_yank.take() {
+            if let Some((mut content, mode)) = self.cut_buffer.rotate() {
+                if matches!(mode, ClipboardMode::Lines) && !content.ends_with('\n') {
+                    content.push('\n');
+                }
+                let yank_end = yanked.start + content.len();
+                self.line_buffer.replace_range(yanked.clone(), &content);
+                self.line_buffer.set_insertion_point(yank_end);
+                self.last_yank = Some(yanked.start..yank_end);
+            }
+        }
     }
 
     fn insert_cut_buffer_after(&mut self) {
@@ -414,6 +1078,10 @@ impl Editor {
                 self.line_buffer.move_right();
                 self.line_buffer.insert_str(&content);
             }
+            (content, ClipboardMode::Block) => {
+                self.line_buffer.move_right();
+                insert_block(&mut self.line_buffer, &content);
+            }
             (mut content, ClipboardMode::Lines) => {
                 // TODO: Simplify that?
                 self.line_buffer.move_to_line_start();
@@ -457,6 +1125,88 @@ impl Editor {
         }
     }
 
+    fn cut_to_matching_bracket(&mut self) {
+        if let Some(index) = self.line_buffer.matching_bracket_index() {
+            let insertion_point = self.line_buffer.insertion_point();
+            let range = if index > insertion_point {
+                insertion_point..index + 1
+            } else {
+                index..self.line_buffer.grapheme_right_index()
+            };
+            self.cut_buffer.set(
+                &self.line_buffer.get_buffer()[range.clone()],
+                ClipboardMode::Normal,
+            );
+            self.line_buffer.clear_range(range.clone());
+            self.line_buffer.set_insertion_point(range.start);
+        }
+    }
+
+    fn cut_text_object(&mut self, text_object: TextObject) {
+        if let Some(range) = self.copy_text_object(text_object) {
+            self.line_buffer.clear_range(range);
+        }
+    }
+
+    /// Copy the text object into the cut buffer and move to its start, returns its range
+    fn copy_text_object(&mut self, text_object: TextObject) -> Option<Range<usize>> {
+        let range = self.line_buffer.text_object_range(text_object)?;
+        let mode = if text_object.kind == TextObjectKind::Paragraph {
+            ClipboardMode::Lines
+        } else {
+            ClipboardMode::Normal
+        };
+        self.cut_buffer
+            .set(&self.line_buffer.get_buffer()[range.clone()], mode);
+        self.line_buffer.set_insertion_point(range.start);
+        Some(range)
+    }
+
+    fn select_text_object(&mut self, text_object: TextObject) {
+        if let Some(range) = self.line_buffer.text_object_range(text_object) {
+            self.selection_anchor = Some(range.start);
+            self.line_buffer.set_insertion_point(range.end);
+        }
+    }
+
+    /// Surround the selection, keeping the last line ending of a line selection outside
+    fn surround_selection(&mut self, left: char, right: char) {
+        if let Some((start, mut end)) = self.get_selection() {
+            if self.line_selection && self.line_buffer.get_buffer()[start..end].ends_with('\n') {
+                end -= 1;
+            }
+            self.line_buffer
+                .replace_range(end..end, right.encode_utf8(&mut [0; 4]));
+            self.line_buffer
+                .replace_range(start..start, left.encode_utf8(&mut [0; 4]));
+            self.line_buffer.set_insertion_point(start);
+        }
+    }
+
+    /// Replace the quotes or brackets around the cursor with the pair, or delete them
+    /// without one, moving to the opening one
+    fn change_surrounding(&mut self, kind: TextObjectKind, pair: Option<(char, char)>) {
+        if !matches!(kind, TextObjectKind::Quote(_) | TextObjectKind::Bracket(_)) {
+            return;
+        }
+        let inner = self.line_buffer.text_object_range(TextObject {
+            scope: TextObjectScope::Inner,
+            kind,
+        });
+        // Quotes and brackets of the text objects are all one byte long
+        if let Some(inner) = inner {
+            let (left, right) = match pair {
+                Some((left, right)) => (left.to_string(), right.to_string()),
+                None => (String::new(), String::new()),
+            };
+            self.line_buffer
+                .replace_range(inner.end..inner.end + 1, &right);
+            self.line_buffer
+                .replace_range(inner.start - 1..inner.start, &left);
+            self.line_buffer.set_insertion_point(inner.start - 1);
+        }
+    }
+
     fn cut_right_until_char(&mut self, c: char, before_char: bool, current_line: bool) {
         if let Some(index) = self.line_buffer.find_char_right(c, current_line) {
             // Saving the section of the string that will be deleted to be
@@ -511,186 +1261,1530 @@ impl Editor {
         self.line_buffer.insert_str(string);
     }
 
-    fn move_left(&mut self, select: bool) {
-        self.update_selection_anchor(select);
-        self.line_buffer.move_left();
-    }
-
-    fn move_right(&mut self, select: bool) {
-        self.update_selection_anchor(select);
-        self.line_buffer.move_right();
+    /// Move the marks and the jump list along with the edits of the text since the last call
+    fn follow_edits(&mut self) {
+        for (range, len) in self.line_buffer.take_edits() {
+            self.follow_edit(range, len);
+        }
     }
 
-    fn select_all(&mut self) {
-        self.selection_anchor = Some(0);
-        self.line_buffer.move_to_end();
+    /// Move the marks and the jump list as the `range` was replaced by `len` bytes
+    fn follow_edit(&mut self, range: Range<usize>, len: usize) {
+        let follow = |position: &mut usize| {
+            if *position >= range.end {
+                *position = *position - range.end + range.start + len;
+            } else if *position > range.start {
+                *position = range.start;
+            }
+        };
+        self.marks.values_mut().for_each(follow);
+        self.jump_list.iter_mut().for_each(follow);
     }
 
-    #[cfg(feature = "system_clipboard")]
-    fn cut_selection_to_system(&mut self) {
-        if let Some((start, end)) = self.get_selection() {
-            let cut_slice = &self.line_buffer.get_buffer()[start..end];
-            self.system_clipboard.set(cut_slice, ClipboardMode::Normal);
-            self.line_buffer.clear_range_safe(start, end);
-            self.selection_anchor = None;
+    /// The position limited to the buffer, which may have changed without moving it, e.g. by
+    /// an undo
+    fn clamp_position(&self, position: usize) -> usize {
+        let buffer = self.line_buffer.get_buffer();
+        let mut position = position.min(buffer.len());
+        while !buffer.is_char_boundary(position) {
+            position -= 1;
         }
+        position
     }
 
-    fn cut_selection_to_cut_buffer(&mut self) {
-        if let Some((start, end)) = self.get_selection() {
-            let cut_slice = &self.line_buffer.get_buffer()[start..end];
-            self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
-            self.line_buffer.clear_range_safe(start, end);
-            self.selection_anchor = None;
+    fn move_to_mark(&mut self, name: char, line: bool, select: bool) {
+        if let Some(&position) = self.marks.get(&name) {
+            let mut position = self.clamp_position(position);
+            if line {
+                let buffer = self.line_buffer.get_buffer();
+                let line_start = line_start(buffer, position);
+                position = line_start + indentation_len(line_at(buffer, line_start));
+            }
+            self.push_jump();
+            self.move_to_position(position, select);
         }
     }
 
-    #[cfg(feature = "system_clipboard")]
-    fn copy_selection_to_system(&mut self) {
-        if let Some((start, end)) = self.get_selection() {
-            let cut_slice = &self.line_buffer.get_buffer()[start..end];
-            self.system_clipboard.set(cut_slice, ClipboardMode::Normal);
+    /// Remember the cursor position before a jump, dropping the newer jumps
+    fn push_jump(&mut self) {
+        let insertion_point = self.insertion_point();
+        self.jump_list.truncate(self.jump_index);
+        if self.jump_list.last() != Some(&insertion_point) {
+            self.jump_list.push(insertion_point);
+        }
+        if self.jump_list.len() > JUMP_LIST_SIZE {
+            self.jump_list.remove(0);
         }
+        self.jump_index = self.jump_list.len();
     }
 
-    fn copy_selection_to_cut_buffer(&mut self) {
-        if let Some((start, end)) = self.get_selection() {
-            let cut_slice = &self.line_buffer.get_buffer()[start..end];
-            self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+    fn move_to_older_jump(&mut self) {
+        let insertion_point = self.insertion_point();
+        if self.jump_index == self.jump_list.len() {
+            // Allows to come back with `MoveToNewerJump`
+            self.push_jump();
+        }
+        while self.jump_index > 0 {
+            self.jump_index -= 1;
+            let position = self.clamp_position(self.jump_list[self.jump_index]);
+            if position != insertion_point {
+                self.move_to_position(position, false);
+                return;
+            }
         }
     }
 
-    /// If a selection is active returns the selected range, otherwise None.
-    /// The range is guaranteed to be ascending.
-    pub fn get_selection(&self) -> Option<(usize, usize)> {
-        self.selection_anchor.map(|selection_anchor| {
-            if self.insertion_point() > selection_anchor {
-                (selection_anchor, self.insertion_point())
-            } else {
-                (self.insertion_point(), selection_anchor)
+    fn move_to_newer_jump(&mut self) {
+        let insertion_point = self.insertion_point();
+        while self.jump_index + 1 < self.jump_list.len() {
+            self.jump_index += 1;
+            let position = self.clamp_position(self.jump_list[self.jump_index]);
+            if position != insertion_point {
+                self.move_to_position(position, false);
+                return;
             }
-        })
+        }
     }
 
-    fn delete_selection(&mut self) {
-        if let Some((start, end)) = self.get_selection() {
-            self.line_buffer.clear_range_safe(start, end);
-            self.selection_anchor = None;
-        }
+    /// The pattern of the last buffer search while its matches should be highlighted
+    pub(crate) fn search_highlight(&self) -> Option<&str> {
+        self.last_search
+            .as_ref()
+            .filter(|_| self.search_highlight)
+            .map(|(pattern, _)| pattern.as_str())
     }
 
-    fn backspace(&mut self) {
-        if self.selection_anchor.is_some() {
-            self.delete_selection();
+    /// Search for `pattern`, or for the pattern of the last search if it is empty
+    fn search_buffer(&mut self, pattern: &str, backward: bool, select: bool) {
+        let pattern = if pattern.is_empty() {
+            match &self.last_search {
+                Some((pattern, _)) => pattern.clone(),
+                None => return,
+            }
         } else {
-            self.line_buffer.delete_left_grapheme();
-        }
+            pattern.to_string()
+        };
+        self.move_to_search_match(&pattern, backward, select);
+        self.last_search = Some((pattern, backward));
     }
 
-    fn delete(&mut self) {
-        if self.selection_anchor.is_some() {
-            self.delete_selection();
+    fn move_to_search_match(&mut self, pattern: &str, backward: bool, select: bool) {
+        if pattern.is_empty() {
+            return;
+        }
+        let insertion_point = self.insertion_point();
+        let mut matches = self
+            .line_buffer
+            .get_buffer()
+            .match_indices(pattern)
+            .map(|(index, _)| index);
+        let found = if backward {
+            let matches: Vec<usize> = matches.collect();
+            matches
+                .iter()
+                .rev()
+                .find(|index| **index < insertion_point)
+                .or_else(|| matches.last())
+                .copied()
         } else {
-            self.line_buffer.delete_right_grapheme();
+            let first = matches.next();
+            first
+                .into_iter()
+                .chain(matches)
+                .find(|index| *index > insertion_point)
+                .or(first)
+        };
+        if let Some(position) = found {
+            self.push_jump();
+            self.move_to_position(position, select);
         }
     }
 
-    fn move_word_left(&mut self, select: bool) {
+    fn overwrite_char(&mut self, c: char) {
+        let replaced = match self.line_buffer.grapheme_right() {
+            "" | "\n" | "\r\n" => None,
+            grapheme => Some(grapheme.to_string()),
+        };
+        if replaced.is_some() {
+            self.line_buffer.delete_right_grapheme();
+        }
+        self.line_buffer.insert_char(c);
+        self.overwritten.push(replaced);
+    }
+
+    fn backspace_overwrite(&mut self) {
+        match self.overwritten.pop() {
+            Some(Some(replaced)) => {
+                self.line_buffer.delete_left_grapheme();
+                self.line_buffer.insert_str(&replaced);
+                self.line_buffer.move_left();
+            }
+            Some(None) => self.line_buffer.delete_left_grapheme(),
+            None => self.line_buffer.move_left(),
+        }
+    }
+
+    fn move_left(&mut self, select: bool) {
+        self.update_selection_anchor(select);
+        self.line_buffer.move_left();
+    }
+
+    fn move_right(&mut self, select: bool) {
+        self.update_selection_anchor(select);
+        self.line_buffer.move_right();
+    }
+
+    fn select_all(&mut self) {
+        self.selection_anchor = Some(0);
+        self.line_buffer.move_to_end();
+    }
+
+    /// Switch between a block and a regular selection, starting a selection if there is none
+    fn toggle_block_selection(&mut self) {
+        if self.selection_anchor.is_none() {
+            self.selection_anchor = Some(self.insertion_point());
+            self.block_selection = true;
+        } else {
+            self.block_selection = !self.block_selection;
+        }
+        self.line_selection = false;
+        self.secondary_cursors.clear();
+    }
+
+    /// Switch between a line and a regular selection, starting a selection if there is none
+    fn toggle_line_selection(&mut self) {
+        if self.selection_anchor.is_none() {
+            self.selection_anchor = Some(self.insertion_point());
+            self.line_selection = true;
+        } else {
+            self.line_selection = !self.line_selection;
+        }
+        self.block_selection = false;
+        self.secondary_cursors.clear();
+    }
+
+    fn swap_selection_ends(&mut self) {
+        if let Some(anchor) = self.selection_anchor {
+            self.selection_anchor = Some(self.insertion_point());
+            self.line_buffer.set_insertion_point(anchor);
+        }
+    }
+
+    /// Replace the text of every selected range with `transform` of it and move to the
+    /// start of the selection
+    fn transform_selection(&mut self, transform: impl Fn(&str) -> String) {
+        let ranges = self
+            .get_block_selection()
+            .unwrap_or_else(|| self.get_selection().into_iter().collect());
+        for (start, end) in ranges.iter().rev() {
+            let text = transform(&self.line_buffer.get_buffer()[*start..*end]);
+            self.line_buffer.replace_range(*start..*end, &text);
+        }
+        if let Some((start, _)) = ranges.first() {
+            self.line_buffer.set_insertion_point(*start);
+        }
+    }
+
+    /// Replace the first or every occurrence of `pattern` in the lines, moving to the start
+    /// of the last line changed
+    fn substitute(&mut self, pattern: &str, replacement: &str, all_lines: bool, global: bool) {
+        if pattern.is_empty() {
+            return;
+        }
+        let buffer = self.line_buffer.get_buffer();
+        let (start, end) = if all_lines {
+            (0, buffer.len())
+        } else {
+            let start = line_start(buffer, self.insertion_point());
+            (start, start + line_at(buffer, start).len())
+        };
+        let mut matches = vec![];
+        let mut offset = start;
+        for line in buffer[start..end].split('\n') {
+            let line_matches = line.match_indices(pattern).map(|(i, _)| offset + i);
+            if global {
+                matches.extend(line_matches);
+            } else {
+                matches.extend(line_matches.take(1));
+            }
+            offset += line.len() + 1;
+        }
+
+        if let Some(&last) = matches.last() {
+            let last_line_start = line_start(buffer, last);
+            for position in matches.into_iter().rev() {
+                self.line_buffer
+                    .replace_range(position..position + pattern.len(), replacement);
+            }
+            self.line_buffer.set_insertion_point(last_line_start);
+        }
+    }
+
+    /// Join the lines touched by the selection, or the current line with the next one,
+    /// leaving the cursor at the last join
+    fn join_lines(&mut self) {
+        let insertion_point = self.insertion_point();
+        let (start, end) = self
+            .selection_anchor
+            .map_or((insertion_point, insertion_point), |anchor| {
+                (anchor.min(insertion_point), anchor.max(insertion_point))
+            });
+        let buffer = self.line_buffer.get_buffer();
+        let mut newlines: Vec<usize> = buffer[start..end]
+            .match_indices('\n')
+            .map(|(i, _)| start + i)
+            .collect();
+        if newlines.is_empty() {
+            match buffer[start..].find('\n') {
+                Some(newline) => newlines.push(start + newline),
+                None => return,
+            }
+        }
+
+        let mut removed = 0;
+        let mut cursor = insertion_point;
+        for newline in newlines {
+            let newline = newline - removed;
+            let buffer = self.line_buffer.get_buffer();
+            let line_end = if buffer[..newline].ends_with('\r') {
+                newline - 1
+            } else {
+                newline
+            };
+            let next_line = &buffer[newline + 1..];
+            let next_text = next_line.trim_start_matches([' ', '\t']);
+            let next_start = buffer.len() - next_text.len();
+            let separator = if next_text.starts_with(['\n', '\r'])
+                || next_text.is_empty()
+                || buffer[..line_end].ends_with([' ', '\t'])
+            {
+                ""
+            } else {
+                " "
+            };
+            removed += next_start - line_end - separator.len();
+            self.line_buffer
+                .replace_range(line_end..next_start, separator);
+            cursor = line_end;
+        }
+        self.line_buffer.set_insertion_point(cursor);
+    }
+
+    #[cfg(feature = "system_clipboard")]
+    fn cut_selection_to_system(&mut self) {
+        if let Some((content, mode)) = self.selected_text() {
+            self.system_clipboard.set(&content, mode);
+            self.delete_cut_selection();
+        }
+    }
+
+    fn cut_selection_to_cut_buffer(&mut self) {
+        if let Some((content, mode)) = self.selected_text() {
+            self.cut_buffer.set(&content, mode);
+            self.delete_cut_selection();
+        }
+    }
+
+    /// Delete the cut selection. A line selection reaching the end of the buffer takes the
+    /// line ending in front of it along like [`EditCommand::KillWholeLine`], leaving no
+    /// empty line
+    fn delete_cut_selection(&mut self) {
+        let buffer = self.line_buffer.get_buffer();
+        match self.get_selection() {
+            Some((start, end))
+                if self.line_selection
+                    && !self.block_selection
+                    && start > 0
+                    && end == buffer.len()
+                    && !buffer.ends_with('\n') =>
+            {
+                let start = start
+                    - if buffer[..start].ends_with("\r\n") {
+                        2
+                    } else {
+                        1
+                    };
+                self.line_buffer.clear_range(start..end);
+                self.line_buffer.set_insertion_point(start);
+                self.line_buffer.move_to_line_start();
+                self.selection_anchor = None;
+            }
+            _ => self.delete_selection(),
+        }
+    }
+
+    #[cfg(feature = "system_clipboard")]
+    fn copy_selection_to_system(&mut self) {
+        if let Some((content, mode)) = self.selected_text() {
+            self.system_clipboard.set(&content, mode);
+        }
+    }
+
+    fn copy_selection_to_cut_buffer(&mut self) {
+        if let Some((content, mode)) = self.selected_text() {
+            self.cut_buffer.set(&content, mode);
+        }
+    }
+
+    fn copy_selection_and_collapse(&mut self) {
+        if let Some((start, _)) = self.get_selection() {
+            self.copy_selection_to_cut_buffer();
+            self.line_buffer.set_insertion_point(start);
+        }
+    }
+
+    /// The selected text and how it has to be pasted again
+    fn selected_text(&self) -> Option<(String, ClipboardMode)> {
+        if let Some(ranges) = self.get_block_selection() {
+            let content = ranges
+                .iter()
+                .map(|(start, end)| &self.line_buffer.get_buffer()[*start..*end])
+                .collect::<Vec<_>>()
+                .join("\n");
+            Some((content, ClipboardMode::Block))
+        } else {
+            let mode = if self.line_selection {
+                ClipboardMode::Lines
+            } else {
+                ClipboardMode::Normal
+            };
+            self.get_selection()
+                .map(|(start, end)| (self.line_buffer.get_buffer()[start..end].to_string(), mode))
+        }
+    }
+
+    /// If a selection is active returns the selected range, otherwise None.
+    /// The range is guaranteed to be ascending.
+    ///
+    /// For a block selection this is the range from its first to its last selected line.
+    /// A line selection covers its lines including the line ending of the last one.
+    pub fn get_selection(&self) -> Option<(usize, usize)> {
+        self.selection_anchor.map(|selection_anchor| {
+            let (start, end) = if self.insertion_point() > selection_anchor {
+                (selection_anchor, self.insertion_point())
+            } else {
+                (self.insertion_point(), selection_anchor)
+            };
+            if self.line_selection {
+                let buffer = self.line_buffer.get_buffer();
+                let end = buffer[end..]
+                    .find('\n')
+                    .map_or(buffer.len(), |newline| end + newline + 1);
+                (line_start(buffer, start), end)
+            } else {
+                (start, end)
+            }
+        })
+    }
+
+    /// If a block selection is active returns the selected range of every line, otherwise None.
+    ///
+    /// The block spans the display columns between the selection anchor and the insertion
+    /// point. Wide graphemes that are only partially covered are included, lines ending left
+    /// of the block contribute an empty range at their end.
+    pub fn get_block_selection(&self) -> Option<Vec<(usize, usize)>> {
+        if !self.block_selection {
+            return None;
+        }
+        let (start, end) = self.get_selection()?;
+        let (left, right) = self.block_columns()?;
+        let buffer = self.line_buffer.get_buffer();
+
+        let mut ranges = Vec::new();
+        let mut line_start = line_start(buffer, start);
+        loop {
+            let line = line_at(buffer, line_start);
+            let from = line_start + offset_at_column(line, left, false);
+            let to = line_start + offset_at_column(line, right, true);
+            ranges.push((from, to.max(from)));
+
+            match buffer[line_start..].find('\n') {
+                Some(newline) if line_start + newline < end => line_start += newline + 1,
+                _ => break,
+            }
+        }
+        Some(ranges)
+    }
+
+    /// Display columns spanned by the block selection, the right one being exclusive
+    fn block_columns(&self) -> Option<(usize, usize)> {
+        let buffer = self.line_buffer.get_buffer();
+        let anchor_column = display_column(buffer, self.selection_anchor?);
+        let cursor_column = display_column(buffer, self.insertion_point());
+        Some((
+            anchor_column.min(cursor_column),
+            anchor_column.max(cursor_column),
+        ))
+    }
+
+    fn delete_selection(&mut self) {
+        if let Some(ranges) = self.get_block_selection() {
+            for (start, end) in ranges.iter().rev() {
+                self.line_buffer.clear_range(*start..*end);
+            }
+            self.line_buffer.set_insertion_point(ranges[0].0);
+            self.selection_anchor = None;
+            self.block_selection = false;
+        } else if let Some((start, end)) = self.get_selection() {
+            self.line_buffer.clear_range_safe(start, end);
+            self.selection_anchor = None;
+        }
+    }
+
+    /// Replace the text of a block selection on every line reaching the block
+    ///
+    /// Leaves a cursor behind the inserted text on each of these lines.
+    fn replace_block_selection(&mut self, text: &str) {
+        if let (Some(ranges), Some((left, _))) = (self.get_block_selection(), self.block_columns())
+        {
+            let buffer = self.line_buffer.get_buffer();
+            let mut removed = 0;
+            let mut cursors = Vec::new();
+            for (start, end) in &ranges {
+                let line = line_at(buffer, line_start(buffer, *start));
+                if start != end || line.width() >= left {
+                    cursors.push(SecondaryCursor {
+                        insertion_point: start - removed,
+                        selection_anchor: None,
+                    });
+                }
+                removed += end - start;
+            }
+            self.delete_selection();
+            if let Some(primary) = cursors.first() {
+                self.line_buffer
+                    .set_insertion_point(primary.insertion_point);
+                self.secondary_cursors = cursors[1..].to_vec();
+                self.apply_at_every_cursor(&EditCommand::InsertString(text.to_string()));
+            }
+        }
+    }
+
+    fn backspace(&mut self) {
+        if self.selection_anchor.is_some() {
+            self.delete_selection();
+        } else {
+            if let Some(auto_pair) = &self.auto_pair {
+                let previous = self.line_buffer.grapheme_left().chars().next_back();
+                let next = self.line_buffer.grapheme_right().chars().next();
+                if auto_pair.is_empty_pair(previous, next) {
+                    self.line_buffer.delete_right_grapheme();
+                }
+            }
+            self.line_buffer.delete_left_grapheme();
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.selection_anchor.is_some() {
+            self.delete_selection();
+        } else {
+            self.line_buffer.delete_right_grapheme();
+        }
+    }
+
+    fn move_word_left(&mut self, select: bool) {
         self.move_to_position(self.line_buffer.word_left_index(), select);
     }
 
-    fn move_big_word_left(&mut self, select: bool) {
-        self.move_to_position(self.line_buffer.big_word_left_index(), select);
+    fn move_big_word_left(&mut self, select: bool) {
+        self.move_to_position(self.line_buffer.big_word_left_index(), select);
+    }
+
+    fn move_word_right(&mut self, select: bool) {
+        self.move_to_position(self.line_buffer.word_right_index(), select);
+    }
+
+    fn move_word_right_start(&mut self, select: bool) {
+        self.move_to_position(self.line_buffer.word_right_start_index(), select);
+    }
+
+    fn move_big_word_right_start(&mut self, select: bool) {
+        self.move_to_position(self.line_buffer.big_word_right_start_index(), select);
+    }
+
+    fn move_word_right_end(&mut self, select: bool) {
+        self.move_to_position(self.line_buffer.word_right_end_index(), select);
+    }
+
+    fn move_big_word_right_end(&mut self, select: bool) {
+        self.move_to_position(self.line_buffer.big_word_right_end_index(), select);
+    }
+
+    fn insert_char(&mut self, c: char) {
+        if self.block_selection {
+            self.replace_block_selection(c.encode_utf8(&mut [0; 4]));
+            return;
+        }
+        self.delete_selection();
+        let next = self.line_buffer.grapheme_right().chars().next();
+        let previous = self.line_buffer.grapheme_left().chars().next_back();
+        if let Some(auto_pair) = &self.auto_pair {
+            if auto_pair.skips_over(c, next) {
+                self.line_buffer.move_right();
+                return;
+            }
+        }
+        let close = self
+            .auto_pair
+            .as_ref()
+            .and_then(|auto_pair| auto_pair.closer_for(c, previous, next));
+
+        self.line_buffer.insert_char(c);
+        if let Some(close) = close {
+            self.line_buffer.insert_char(close);
+            self.line_buffer.move_left();
+        }
+
+        let indentation = self.indenter.as_ref().and_then(|indenter| {
+            indenter.indent_after_typing(
+                self.line_buffer.get_buffer(),
+                self.line_buffer.insertion_point(),
+                c,
+            )
+        });
+        if let Some(indentation) = indentation {
+            let line_start = self.line_buffer.current_line_range().start;
+            let current = indentation_len(&self.line_buffer.get_buffer()[line_start..]);
+            self.replace_line_prefix(line_start, current, &indentation);
+        }
+    }
+
+    fn insert_str(&mut self, str: &str) {
+        if self.block_selection {
+            self.replace_block_selection(str);
+            return;
+        }
+        self.delete_selection();
+        self.line_buffer.insert_str(str);
+    }
+
+    fn insert_newline(&mut self) {
+        self.delete_selection();
+        let indenter = match &self.indenter {
+            Some(indenter) => indenter,
+            None => {
+                self.line_buffer.insert_newline();
+                return;
+            }
+        };
+
+        let indentation = indenter.indent_for_newline(
+            self.line_buffer.get_buffer(),
+            self.line_buffer.insertion_point(),
+        );
+        self.line_buffer.insert_newline();
+        self.line_buffer.insert_str(&indentation);
+
+        // Move a closing token right behind the cursor onto its own line, if the indenter
+        // dedents it
+        if let Some(next) = self.line_buffer.grapheme_right().chars().next() {
+            let insertion_point = self.line_buffer.insertion_point();
+            let closer_indentation = indenter.indent_after_typing(
+                self.line_buffer.get_buffer(),
+                insertion_point + next.len_utf8(),
+                next,
+            );
+            if let Some(closer_indentation) = closer_indentation {
+                if closer_indentation != indentation {
+                    self.line_buffer.insert_newline();
+                    self.line_buffer.insert_str(&closer_indentation);
+                    self.line_buffer.set_insertion_point(insertion_point);
+                }
+            }
+        }
+    }
+
+    /// Indent or dedent the current line or every line touched by the selection
+    fn change_indentation(&mut self, dedent: bool) {
+        let indent_unit = self.indenter.as_ref().map_or_else(
+            || DefaultIndenter.indent_unit().to_string(),
+            |indenter| indenter.indent_unit().to_string(),
+        );
+        let (start, end) = self.get_selection().unwrap_or_else(|| {
+            let insertion_point = self.insertion_point();
+            (insertion_point, insertion_point)
+        });
+
+        let buffer = self.line_buffer.get_buffer();
+        let mut line_starts = vec![buffer[..start].rfind('\n').map_or(0, |i| i + 1)];
+        line_starts.extend(
+            buffer[start..end]
+                .match_indices('\n')
+                .map(|(i, _)| start + i + 1)
+                // A selection ending at the start of a line does not cover that line
+                .filter(|line_start| *line_start < end),
+        );
+        let multiple_lines = line_starts.len() > 1;
+
+        for line_start in line_starts.into_iter().rev() {
+            let line = &self.line_buffer.get_buffer()[line_start..];
+            if dedent {
+                let removed = if line.starts_with(&indent_unit) {
+                    indent_unit.len()
+                } else if line.starts_with('\t') {
+                    1
+                } else {
+                    line.bytes()
+                        .take(indent_unit.len())
+                        .take_while(|b| *b == b' ')
+                        .count()
+                };
+                self.replace_line_prefix(line_start, removed, "");
+            } else if !(multiple_lines && matches!(line.bytes().next(), None | Some(b'\n' | b'\r')))
+            {
+                self.replace_line_prefix(line_start, 0, &indent_unit);
+            }
+        }
+    }
+
+    /// Replace the first `len` bytes of the line starting at `line_start`, keeping the cursor
+    /// and the selection anchor on the same text
+    fn replace_line_prefix(&mut self, line_start: usize, len: usize, replacement: &str) {
+        let adjust = |position: usize| {
+            if position >= line_start + len {
+                position - len + replacement.len()
+            } else if position > line_start {
+                line_start + replacement.len()
+            } else {
+                position
+            }
+        };
+        self.line_buffer
+            .replace_range(line_start..line_start + len, replacement);
+        self.line_buffer
+            .set_insertion_point(adjust(self.line_buffer.insertion_point()));
+        self.selection_anchor = self.selection_anchor.map(adjust);
+    }
+
+    #[cfg(feature = "system_clipboard")]
+    fn paste_from_system(&mut self) {
+        self.delete_selection();
+        insert_clipboard_content_before(&mut self.line_buffer, self.system_clipboard.deref_mut());
+    }
+
+    fn paste_cut_buffer(&mut self) {
+        self.delete_selection();
+        insert_clipboard_content_before(&mut self.line_buffer, &mut self.cut_buffer);
+    }
+}
+
+/// Commands that are run at every cursor while there are secondary cursors
+fn applies_at_every_cursor(command: &EditCommand) -> bool {
+    matches!(
+        command,
+        EditCommand::InsertChar(_)
+            | EditCommand::InsertString(_)
+            | EditCommand::InsertNewline
+            | EditCommand::Backspace
+            | EditCommand::Delete
+            | EditCommand::BackspaceWord
+            | EditCommand::DeleteWord
+            | EditCommand::MoveLeft { .. }
+            | EditCommand::MoveRight { .. }
+            | EditCommand::MoveWordLeft { .. }
+            | EditCommand::MoveBigWordLeft { .. }
+            | EditCommand::MoveWordRight { .. }
+            | EditCommand::MoveWordRightStart { .. }
+            | EditCommand::MoveBigWordRightStart { .. }
+            | EditCommand::MoveWordRightEnd { .. }
+            | EditCommand::MoveBigWordRightEnd { .. }
+            | EditCommand::MoveToLineStart { .. }
+            | EditCommand::MoveToLineEnd { .. }
+            | EditCommand::CutSelection
+            | EditCommand::CopySelection
+    )
+}
+
+/// Commands managing the secondary cursors, every other command drops them
+fn is_secondary_cursor_command(command: &EditCommand) -> bool {
+    matches!(
+        command,
+        EditCommand::AddCursorAbove
+            | EditCommand::AddCursorBelow
+            | EditCommand::AddCursorAtNextMatch
+    )
+}
+
+/// Commands that cut text and are joined in the kill ring when executed consecutively
+///
+/// [`EditCommand::KillWholeLine`] kills in either direction, see `Editor::whole_line_kill`.
+fn kill_direction(command: &EditCommand) -> Option<KillDirection> {
+    match command {
+        EditCommand::CutToEnd
+        | EditCommand::CutToLineEnd
+        | EditCommand::CutWordRight
+        | EditCommand::CutBigWordRight
+        | EditCommand::CutWordRightToNext
+        | EditCommand::CutBigWordRightToNext
+        | EditCommand::CutRightUntil(_)
+        | EditCommand::CutRightBefore(_) => Some(KillDirection::Forward),
+        EditCommand::CutFromStart
+        | EditCommand::CutFromLineStart
+        | EditCommand::CutWordLeft
+        | EditCommand::CutBigWordLeft
+        | EditCommand::CutLeftUntil(_)
+        | EditCommand::CutLeftBefore(_) => Some(KillDirection::Backward),
+        _ => None,
+    }
+}
+
+fn insert_clipboard_content_before(line_buffer: &mut LineBuffer, clipboard: &mut dyn Clipboard) {
+    match clipboard.get() {
+        (content, ClipboardMode::Normal) => {
+            line_buffer.insert_str(&content);
+        }
+        (content, ClipboardMode::Block) => insert_block(line_buffer, &content),
+        (mut content, ClipboardMode::Lines) => {
+            // TODO: Simplify that?
+            line_buffer.move_to_line_start();
+            line_buffer.move_line_up();
+            if !content.ends_with('\n') {
+                // TODO: Make sure platform requirements are met
+                content.push('\n');
+            }
+            line_buffer.insert_str(&content);
+        }
+    }
+}
+
+/// Insert every line of `content` at the column of the insertion point on consecutive lines
+///
+/// Lines are padded with spaces and added at the end of the buffer as necessary. The
+/// insertion point stays at the top left corner of the inserted block.
+fn insert_block(line_buffer: &mut LineBuffer, content: &str) {
+    let origin = line_buffer.insertion_point();
+    let column = display_column(line_buffer.get_buffer(), origin);
+    let mut current_line_start = line_start(line_buffer.get_buffer(), origin);
+    for (index, piece) in content.split('\n').enumerate() {
+        if index > 0 {
+            let buffer = line_buffer.get_buffer();
+            match buffer[current_line_start..].find('\n') {
+                Some(newline) => current_line_start += newline + 1,
+                None => {
+                    line_buffer.move_to_end();
+                    line_buffer.insert_newline();
+                    current_line_start = line_buffer.len();
+                }
+            }
+        }
+        let line = line_at(line_buffer.get_buffer(), current_line_start);
+        let offset = current_line_start + offset_at_column(line, column, false);
+        let padding = column.saturating_sub(line.width());
+        line_buffer.set_insertion_point(offset);
+        line_buffer.insert_str(&" ".repeat(padding));
+        line_buffer.insert_str(piece);
+    }
+    line_buffer.set_insertion_point(origin);
+}
+
+/// Offset of the start of the line containing `offset`
+fn line_start(buffer: &str, offset: usize) -> usize {
+    buffer[..offset]
+        .rfind('\n')
+        .map_or(0, |newline| newline + 1)
+}
+
+/// The range of `old` differing from `new` and the length of its replacement in `new`
+fn changed_range(old: &str, new: &str) -> (Range<usize>, usize) {
+    let mut prefix = old
+        .bytes()
+        .zip(new.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !old.is_char_boundary(prefix) {
+        prefix -= 1;
+    }
+    let mut suffix = old[prefix..]
+        .bytes()
+        .rev()
+        .zip(new[prefix..].bytes().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !old.is_char_boundary(old.len() - suffix) {
+        suffix -= 1;
+    }
+    (prefix..old.len() - suffix, new.len() - suffix - prefix)
+}
+
+/// The text with the case of every character switched
+fn switch_case(text: &str) -> String {
+    text.chars()
+        .flat_map(|c| {
+            if c.is_uppercase() {
+                c.to_lowercase().collect::<Vec<_>>()
+            } else {
+                c.to_uppercase().collect::<Vec<_>>()
+            }
+        })
+        .collect()
+}
+
+/// The line starting at `line_start` without its line ending
+fn line_at(buffer: &str, line_start: usize) -> &str {
+    let line = &buffer[line_start..];
+    let line = line.find('\n').map_or(line, |newline| &line[..newline]);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+/// Display column of `offset` in its line, accounting for wide graphemes
+fn display_column(buffer: &str, offset: usize) -> usize {
+    buffer[line_start(buffer, offset)..offset].width()
+}
+
+/// Offset in `line` of the grapheme covering display `column`, or the end of the line
+///
+/// With `after_partial` a grapheme only partially left of `column` is skipped as well.
+fn offset_at_column(line: &str, column: usize, after_partial: bool) -> usize {
+    let mut current_column = 0;
+    for (offset, grapheme) in line.grapheme_indices(true) {
+        let width = grapheme.width();
+        let covers = if after_partial {
+            current_column >= column
+        } else {
+            current_column + width > column
+        };
+        if covers {
+            return offset;
+        }
+        current_column += width;
+    }
+    line.len()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::TextObjectScope;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn editor_with(buffer: &str) -> Editor {
+        let mut editor = Editor::default();
+        editor.set_buffer(buffer.to_string(), UndoBehavior::CreateUndoPoint);
+        editor
+    }
+
+    #[rstest]
+    #[case("abc def ghi", 11, "abc def ")]
+    #[case("abc def-ghi", 11, "abc def-")]
+    #[case("abc def.ghi", 11, "abc ")]
+    fn test_cut_word_left(#[case] input: &str, #[case] position: usize, #[case] expected: &str) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.cut_word_left();
+
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    #[rstest]
+    #[case(WordDefinition::Unicode, "vim src/a.b", "vim src/")]
+    #[case(WordDefinition::Separators(".".into()), "vim src/a.b", "vim src/a.")]
+    #[case(WordDefinition::WordChars("/.".into()), "vim src/a.b", "vim ")]
+    fn cut_word_left_honors_word_definition(
+        #[case] word_definition: WordDefinition,
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        let mut editor = editor_with(input);
+        editor.set_word_definition(word_definition);
+
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    fn editor_with_auto_pair() -> Editor {
+        let mut editor = Editor::default();
+        editor.set_auto_pair(Some(AutoPair::default()));
+        editor
+    }
+
+    #[rstest]
+    #[case("f", 1, '(', "f()", 2)]
+    #[case("f(x)", 3, ')', "f(x)", 4)]
+    #[case("[]", 1, '{', "[{}]", 2)]
+    #[case("a b", 2, '(', "a (b", 3)]
+    #[case("don", 3, '\'', "don'", 4)]
+    #[case("say \"hi\"", 7, '"', "say \"hi\"", 8)]
+    #[case("say ", 4, '"', "say \"\"", 5)]
+    fn auto_pair_typing(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] typed: char,
+        #[case] expected: &str,
+        #[case] expected_insertion_point: usize,
+    ) {
+        let mut editor = editor_with(input);
+        editor.set_auto_pair(Some(AutoPair::default()));
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&EditCommand::InsertChar(typed));
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_insertion_point);
+    }
+
+    #[test]
+    fn auto_pair_backspace_deletes_empty_pair() {
+        let mut editor = editor_with_auto_pair();
+        editor.run_edit_command(&EditCommand::InsertChar('('));
+        editor.run_edit_command(&EditCommand::InsertChar('('));
+        editor.run_edit_command(&EditCommand::Backspace);
+        assert_eq!(editor.get_buffer(), "()");
+
+        editor.run_edit_command(&EditCommand::MoveToEnd { select: false });
+        editor.run_edit_command(&EditCommand::Backspace);
+        assert_eq!(editor.get_buffer(), "(");
+    }
+
+    #[test]
+    fn auto_pair_is_undone_with_the_opener() {
+        let mut editor = editor_with_auto_pair();
+        for c in "ls (".chars() {
+            editor.run_edit_command(&EditCommand::InsertChar(c));
+        }
+        assert_eq!(editor.get_buffer(), "ls ()");
+
+        // The closer belongs to the same undo step as the opener and the space before it
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "ls");
+    }
+
+    #[test]
+    fn auto_pair_ignores_inserted_strings() {
+        let mut editor = editor_with_auto_pair();
+        editor.run_edit_command(&EditCommand::InsertString("echo (".into()));
+        assert_eq!(editor.get_buffer(), "echo (");
+    }
+
+    fn editor_with_indenter(buffer: &str) -> Editor {
+        let mut editor = editor_with(buffer);
+        editor.set_indenter(Some(Box::new(DefaultIndenter)));
+        editor
+    }
+
+    #[test]
+    fn newline_keeps_indentation_and_dedents_closer() {
+        let mut editor = editor_with_indenter("fn {");
+        editor.run_edit_command(&EditCommand::InsertNewline);
+        editor.run_edit_command(&EditCommand::InsertString("a".into()));
+        editor.run_edit_command(&EditCommand::InsertNewline);
+        assert_eq!(editor.get_buffer(), "fn {\n    a\n    ");
+
+        editor.run_edit_command(&EditCommand::InsertChar('}'));
+        assert_eq!(editor.get_buffer(), "fn {\n    a\n}");
+        assert_eq!(editor.insertion_point(), editor.get_buffer().len());
+    }
+
+    #[test]
+    fn newline_between_pair_puts_closer_on_own_line() {
+        let mut editor = editor_with_indenter("f(");
+        editor.set_auto_pair(Some(AutoPair::default()));
+        editor.run_edit_command(&EditCommand::InsertChar('['));
+        editor.run_edit_command(&EditCommand::InsertNewline);
+
+        assert_eq!(editor.get_buffer(), "f([\n    \n]");
+        assert_eq!(editor.insertion_point(), 8);
+    }
+
+    #[rstest]
+    #[case("ab", 1, None, EditCommand::Indent, "    ab", 5)]
+    #[case("  ab", 4, None, EditCommand::Indent, "      ab", 8)]
+    #[case("      ab", 7, None, EditCommand::Dedent, "  ab", 3)]
+    #[case("  ab", 1, None, EditCommand::Dedent, "ab", 0)]
+    #[case("\tab", 3, None, EditCommand::Dedent, "ab", 2)]
+    #[case("a\n\nb\nc", 0, Some(5), EditCommand::Indent, "    a\n\n    b\nc", 4)]
+    #[case("    a\n  b\nc", 1, Some(9), EditCommand::Dedent, "a\nb\nc", 0)]
+    fn indent_and_dedent_lines(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] anchor: Option<usize>,
+        #[case] command: EditCommand,
+        #[case] expected: &str,
+        #[case] expected_insertion_point: usize,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+        editor.selection_anchor = anchor;
+
+        editor.run_edit_command(&command);
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_insertion_point);
+    }
+
+    #[rstest]
+    #[case("one two", 7, Some(4), EditCommand::UppercaseSelection, "one TWO", 4)]
+    #[case("ONE Two", 0, Some(5), EditCommand::LowercaseSelection, "one two", 0)]
+    #[case(
+        "aBc straße",
+        11,
+        Some(0),
+        EditCommand::SwitchcaseSelection,
+        "AbC STRASSE",
+        0
+    )]
+    #[case(
+        "ab\ncd",
+        4,
+        Some(1),
+        EditCommand::ReplaceSelectedChars('x'),
+        "ax\nxd",
+        1
+    )]
+    #[case("ab", 1, None, EditCommand::UppercaseSelection, "ab", 1)]
+    #[case("a\n  b\nc", 0, None, EditCommand::JoinLines, "a b\nc", 1)]
+    #[case("a\n  b\nc", 0, Some(6), EditCommand::JoinLines, "a b c", 3)]
+    #[case("a \n\nb", 0, None, EditCommand::JoinLines, "a \nb", 2)]
+    #[case("a\r\nb", 0, None, EditCommand::JoinLines, "a b", 1)]
+    #[case("a", 0, None, EditCommand::JoinLines, "a", 0)]
+    fn edit_selected_text(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] anchor: Option<usize>,
+        #[case] command: EditCommand,
+        #[case] expected: &str,
+        #[case] expected_insertion_point: usize,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+        editor.selection_anchor = anchor;
+
+        editor.run_edit_command(&command);
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_insertion_point);
+        assert_eq!(editor.get_selection(), None);
+    }
+
+    #[rstest]
+    #[case("a a\na a", 6, false, false, "a a\nb a", 4)]
+    #[case("a a\na a", 6, false, true, "a a\nb b", 4)]
+    #[case("a a\na a", 6, true, false, "b a\nb a", 4)]
+    #[case("a a\r\nx\r\n", 0, true, true, "b b\r\nx\r\n", 0)]
+    #[case("x y", 1, true, true, "x y", 1)]
+    fn test_substitute(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] all_lines: bool,
+        #[case] global: bool,
+        #[case] expected: &str,
+        #[case] expected_insertion_point: usize,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&EditCommand::Substitute {
+            pattern: "a".to_string(),
+            replacement: "b".to_string(),
+            all_lines,
+            global,
+        });
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_insertion_point);
+    }
+
+    #[test]
+    fn backspace_restores_overwritten_text() {
+        let mut editor = editor_with("ab\ncd");
+        editor.line_buffer.set_insertion_point(1);
+
+        for c in ['x', 'y', 'z'] {
+            editor.run_edit_command(&EditCommand::OverwriteChar(c));
+        }
+        assert_eq!(editor.get_buffer(), "axyz\ncd");
+
+        for _ in 0..3 {
+            editor.run_edit_command(&EditCommand::BackspaceOverwrite);
+        }
+        assert_eq!(editor.get_buffer(), "ab\ncd");
+        assert_eq!(editor.insertion_point(), 1);
+
+        // Text in front of the first overwritten character is only moved over
+        editor.run_edit_command(&EditCommand::BackspaceOverwrite);
+        assert_eq!(editor.get_buffer(), "ab\ncd");
+        assert_eq!(editor.insertion_point(), 0);
     }
 
-    fn move_word_right(&mut self, select: bool) {
-        self.move_to_position(self.line_buffer.word_right_index(), select);
+    #[test]
+    fn marks_follow_edits_and_undo() {
+        let mut editor = editor_with("one\ntwo");
+        editor.line_buffer.set_insertion_point(5);
+        editor.run_edit_command(&EditCommand::SetMark('a'));
+
+        editor.run_edit_command(&EditCommand::MoveToStart { select: false });
+        editor.run_edit_command(&EditCommand::InsertString("zero ".into()));
+        editor.run_edit_command(&EditCommand::MoveToMark {
+            name: 'a',
+            line: false,
+            select: false,
+        });
+        assert_eq!(editor.insertion_point(), 10);
+
+        editor.run_edit_command(&EditCommand::Undo);
+        editor.run_edit_command(&EditCommand::MoveToStart { select: false });
+        editor.run_edit_command(&EditCommand::MoveToMark {
+            name: 'a',
+            line: false,
+            select: false,
+        });
+        assert_eq!(editor.get_buffer(), "one\ntwo");
+        assert_eq!(editor.insertion_point(), 5);
+
+        editor.run_edit_command(&EditCommand::MoveToMark {
+            name: 'a',
+            line: true,
+            select: false,
+        });
+        assert_eq!(editor.insertion_point(), 4);
     }
 
-    fn move_word_right_start(&mut self, select: bool) {
-        self.move_to_position(self.line_buffer.word_right_start_index(), select);
+    #[test]
+    fn deleting_marked_text_moves_mark_to_deletion() {
+        let mut editor = editor_with("one two three");
+        editor.line_buffer.set_insertion_point(5);
+        editor.run_edit_command(&EditCommand::SetMark('a'));
+        editor.line_buffer.set_insertion_point(4);
+
+        editor.run_edit_command(&EditCommand::CutWordRightToNext);
+        editor.run_edit_command(&EditCommand::MoveToEnd { select: false });
+        editor.run_edit_command(&EditCommand::MoveToMark {
+            name: 'a',
+            line: false,
+            select: false,
+        });
+
+        assert_eq!(editor.insertion_point(), 4);
     }
 
-    fn move_big_word_right_start(&mut self, select: bool) {
-        self.move_to_position(self.line_buffer.big_word_right_start_index(), select);
+    #[test]
+    fn jump_list_moves_back_and_forth() {
+        let mut editor = editor_with("(a) b");
+        editor.line_buffer.set_insertion_point(5);
+        editor.run_edit_command(&EditCommand::SetMark('a'));
+        editor.line_buffer.set_insertion_point(0);
+        editor.run_edit_command(&EditCommand::MoveToMatchingBracket { select: false });
+        editor.run_edit_command(&EditCommand::MoveToMark {
+            name: 'a',
+            line: false,
+            select: false,
+        });
+
+        editor.run_edit_command(&EditCommand::MoveToOlderJump);
+        assert_eq!(editor.insertion_point(), 2);
+        editor.run_edit_command(&EditCommand::MoveToOlderJump);
+        assert_eq!(editor.insertion_point(), 0);
+        editor.run_edit_command(&EditCommand::MoveToOlderJump);
+        assert_eq!(editor.insertion_point(), 0);
+        editor.run_edit_command(&EditCommand::MoveToNewerJump);
+        assert_eq!(editor.insertion_point(), 2);
+        editor.run_edit_command(&EditCommand::MoveToNewerJump);
+        assert_eq!(editor.insertion_point(), 5);
+        editor.run_edit_command(&EditCommand::MoveToNewerJump);
+        assert_eq!(editor.insertion_point(), 5);
     }
 
-    fn move_word_right_end(&mut self, select: bool) {
-        self.move_to_position(self.line_buffer.word_right_end_index(), select);
+    #[test]
+    fn buffer_search_wraps_around_and_repeats() {
+        let mut editor = editor_with("foo bar\nfoo baz foo");
+        editor.line_buffer.set_insertion_point(0);
+        editor.run_edit_command(&EditCommand::SearchBuffer {
+            pattern: "foo".to_string(),
+            backward: false,
+            select: false,
+        });
+        assert_eq!(editor.insertion_point(), 8);
+        assert_eq!(editor.search_highlight(), Some("foo"));
+
+        editor.run_edit_command(&EditCommand::RepeatBufferSearch {
+            reverse: false,
+            select: false,
+        });
+        assert_eq!(editor.insertion_point(), 16);
+        editor.run_edit_command(&EditCommand::RepeatBufferSearch {
+            reverse: false,
+            select: false,
+        });
+        assert_eq!(editor.insertion_point(), 0);
+        editor.run_edit_command(&EditCommand::RepeatBufferSearch {
+            reverse: true,
+            select: false,
+        });
+        assert_eq!(editor.insertion_point(), 16);
+
+        editor.run_edit_command(&EditCommand::MoveToOlderJump);
+        assert_eq!(editor.insertion_point(), 0);
+        assert_eq!(editor.search_highlight(), None);
     }
 
-    fn move_big_word_right_end(&mut self, select: bool) {
-        self.move_to_position(self.line_buffer.big_word_right_end_index(), select);
+    #[test]
+    fn empty_buffer_search_repeats_the_last_pattern() {
+        let mut editor = editor_with("ab ab ab");
+        editor.run_edit_command(&EditCommand::SearchBuffer {
+            pattern: "ab".to_string(),
+            backward: true,
+            select: false,
+        });
+        assert_eq!(editor.insertion_point(), 6);
+
+        editor.run_edit_command(&EditCommand::SearchBuffer {
+            pattern: String::new(),
+            backward: true,
+            select: true,
+        });
+        assert_eq!(editor.insertion_point(), 3);
+        assert_eq!(editor.get_selection(), Some((3, 6)));
     }
 
-    fn insert_char(&mut self, c: char) {
-        self.delete_selection();
-        self.line_buffer.insert_char(c);
+    #[test]
+    fn swap_selection_ends_keeps_line_selection() {
+        let mut editor = editor_with("ab\ncd\nef");
+        editor.line_buffer.set_insertion_point(1);
+        editor.run_edit_command(&EditCommand::ToggleLineSelection);
+        editor.run_edit_command(&EditCommand::MoveLineDown { select: true });
+
+        editor.run_edit_command(&EditCommand::SwapSelectionEnds);
+
+        assert_eq!(editor.insertion_point(), 1);
+        assert_eq!(editor.get_selection(), Some((0, 6)));
     }
 
-    fn insert_str(&mut self, str: &str) {
-        self.delete_selection();
-        self.line_buffer.insert_str(str);
+    #[rstest]
+    #[case("ab\ncd\nef", 1, "cd\nef", 0, "ab\n")]
+    #[case("ab\ncd\nef", 7, "ab\ncd", 3, "ef")]
+    #[case("ab\r\ncd", 5, "ab", 0, "cd")]
+    #[case("ab", 1, "", 0, "ab")]
+    fn cut_line_selection(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: &str,
+        #[case] expected_insertion_point: usize,
+        #[case] expected_cut: &str,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+        editor.run_edit_command(&EditCommand::ToggleLineSelection);
+
+        editor.run_edit_command(&EditCommand::CutSelection);
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_insertion_point);
+        let (cut, mode) = editor.cut_buffer.get();
+        assert_eq!(cut, expected_cut);
+        assert!(matches!(mode, ClipboardMode::Lines));
     }
 
-    fn insert_newline(&mut self) {
-        self.delete_selection();
-        self.line_buffer.insert_newline();
+    #[rstest]
+    #[case("ls (a b) c", 0, "ls (a b) c", 7)]
+    #[case("ls (a b) c", 7, "ls (a b) c", 3)]
+    #[case("ls a b c", 0, "ls a b c", 0)]
+    fn move_to_matching_bracket(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: &str,
+        #[case] expected_insertion_point: usize,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&EditCommand::MoveToMatchingBracket { select: false });
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_insertion_point);
     }
 
-    #[cfg(feature = "system_clipboard")]
-    fn paste_from_system(&mut self) {
-        self.delete_selection();
-        insert_clipboard_content_before(&mut self.line_buffer, self.system_clipboard.deref_mut());
+    #[rstest]
+    #[case("ls (a b) c", 0, " c", "ls (a b)")]
+    #[case("ls (a b) c", 7, "ls  c", "(a b)")]
+    #[case("f {\n}", 1, "f", " {\n}")]
+    fn cut_to_matching_bracket(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: &str,
+        #[case] expected_cut: &str,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&EditCommand::CutToMatchingBracket);
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.cut_buffer.get().0, expected_cut);
     }
 
-    fn paste_cut_buffer(&mut self) {
-        self.delete_selection();
-        insert_clipboard_content_before(&mut self.line_buffer, self.cut_buffer.deref_mut());
+    #[rstest]
+    #[case("one\ntwo\nthree", 5, "one\nthree", 4, "two\n")]
+    #[case("one\ntwo", 5, "one", 0, "\ntwo")]
+    #[case("one\r\ntwo", 6, "one", 0, "\r\ntwo")]
+    #[case("one\n", 4, "one", 0, "\n")]
+    #[case("one", 1, "", 0, "one")]
+    fn kill_whole_line(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: &str,
+        #[case] expected_position: usize,
+        #[case] expected_cut: &str,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&EditCommand::KillWholeLine);
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_position);
+        let (cut, mode) = editor.cut_buffer.get();
+        assert_eq!(cut, expected_cut);
+        assert!(matches!(mode, ClipboardMode::Normal));
     }
-}
 
-fn insert_clipboard_content_before(line_buffer: &mut LineBuffer, clipboard: &mut dyn Clipboard) {
-    match clipboard.get() {
-        (content, ClipboardMode::Normal) => {
-            line_buffer.insert_str(&content);
-        }
-        (mut content, ClipboardMode::Lines) => {
-            // TODO: Simplify that?
-            line_buffer.move_to_line_start();
-            line_buffer.move_line_up();
-            if !content.ends_with('\n') {
-                // TODO: Make sure platform requirements are met
-                content.push('\n');
-            }
-            line_buffer.insert_str(&content);
-        }
+    #[test]
+    fn cut_text_object_is_undoable() {
+        let mut editor = editor_with("echo \"a b\" c");
+        editor.line_buffer.set_insertion_point(7);
+        let text_object = TextObject {
+            scope: TextObjectScope::Around,
+            kind: TextObjectKind::Quote('"'),
+        };
+
+        editor.run_edit_command(&EditCommand::CutTextObject(text_object));
+        assert_eq!(editor.get_buffer(), "echo c");
+        assert_eq!(editor.insertion_point(), 5);
+        assert_eq!(editor.cut_buffer.get().0, "\"a b\" ");
+
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "echo \"a b\" c");
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use pretty_assertions::assert_eq;
-    use rstest::rstest;
+    #[test]
+    fn select_text_object() {
+        let mut editor = editor_with("f(a, bc)");
+        editor.line_buffer.set_insertion_point(6);
+        let text_object = TextObject {
+            scope: TextObjectScope::Inner,
+            kind: TextObjectKind::Argument,
+        };
 
-    fn editor_with(buffer: &str) -> Editor {
-        let mut editor = Editor::default();
-        editor.set_buffer(buffer.to_string(), UndoBehavior::CreateUndoPoint);
-        editor
+        editor.run_edit_command(&EditCommand::SelectTextObject(text_object));
+        assert_eq!(editor.get_selection(), Some((5, 7)));
+
+        editor.run_edit_command(&EditCommand::CopyTextObject(text_object));
+        assert_eq!(editor.get_selection(), None);
+        assert_eq!(editor.insertion_point(), 5);
+        assert_eq!(editor.cut_buffer.get().0, "bc");
     }
 
     #[rstest]
-    #[case("abc def ghi", 11, "abc def ")]
-    #[case("abc def-ghi", 11, "abc def-")]
-    #[case("abc def.ghi", 11, "abc ")]
-    fn test_cut_word_left(#[case] input: &str, #[case] position: usize, #[case] expected: &str) {
+    #[case("say \"hi\" now", 6, TextObjectKind::Quote('"'), None, "say hi now", 4)]
+    #[case("say \"hi\" now", 6, TextObjectKind::Quote('"'), Some(('\'', '\'')), "say 'hi' now", 4)]
+    #[case("f(a, (b))", 6, TextObjectKind::Bracket('('), Some(('[', ']')), "f(a, [b])", 5)]
+    #[case("f(a, (b))", 3, TextObjectKind::Bracket('('), None, "fa, (b)", 1)]
+    #[case("no quotes", 3, TextObjectKind::Quote('"'), None, "no quotes", 3)]
+    #[case("a word", 3, TextObjectKind::Word, None, "a word", 3)]
+    fn test_change_surrounding(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] kind: TextObjectKind,
+        #[case] pair: Option<(char, char)>,
+        #[case] expected: &str,
+        #[case] expected_insertion_point: usize,
+    ) {
         let mut editor = editor_with(input);
         editor.line_buffer.set_insertion_point(position);
 
-        editor.cut_word_left();
+        let command = match pair {
+            Some((left, right)) => EditCommand::ChangeSurrounding { kind, left, right },
+            None => EditCommand::DeleteSurrounding(kind),
+        };
+        editor.run_edit_command(&command);
 
         assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_insertion_point);
+    }
+
+    #[test]
+    fn surround_selection_keeps_line_ending_outside() {
+        let mut editor = editor_with(
+            "one two
+three",
+        );
+        editor.line_buffer.set_insertion_point(4);
+        editor.run_edit_command(&EditCommand::MoveToLineEnd { select: true });
+        editor.run_edit_command(&EditCommand::SurroundSelection {
+            left: '(',
+            right: ')',
+        });
+        assert_eq!(editor.get_buffer(), "one (two)\nthree");
+        assert_eq!(editor.insertion_point(), 4);
+        assert_eq!(editor.get_selection(), None);
+
+        editor.run_edit_command(&EditCommand::ToggleLineSelection);
+        editor.run_edit_command(&EditCommand::SurroundSelection {
+            left: '"',
+            right: '"',
+        });
+        assert_eq!(editor.get_buffer(), "\"one (two)\"\nthree");
+    }
+
+    #[test]
+    fn registers_are_separate_from_cut_buffer() {
+        let mut editor = editor_with("one two");
+        editor.line_buffer.set_insertion_point(0);
+
+        editor.run_edit_command(&EditCommand::SelectRegister('a'));
+        editor.run_edit_command(&EditCommand::CutWordRightToNext);
+        editor.run_edit_command(&EditCommand::CutWordRight);
+        assert_eq!(editor.get_buffer(), "");
+        assert_eq!(editor.cut_buffer.get().0, "two");
+        assert_eq!(
+            editor.register('a').map(|(content, _)| content),
+            Some("one ".to_string())
+        );
+
+        editor.run_edit_command(&EditCommand::SelectRegister('a'));
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        editor.run_edit_command(&EditCommand::SelectRegister('b'));
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "one ");
+        assert_eq!(editor.register('b').map(|(content, _)| content), None);
+    }
+
+    #[test]
+    fn copy_selection_and_collapse_moves_to_start() {
+        let mut editor = editor_with("one two");
+        editor.line_buffer.set_insertion_point(7);
+
+        editor.run_edit_command(&EditCommand::MoveWordLeft { select: true });
+        editor.run_edit_command(&EditCommand::CopySelectionAndCollapse);
+
+        assert_eq!(editor.cut_buffer.get().0, "two");
+        assert_eq!(editor.insertion_point(), 4);
+        assert_eq!(editor.get_selection(), None);
+    }
+
+    #[test]
+    fn copy_current_line_pastes_as_line() {
+        let mut editor = editor_with("one\ntwo");
+        editor.line_buffer.set_insertion_point(1);
+
+        editor.run_edit_command(&EditCommand::CopyCurrentLine);
+        editor.run_edit_command(&EditCommand::PasteCutBufferAfter);
+
+        assert_eq!(editor.get_buffer(), "one\none\ntwo");
+    }
+
+    #[test]
+    fn word_definition_survives_undo() {
+        let mut editor = editor_with("cd ~/src");
+        editor.set_word_definition(WordDefinition::WordChars("~/".into()));
+
+        editor.run_edit_command(&EditCommand::Undo);
+        editor.run_edit_command(&EditCommand::InsertString("cd ~/src".into()));
+        editor.run_edit_command(&EditCommand::MoveWordLeft { select: false });
+
+        assert_eq!(editor.insertion_point(), 3);
     }
 
     #[rstest]
@@ -813,6 +2907,21 @@ mod test {
         assert_eq!(editor.get_buffer(), "This is a");
     }
 
+    #[test]
+    fn test_edit_buffer_is_a_single_undo_step() {
+        let mut editor = editor_with("This is a");
+        editor.edit_buffer(
+            |buffer| buffer.insert_str(" test"),
+            UndoBehavior::CreateUndoPoint,
+        );
+        editor.run_edit_command(&EditCommand::InsertChar('!'));
+        assert_eq!(editor.get_buffer(), "This is a test!");
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "This is a test");
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "This is a");
+    }
+
     #[test]
     fn test_undo_backspace_with_newline() {
         let mut editor = editor_with("This is a \n test");
@@ -876,6 +2985,327 @@ mod test {
         editor.run_edit_command(&EditCommand::Undo);
         assert_eq!(editor.get_buffer(), "This \r\n is a test");
     }
+    #[test]
+    fn test_edit_after_undo_keeps_redo_branch() {
+        let mut editor = editor_with("This is");
+        for cmd in str_to_edit_commands(" a test") {
+            editor.run_edit_command(&cmd);
+        }
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "This is a");
+        editor.run_edit_command(&EditCommand::InsertChar('!'));
+        assert_eq!(editor.get_buffer(), "This is a!");
+
+        editor.run_edit_command(&EditCommand::UndoEarlier);
+        assert_eq!(editor.get_buffer(), "This is a test");
+        editor.run_edit_command(&EditCommand::UndoLater);
+        assert_eq!(editor.get_buffer(), "This is a!");
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "This is a");
+        editor.run_edit_command(&EditCommand::Redo);
+        assert_eq!(editor.get_buffer(), "This is a!");
+    }
+
+    #[test]
+    fn undo_steps_keep_only_the_changed_text() {
+        let mut editor = editor_with(&"x".repeat(1000));
+        editor.run_edit_command(&EditCommand::MoveToStart { select: false });
+        for cmd in str_to_edit_commands("abc ") {
+            editor.run_edit_command(&cmd);
+        }
+        editor.run_edit_command(&EditCommand::Backspace);
+
+        let step = editor.undo_tree().current();
+        assert_eq!(
+            (step.start(), step.removed(), step.inserted()),
+            (3, " ", "")
+        );
+        editor.run_edit_command(&EditCommand::Undo);
+        let step = editor.undo_tree().current();
+        assert_eq!(
+            (step.start(), step.removed(), step.inserted()),
+            (3, "", " ")
+        );
+    }
+
+    #[test]
+    fn undo_states_are_restored_along_every_branch() {
+        let mut editor = Editor::default();
+        let mut states = HashMap::new();
+        let mut record = |editor: &Editor| {
+            states.insert(
+                editor.undo_tree().current_id(),
+                (editor.get_buffer().to_owned(), editor.insertion_point()),
+            );
+        };
+        record(&editor);
+        let commands = [
+            EditCommand::InsertString("one two three".into()),
+            EditCommand::MoveWordLeft { select: false },
+            EditCommand::InsertString("big ".into()),
+            EditCommand::MoveToStart { select: false },
+            EditCommand::Delete,
+            EditCommand::Delete,
+            EditCommand::Undo,
+            EditCommand::CutWordRight,
+            EditCommand::Undo,
+            EditCommand::Undo,
+            EditCommand::MoveToEnd { select: false },
+            EditCommand::InsertChar('!'),
+            EditCommand::InsertNewline,
+            EditCommand::InsertString("four".into()),
+            EditCommand::SwapWords,
+        ];
+        for command in &commands {
+            editor.run_edit_command(command);
+            if !matches!(command, EditCommand::Undo) {
+                record(&editor);
+            }
+        }
+
+        let last = editor.undo_tree().num_states() - 1;
+        assert_eq!(states.len(), last + 1);
+        for id in (0..last).rev().chain(1..=last) {
+            if id < editor.undo_tree().current_id() {
+                editor.run_edit_command(&EditCommand::UndoEarlier);
+            } else {
+                editor.run_edit_command(&EditCommand::UndoLater);
+            }
+            assert_eq!(editor.undo_tree().current_id(), id);
+            assert_eq!(
+                (editor.get_buffer(), editor.insertion_point()),
+                (states[&id].0.as_str(), states[&id].1)
+            );
+        }
+    }
+
+    #[test]
+    fn test_consecutive_kills_are_joined() {
+        let mut editor = editor_with("foo bar baz");
+        editor.set_join_kills(true);
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+        assert_eq!(editor.get_buffer(), "foo ");
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "foo bar baz");
+    }
+
+    #[test]
+    fn test_consecutive_whole_line_kills_are_joined() {
+        let mut editor = editor_with("one\ntwo\nthree");
+        editor.line_buffer.set_insertion_point(0);
+        editor.set_join_kills(true);
+        editor.run_edit_command(&EditCommand::KillWholeLine);
+        editor.run_edit_command(&EditCommand::KillWholeLine);
+        assert_eq!(editor.get_buffer(), "three");
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "one\ntwo\nthree");
+
+        // The last lines are killed backward, along with the line ending in front of them
+        let mut editor = editor_with("a\nb\nc");
+        editor.set_join_kills(true);
+        editor.run_edit_command(&EditCommand::KillWholeLine);
+        editor.run_edit_command(&EditCommand::KillWholeLine);
+        assert_eq!(editor.get_buffer(), "a");
+        editor.run_edit_command(&EditCommand::MoveToEnd { select: false });
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "a\nb\nc");
+    }
+
+    #[test]
+    fn test_consecutive_kills_are_kept_apart_unless_joined() {
+        let mut editor = editor_with("foo bar baz");
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+        assert_eq!(editor.get_buffer(), "foo ");
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "foo bar ");
+    }
+
+    #[test]
+    fn test_yank_pop_cycles_through_kill_ring() {
+        let mut editor = editor_with("one two");
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+        editor.run_edit_command(&EditCommand::MoveLeft { select: false });
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+        assert_eq!(editor.get_buffer(), " ");
+
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "one ");
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "two ");
+        assert_eq!(editor.insertion_point(), 3);
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "one ");
+    }
+
+    #[test]
+    fn test_yank_pop_requires_preceding_yank() {
+        let mut editor = editor_with("one two");
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "one ");
+    }
+
+    fn cursor_positions(editor: &Editor) -> Vec<usize> {
+        editor
+            .secondary_cursors()
+            .iter()
+            .map(|cursor| cursor.insertion_point)
+            .collect()
+    }
+
+    #[test]
+    fn test_add_cursor_below_and_insert() {
+        let mut editor = editor_with("ls\ncd\nrm");
+        editor.line_buffer.set_insertion_point(0);
+        editor.run_edit_command(&EditCommand::AddCursorBelow);
+        editor.run_edit_command(&EditCommand::AddCursorBelow);
+        editor.run_edit_command(&EditCommand::AddCursorBelow);
+        assert_eq!(cursor_positions(&editor), vec![3, 6]);
+
+        for cmd in str_to_edit_commands("x ") {
+            editor.run_edit_command(&cmd);
+        }
+        assert_eq!(editor.get_buffer(), "x ls\nx cd\nx rm");
+        assert_eq!(editor.insertion_point(), 2);
+        assert_eq!(cursor_positions(&editor), vec![7, 12]);
+
+        editor.run_edit_command(&EditCommand::Backspace);
+        editor.run_edit_command(&EditCommand::MoveWordRight { select: false });
+        editor.run_edit_command(&EditCommand::InsertChar('!'));
+        assert_eq!(editor.get_buffer(), "xls!\nxcd!\nxrm!");
+    }
+
+    #[test]
+    fn test_add_cursor_above_keeps_column() {
+        let mut editor = editor_with("echo a\necho b");
+        editor.run_edit_command(&EditCommand::AddCursorAbove);
+        editor.run_edit_command(&EditCommand::AddCursorAbove);
+        assert_eq!(cursor_positions(&editor), vec![6]);
+        editor.run_edit_command(&EditCommand::InsertChar(';'));
+        assert_eq!(editor.get_buffer(), "echo a;\necho b;");
+    }
+
+    #[test]
+    fn test_add_cursor_at_next_match_and_cut() {
+        let mut editor = editor_with("cat foo | grep foo | sort foo");
+        editor.line_buffer.set_insertion_point(5);
+        editor.run_edit_command(&EditCommand::AddCursorAtNextMatch);
+        assert_eq!(editor.get_selection(), Some((4, 7)));
+        editor.run_edit_command(&EditCommand::AddCursorAtNextMatch);
+        editor.run_edit_command(&EditCommand::AddCursorAtNextMatch);
+        // Wraps around to the primary selection and does not add it again
+        editor.run_edit_command(&EditCommand::AddCursorAtNextMatch);
+        assert_eq!(cursor_positions(&editor), vec![18, 29]);
+
+        editor.run_edit_command(&EditCommand::CutSelection);
+        assert_eq!(editor.get_buffer(), "cat  | grep  | sort ");
+        assert_eq!(editor.cut_buffer.get().0, "foo\nfoo\nfoo");
+        editor.run_edit_command(&EditCommand::InsertString("bar".into()));
+        assert_eq!(editor.get_buffer(), "cat bar | grep bar | sort bar");
+
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "cat  | grep  | sort ");
+        assert!(editor.secondary_cursors().is_empty());
+    }
+
+    #[test]
+    fn test_other_commands_drop_secondary_cursors() {
+        let mut editor = editor_with("a\nb");
+        editor.run_edit_command(&EditCommand::AddCursorAbove);
+        assert_eq!(cursor_positions(&editor), vec![1]);
+        editor.run_edit_command(&EditCommand::MoveToStart { select: false });
+        assert!(editor.secondary_cursors().is_empty());
+    }
+
+    #[test]
+    fn test_cursors_merge_when_meeting() {
+        let mut editor = editor_with("ab\ncd");
+        editor.line_buffer.set_insertion_point(1);
+        editor.run_edit_command(&EditCommand::AddCursorBelow);
+        editor.run_edit_command(&EditCommand::MoveToLineStart { select: false });
+        editor.run_edit_command(&EditCommand::Backspace);
+        assert_eq!(editor.get_buffer(), "abcd");
+        assert_eq!(cursor_positions(&editor), vec![2]);
+        editor.run_edit_command(&EditCommand::MoveToLineStart { select: false });
+        assert_eq!(editor.insertion_point(), 0);
+        assert!(editor.secondary_cursors().is_empty());
+    }
+
+    fn editor_with_block(buffer: &str, anchor: usize, insertion_point: usize) -> Editor {
+        let mut editor = editor_with(buffer);
+        editor.line_buffer.set_insertion_point(anchor);
+        editor.run_edit_command(&EditCommand::ToggleBlockSelection);
+        editor.line_buffer.set_insertion_point(insertion_point);
+        editor
+    }
+
+    #[rstest]
+    #[case("abcd\nefgh\nijkl", 1, 13, vec![(1, 3), (6, 8), (11, 13)])]
+    #[case("abcd\nefgh\nijkl", 13, 1, vec![(1, 3), (6, 8), (11, 13)])]
+    #[case("abcd\nef\nijkl", 1, 11, vec![(1, 3), (6, 7), (9, 11)])]
+    #[case("abcd\n\nijkl", 2, 9, vec![(2, 3), (5, 5), (8, 9)])]
+    // Wide graphemes are selected when partially covered
+    #[case("abcd\n漢字", 1, 8, vec![(1, 2), (5, 8)])]
+    #[case("ab\r\ncd", 0, 5, vec![(0, 1), (4, 5)])]
+    fn test_block_selection_ranges(
+        #[case] input: &str,
+        #[case] anchor: usize,
+        #[case] insertion_point: usize,
+        #[case] expected: Vec<(usize, usize)>,
+    ) {
+        let editor = editor_with_block(input, anchor, insertion_point);
+        assert_eq!(editor.get_block_selection(), Some(expected));
+    }
+
+    #[test]
+    fn test_cut_and_paste_block() {
+        let mut editor = editor_with_block("abcd\nefgh\nijkl", 1, 13);
+        editor.run_edit_command(&EditCommand::CutSelection);
+        assert_eq!(editor.get_buffer(), "ad\neh\nil");
+        assert_eq!(editor.insertion_point(), 1);
+        assert!(editor.get_block_selection().is_none());
+
+        editor.run_edit_command(&EditCommand::MoveToEnd { select: false });
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "ad\neh\nilbc\n  fg\n  jk");
+        assert_eq!(editor.insertion_point(), 8);
+    }
+
+    #[test]
+    fn test_paste_block_pads_short_lines() {
+        let mut editor = editor_with_block("ab\ncd", 0, 4);
+        editor.run_edit_command(&EditCommand::CopySelection);
+        editor.set_buffer("wide 漢\n\nxy".to_string(), UndoBehavior::CreateUndoPoint);
+        editor.line_buffer.set_insertion_point(8);
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "wide 漢a\n       c\nxy");
+    }
+
+    #[test]
+    fn test_insert_into_block() {
+        let mut editor = editor_with_block("a = 1\nb\nc = 3", 2, 10);
+        editor.run_edit_command(&EditCommand::InsertChar('='));
+        assert_eq!(editor.get_buffer(), "a == 1\nb\nc == 3");
+        assert_eq!(editor.secondary_cursors().len(), 1);
+
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "a = 1\nb\nc = 3");
+    }
+
+    #[test]
+    fn test_toggle_block_selection_keeps_anchor() {
+        let mut editor = editor_with_block("abc\ndef", 1, 6);
+        editor.run_edit_command(&EditCommand::ToggleBlockSelection);
+        assert!(editor.get_block_selection().is_none());
+        assert_eq!(editor.get_selection(), Some((1, 6)));
+        editor.run_edit_command(&EditCommand::MoveLeft { select: false });
+        editor.run_edit_command(&EditCommand::ToggleBlockSelection);
+        editor.run_edit_command(&EditCommand::MoveLeft { select: false });
+        assert!(editor.get_block_selection().is_none());
+    }
+
     #[cfg(feature = "system_clipboard")]
     mod without_system_clipboard {
         use super::*;
@@ -1,16 +1,41 @@
 use {
+    super::{line_index::LineIndex, WordDefinition},
+    crate::{TextObject, TextObjectKind, TextObjectScope},
     itertools::Itertools,
-    std::{convert::From, ops::Range},
+    std::{
+        convert::From,
+        ops::{Bound, Range, RangeBounds},
+    },
     unicode_segmentation::UnicodeSegmentation,
 };
 
 /// In memory representation of the entered line(s) including a cursor position to facilitate cursor based editing.
-#[derive(Debug, PartialEq, Eq, Clone, Default)]
+///
+/// The text is kept contiguous so it can be handed out as `&str` on every repaint.
+/// Line based queries go through an index of the line feeds instead of scanning the text,
+/// so they stay cheap for very large (e.g. pasted) inputs.
+#[derive(Debug, Clone, Default)]
 pub struct LineBuffer {
     lines: String,
     insertion_point: usize,
+    line_index: LineIndex,
+    word_definition: WordDefinition,
+    // Replaced ranges with the length of their replacement since the last `take_edits`,
+    // recorded for positions kept outside the buffer. `None` while not tracked
+    edits: Option<Vec<(Range<usize>, usize)>>,
+    // The range covering the text changed since the last `take_change` and the text it
+    // replaced, recorded for the undo steps. `None` while not tracked
+    change: Option<Option<(Range<usize>, String)>>,
 }
 
+impl PartialEq for LineBuffer {
+    fn eq(&self, other: &Self) -> bool {
+        self.lines == other.lines && self.insertion_point == other.insertion_point
+    }
+}
+
+impl Eq for LineBuffer {}
+
 impl From<&str> for LineBuffer {
     fn from(input: &str) -> Self {
         let mut line_buffer = LineBuffer::new();
@@ -65,6 +90,16 @@ impl LineBuffer {
         self.insertion_point
     }
 
+    /// Gets the definition of words used by the word motions
+    pub fn word_definition(&self) -> &WordDefinition {
+        &self.word_definition
+    }
+
+    /// Sets the definition of words used by the word motions
+    pub fn set_word_definition(&mut self, word_definition: WordDefinition) {
+        self.word_definition = word_definition;
+    }
+
     /// Sets the current edit position
     /// ## Unicode safety:
     /// Not checked, improper use may cause panics in following operations
@@ -79,6 +114,8 @@ impl LineBuffer {
 
     /// Set to a single line of `buffer` and reset the `InsertionPoint` cursor to the end
     pub fn set_buffer(&mut self, buffer: String) {
+        self.record_edit(0..self.lines.len(), buffer.len());
+        self.line_index = LineIndex::new(&buffer);
         self.lines = buffer;
         self.insertion_point = self.lines.len();
     }
@@ -87,12 +124,12 @@ impl LineBuffer {
     ///
     /// Zero-based index
     pub fn line(&self) -> usize {
-        self.lines[..self.insertion_point].matches('\n').count()
+        self.line_index.count_before(self.insertion_point)
     }
 
     /// Counts the number of lines in the buffer
     pub fn num_lines(&self) -> usize {
-        self.lines.split('\n').count()
+        self.line_index.len() + 1
     }
 
     /// Checks to see if the buffer ends with a given character
@@ -107,10 +144,7 @@ impl LineBuffer {
 
     /// Move the cursor before the first character of the line
     pub fn move_to_line_start(&mut self) {
-        self.insertion_point = self.lines[..self.insertion_point]
-            .rfind('\n')
-            .map_or(0, |offset| offset + 1);
-        // str is guaranteed to be utf8, thus \n is safe to assume 1 byte long
+        self.insertion_point = self.current_line_range().start;
     }
 
     /// Move cursor position to the end of the line
@@ -137,10 +171,9 @@ impl LineBuffer {
     /// - end of buffer (`len()`)
     /// - `\n` or `\r\n` (on the first byte)
     pub fn find_current_line_end(&self) -> usize {
-        self.lines[self.insertion_point..].find('\n').map_or_else(
+        self.line_index.next(self.insertion_point).map_or_else(
             || self.lines.len(),
-            |i| {
-                let absolute_index = i + self.insertion_point;
+            |absolute_index| {
                 if absolute_index > 0 && self.lines.as_bytes()[absolute_index - 1] == b'\r' {
                     absolute_index - 1
                 } else {
@@ -163,15 +196,15 @@ impl LineBuffer {
     pub fn grapheme_left_index(&self) -> usize {
         self.lines[..self.insertion_point]
             .grapheme_indices(true)
-            .last()
+            .next_back()
             .map(|(i, _)| i)
             .unwrap_or(0)
     }
 
     /// Cursor position *behind* the next word to the right
     pub fn word_right_index(&self) -> usize {
-        self.lines[self.insertion_point..]
-            .split_word_bound_indices()
+        self.word_definition
+            .split_word_bound_indices(&self.lines[self.insertion_point..])
             .find(|(_, word)| !is_whitespace_str(word))
             .map(|(i, word)| self.insertion_point + i + word.len())
             .unwrap_or_else(|| self.lines.len())
@@ -193,8 +226,8 @@ impl LineBuffer {
 
     /// Cursor position *at end of* the next word to the right
     pub fn word_right_end_index(&self) -> usize {
-        self.lines[self.insertion_point..]
-            .split_word_bound_indices()
+        self.word_definition
+            .split_word_bound_indices(&self.lines[self.insertion_point..])
             .find_map(|(i, word)| {
                 word.grapheme_indices(true)
                     .next_back()
@@ -204,7 +237,7 @@ impl LineBuffer {
             .unwrap_or_else(|| {
                 self.lines
                     .grapheme_indices(true)
-                    .last()
+                    .next_back()
                     .map(|x| x.0)
                     .unwrap_or(0)
             })
@@ -229,7 +262,7 @@ impl LineBuffer {
             .unwrap_or_else(|| {
                 self.lines
                     .grapheme_indices(true)
-                    .last()
+                    .next_back()
                     .map(|x| x.0)
                     .unwrap_or(0)
             })
@@ -237,8 +270,8 @@ impl LineBuffer {
 
     /// Cursor position *in front of* the next word to the right
     pub fn word_right_start_index(&self) -> usize {
-        self.lines[self.insertion_point..]
-            .split_word_bound_indices()
+        self.word_definition
+            .split_word_bound_indices(&self.lines[self.insertion_point..])
             .find(|(i, word)| *i != 0 && !is_whitespace_str(word))
             .map(|(i, _)| self.insertion_point + i)
             .unwrap_or_else(|| self.lines.len())
@@ -260,32 +293,23 @@ impl LineBuffer {
 
     /// Cursor position *in front of* the next word to the left
     pub fn word_left_index(&self) -> usize {
-        self.lines[..self.insertion_point]
-            .split_word_bound_indices()
-            .filter(|(_, word)| !is_whitespace_str(word))
-            .last()
+        self.word_definition
+            .split_word_bound_indices(&self.lines[..self.insertion_point])
+            .rfind(|(_, word)| !is_whitespace_str(word))
             .map(|(i, _)| i)
             .unwrap_or(0)
     }
 
     /// Cursor position *in front of* the next WORD to the left
     pub fn big_word_left_index(&self) -> usize {
+        // Walk backwards so only the WORD in front of the cursor gets segmented
         self.lines[..self.insertion_point]
             .split_word_bound_indices()
-            .fold(None, |last_word_index, (i, word)| {
-                match (last_word_index, is_whitespace_str(word)) {
-                    (None, true) => None,
-                    (None, false) => Some(i),
-                    (Some(v), true) => {
-                        if is_whitespace_str(&self.lines[i..self.insertion_point]) {
-                            Some(v)
-                        } else {
-                            None
-                        }
-                    }
-                    (Some(v), false) => Some(v),
-                }
-            })
+            .rev()
+            .skip_while(|(_, word)| is_whitespace_str(word))
+            .take_while(|(_, word)| !is_whitespace_str(word))
+            .last()
+            .map(|(i, _)| i)
             .unwrap_or(0)
     }
 
@@ -345,7 +369,8 @@ impl LineBuffer {
 
     ///Insert a single character at the insertion point and move right
     pub fn insert_char(&mut self, c: char) {
-        self.lines.insert(self.insertion_point, c);
+        let insertion_point = self.insertion_point;
+        self.replace_range(insertion_point..insertion_point, c.encode_utf8(&mut [0; 4]));
         self.move_right();
     }
 
@@ -356,7 +381,8 @@ impl LineBuffer {
     /// ## Unicode safety:
     /// Does not validate the incoming string or the current cursor position
     pub fn insert_str(&mut self, string: &str) {
-        self.lines.insert_str(self.insertion_point(), string);
+        let insertion_point = self.insertion_point;
+        self.replace_range(insertion_point..insertion_point, string);
         self.insertion_point = self.insertion_point() + string.len();
     }
 
@@ -373,14 +399,16 @@ impl LineBuffer {
 
     /// Empty buffer and reset cursor
     pub fn clear(&mut self) {
+        self.record_edit(0..self.lines.len(), 0);
         self.lines = String::new();
+        self.line_index = LineIndex::default();
         self.insertion_point = 0;
     }
 
     /// Clear everything beginning at the cursor to the right/end.
     /// Keeps the cursor at the end.
     pub fn clear_to_end(&mut self) {
-        self.lines.truncate(self.insertion_point);
+        self.clear_range(self.insertion_point..);
     }
 
     /// Clear beginning at the cursor up to the end of the line.
@@ -422,7 +450,7 @@ impl LineBuffer {
     /// Safety: Does not change the insertion point/offset and is thus not unicode safe!
     pub(crate) fn clear_range<R>(&mut self, range: R)
     where
-        R: std::ops::RangeBounds<usize>,
+        R: RangeBounds<usize>,
     {
         self.replace_range(range, "");
     }
@@ -432,9 +460,71 @@ impl LineBuffer {
     /// Safety: Does not change the insertion point/offset and is thus not unicode safe!
     pub fn replace_range<R>(&mut self, range: R, replace_with: &str)
     where
-        R: std::ops::RangeBounds<usize>,
+        R: RangeBounds<usize>,
     {
-        self.lines.replace_range(range, replace_with);
+        // All edits of the text pass through here to keep the line index in sync
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.lines.len(),
+        };
+        self.record_edit(start..end, replace_with.len());
+        self.lines.replace_range(start..end, replace_with);
+        self.line_index.replace(start..end, replace_with);
+    }
+
+    /// Record replacing the `range` by `len` bytes, before the text is changed
+    fn record_edit(&mut self, range: Range<usize>, len: usize) {
+        if let Some(change) = &mut self.change {
+            *change = Some(match change.take() {
+                None => (
+                    range.start..range.start + len,
+                    self.lines[range.clone()].to_owned(),
+                ),
+                // Grow the change to cover both, taking the text in between along
+                Some((changed, removed)) => {
+                    let start = changed.start.min(range.start);
+                    let end = changed.end.max(range.end);
+                    let removed = [
+                        &self.lines[start..changed.start],
+                        &removed,
+                        &self.lines[changed.end..end],
+                    ]
+                    .concat();
+                    (start..end - range.len() + len, removed)
+                }
+            });
+        }
+        if let Some(edits) = &mut self.edits {
+            edits.push((range, len));
+        }
+    }
+
+    /// Start recording the edits of the text, see [`LineBuffer::take_edits`]
+    pub(crate) fn track_edits(&mut self) {
+        self.edits = Some(Vec::new());
+    }
+
+    /// The ranges replaced since the last call, in order, with the length of their
+    /// replacement
+    pub(crate) fn take_edits(&mut self) -> Vec<(Range<usize>, usize)> {
+        self.edits.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// Start recording the changed text, see [`LineBuffer::take_change`]
+    pub(crate) fn track_change(&mut self) {
+        self.change = Some(None);
+    }
+
+    /// The range now covering all text changed since the last call and the text it
+    /// replaced, `None` if nothing changed
+    pub(crate) fn take_change(&mut self) -> Option<(Range<usize>, String)> {
+        self.change.as_mut().and_then(Option::take)
     }
 
     /// Checks to see if the current edit position is pointing to whitespace
@@ -459,10 +549,10 @@ impl LineBuffer {
     /// Gets the range of the word the current edit position is pointing to
     pub fn current_word_range(&self) -> Range<usize> {
         let right_index = self.word_right_index();
-        let left_index = self.lines[..right_index]
-            .split_word_bound_indices()
-            .filter(|(_, word)| !is_whitespace_str(word))
-            .last()
+        let left_index = self
+            .word_definition
+            .split_word_bound_indices(&self.lines[..right_index])
+            .rfind(|(_, word)| !is_whitespace_str(word))
             .map(|(i, _)| i)
             .unwrap_or(0);
 
@@ -475,16 +565,28 @@ impl LineBuffer {
     /// extending beyond the potential carriage return and line feed characters
     /// terminating the line
     pub fn current_line_range(&self) -> Range<usize> {
-        let left_index = self.lines[..self.insertion_point]
-            .rfind('\n')
+        self.line_range_at(self.insertion_point)
+    }
+
+    /// Range over the line containing the position, like [`LineBuffer::current_line_range`]
+    fn line_range_at(&self, position: usize) -> Range<usize> {
+        let left_index = self
+            .line_index
+            .previous(position)
             .map_or(0, |offset| offset + 1);
-        let right_index = self.lines[self.insertion_point..]
-            .find('\n')
-            .map_or_else(|| self.lines.len(), |i| i + self.insertion_point + 1);
+        let right_index = self
+            .line_index
+            .next(position)
+            .map_or_else(|| self.lines.len(), |offset| offset + 1);
 
         left_index..right_index
     }
 
+    /// Whether the line covered by the range holds nothing but whitespace
+    fn is_blank_line(&self, range: Range<usize>) -> bool {
+        self.lines[range].trim().is_empty()
+    }
+
     /// Uppercases the current word
     pub fn uppercase_word(&mut self) {
         let change_range = self.current_word_range();
@@ -523,6 +625,21 @@ impl LineBuffer {
         }
     }
 
+    /// Upper cases the first character of the current word and lower cases the rest, then
+    /// moves behind the word
+    pub fn capitalize_word(&mut self) {
+        let change_range = self.current_word_range();
+        let mut chars = self.get_buffer()[change_range.clone()].chars();
+        let capitalized = match chars.next() {
+            Some(first) => {
+                first.to_uppercase().collect::<String>() + &chars.as_str().to_lowercase()
+            }
+            None => return,
+        };
+        self.replace_range(change_range.clone(), &capitalized);
+        self.insertion_point = change_range.start + capitalized.len();
+    }
+
     /// Capitalize the character at insertion point (or the first character
     /// following the whitespace at the insertion point) and move the insertion
     /// point right one grapheme.
@@ -615,6 +732,91 @@ impl LineBuffer {
         }
     }
 
+    /// Swaps the current line with the line above, or with the line below on the first
+    /// line, and moves behind both lines
+    pub fn transpose_lines(&mut self) {
+        let current = self.current_line_range();
+        let (first, second) = if current.start > 0 {
+            (self.line_range_at(current.start - 1), current)
+        } else if self.lines[current.clone()].ends_with('\n') {
+            (current.clone(), self.line_range_at(current.end))
+        } else {
+            return;
+        };
+
+        let split_line_ending = |line: &str| {
+            let text = line.trim_end_matches(['\r', '\n']);
+            (text.to_string(), line[text.len()..].to_string())
+        };
+        let (first_text, first_ending) = split_line_ending(&self.lines[first.clone()]);
+        let (second_text, second_ending) = split_line_ending(&self.lines[second.clone()]);
+        let swapped = format!("{second_text}{first_ending}{first_text}{second_ending}");
+        self.replace_range(first.start..second.end, &swapped);
+        self.insertion_point = first.start + swapped.len();
+    }
+
+    /// Range over the spaces and tabs around the insertion point
+    fn horizontal_space_range(&self) -> Range<usize> {
+        let is_space = |c: char| c == ' ' || c == '\t';
+        let before = self.lines[..self.insertion_point].trim_end_matches(is_space);
+        let after = self.lines[self.insertion_point..].trim_start_matches(is_space);
+        before.len()..self.lines.len() - after.len()
+    }
+
+    /// Deletes the spaces and tabs around the insertion point
+    pub fn delete_horizontal_space(&mut self) {
+        let range = self.horizontal_space_range();
+        self.clear_range(range.clone());
+        self.insertion_point = range.start;
+    }
+
+    /// Replaces the spaces and tabs around the insertion point with a single space and
+    /// moves behind it
+    pub fn just_one_space(&mut self) {
+        let range = self.horizontal_space_range();
+        self.replace_range(range.clone(), " ");
+        self.insertion_point = range.start + 1;
+    }
+
+    /// Deletes the blank lines around the current line if it is blank, keeping one of
+    /// them, or the blank lines following it otherwise
+    ///
+    /// A single blank line is deleted as well.
+    pub fn delete_blank_lines(&mut self) {
+        let current = self.current_line_range();
+        let mut blank = current.clone();
+        while blank.end < self.lines.len() {
+            let next = self.line_range_at(blank.end);
+            if !self.is_blank_line(next.clone()) {
+                break;
+            }
+            blank.end = next.end;
+        }
+
+        if !self.is_blank_line(current.clone()) {
+            self.clear_range(current.end..blank.end);
+            return;
+        }
+
+        while blank.start > 0 {
+            let previous = self.line_range_at(blank.start - 1);
+            if !self.is_blank_line(previous.clone()) {
+                break;
+            }
+            blank.start = previous.start;
+        }
+        let kept_line = if blank == current {
+            ""
+        } else {
+            // The line ending of the last blank line, none at the end of the buffer
+            let last_line = &self.lines[self.line_range_at(blank.end - 1)];
+            &last_line[last_line.trim_end_matches(['\r', '\n']).len()..]
+        }
+        .to_string();
+        self.replace_range(blank.clone(), &kept_line);
+        self.insertion_point = blank.start;
+    }
+
     /// Moves one line up
     pub fn move_line_up(&mut self) {
         if !self.is_cursor_at_first_line() {
@@ -671,12 +873,309 @@ impl LineBuffer {
 
     /// Checks to see if the cursor is on the first line of the buffer
     pub fn is_cursor_at_first_line(&self) -> bool {
-        !self.get_buffer()[0..self.insertion_point()].contains('\n')
+        self.line_index.previous(self.insertion_point).is_none()
     }
 
     /// Checks to see if the cursor is on the last line of the buffer
     pub fn is_cursor_at_last_line(&self) -> bool {
-        !self.get_buffer()[self.insertion_point()..].contains('\n')
+        self.line_index.next(self.insertion_point).is_none()
+    }
+
+    /// Offset of the bracket matching the one at `offset`, searching across lines
+    ///
+    /// Supports `()`, `[]` and `{}`. Returns `None` if there is no bracket at `offset` or
+    /// it has no partner.
+    pub fn matching_bracket(&self, offset: usize) -> Option<usize> {
+        self.matching_bracket_within(offset, 0..self.lines.len())
+    }
+
+    /// Offset of the bracket matching the one at `offset`, searching at most `lines` lines
+    /// before and after its line
+    ///
+    /// Unlike [`LineBuffer::matching_bracket`] the cost does not grow with the buffer, as
+    /// the line index finds the searched lines directly. This suits highlighting on every
+    /// repaint.
+    pub fn matching_bracket_within_lines(&self, offset: usize, lines: usize) -> Option<usize> {
+        let line = self.line_index.count_before(offset);
+        let start = line
+            .checked_sub(lines + 1)
+            .map_or(0, |index| self.line_index.get(index) + 1);
+        let end = line
+            .checked_add(lines)
+            .filter(|&index| index < self.line_index.len())
+            .map_or(self.lines.len(), |index| self.line_index.get(index) + 1);
+        self.matching_bracket_within(offset, start..end)
+    }
+
+    fn matching_bracket_within(&self, offset: usize, range: Range<usize>) -> Option<usize> {
+        let bracket = self.lines.get(offset..range.end)?.chars().next()?;
+        let (open, close) = bracket_pair(bracket)?;
+        let mut depth = 0usize;
+        let mut track = |(i, c): (usize, char)| {
+            if c == bracket {
+                depth += 1;
+            } else if c == open || c == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            None
+        };
+        if bracket == open {
+            self.lines[offset..range.end]
+                .char_indices()
+                .find_map(|(i, c)| track((offset + i, c)))
+        } else {
+            self.lines[range.start..=offset]
+                .char_indices()
+                .rev()
+                .find_map(|(i, c)| track((range.start + i, c)))
+        }
+    }
+
+    /// Offset of the bracket matching the first bracket at or behind the cursor on the
+    /// current line, like `%` in Vi
+    pub fn matching_bracket_index(&self) -> Option<usize> {
+        let line_end = self.find_current_line_end();
+        self.lines[self.insertion_point..line_end]
+            .char_indices()
+            .find(|(_, c)| bracket_pair(*c).is_some())
+            .and_then(|(i, _)| self.matching_bracket(self.insertion_point + i))
+    }
+
+    /// Range of the given text object around the cursor, `None` if there is none
+    pub fn text_object_range(&self, text_object: TextObject) -> Option<Range<usize>> {
+        let around = text_object.scope == TextObjectScope::Around;
+        match text_object.kind {
+            TextObjectKind::Word => self.word_object_range(false, around),
+            TextObjectKind::BigWord => self.word_object_range(true, around),
+            TextObjectKind::Quote(quote) => self.quote_object_range(quote, around),
+            TextObjectKind::Bracket(open) => self.bracket_object_range(open, around),
+            TextObjectKind::Paragraph => self.paragraph_object_range(around),
+            TextObjectKind::Argument => self.argument_object_range(around),
+        }
+    }
+
+    /// The word (or WORD) or run of whitespace at the cursor, `around` adds the adjacent
+    /// whitespace or the following word respectively
+    fn word_object_range(&self, big: bool, around: bool) -> Option<Range<usize>> {
+        let line_start = self.current_line_range().start;
+        let line_end = self.find_current_line_end();
+
+        let mut segments: Vec<(Range<usize>, bool)> = Vec::new();
+        for (i, segment) in self
+            .word_definition
+            .split_word_bound_indices(&self.lines[line_start..line_end])
+        {
+            let range = line_start + i..line_start + i + segment.len();
+            let whitespace = is_whitespace_str(segment);
+            match segments.last_mut() {
+                Some((last, last_whitespace))
+                    if *last_whitespace == whitespace && (whitespace || big) =>
+                {
+                    last.end = range.end;
+                }
+                _ => segments.push((range, whitespace)),
+            }
+        }
+
+        let index = segments
+            .iter()
+            .position(|(range, _)| range.contains(&self.insertion_point))
+            .or_else(|| segments.len().checked_sub(1))?;
+        let (range, whitespace) = segments[index].clone();
+        if !around {
+            return Some(range);
+        }
+        match (segments.get(index + 1), index.checked_sub(1)) {
+            (Some((next, _)), _) if whitespace => Some(range.start..next.end),
+            (Some((next, true)), _) => Some(range.start..next.end),
+            (_, Some(previous)) if !whitespace && segments[previous].1 => {
+                Some(segments[previous].0.start..range.end)
+            }
+            _ => Some(range),
+        }
+    }
+
+    /// The text between the pair of quotes on the current line that surrounds or follows
+    /// the cursor, `around` adds the quotes and the trailing (or else leading) whitespace
+    fn quote_object_range(&self, quote: char, around: bool) -> Option<Range<usize>> {
+        let line_start = self.current_line_range().start;
+        let line_end = self.find_current_line_end();
+        let quotes: Vec<usize> = self.lines[line_start..line_end]
+            .match_indices(quote)
+            .map(|(i, _)| line_start + i)
+            .collect();
+        let (open, close) = quotes
+            .chunks_exact(2)
+            .map(|pair| (pair[0], pair[1]))
+            .find(|(_, close)| self.insertion_point <= *close)?;
+        if !around {
+            return Some(open + quote.len_utf8()..close);
+        }
+
+        let end = close + quote.len_utf8();
+        let trailing =
+            self.lines[end..line_end].len() - self.lines[end..line_end].trim_start().len();
+        if trailing > 0 {
+            Some(open..end + trailing)
+        } else {
+            Some(self.lines[line_start..open].trim_end().len() + line_start..end)
+        }
+    }
+
+    /// The text inside the brackets surrounding the cursor, `around` includes the brackets
+    fn bracket_object_range(&self, open: char, around: bool) -> Option<Range<usize>> {
+        let close = match open {
+            '(' => ')',
+            '[' => ']',
+            '{' => '}',
+            '<' => '>',
+            _ => return None,
+        };
+        let start = if self.lines[self.insertion_point..].starts_with(open) {
+            self.insertion_point
+        } else {
+            let mut depth = 0usize;
+            self.lines[..self.insertion_point]
+                .char_indices()
+                .rev()
+                .find(|(_, c)| {
+                    if *c == close {
+                        depth += 1;
+                    } else if *c == open {
+                        if depth == 0 {
+                            return true;
+                        }
+                        depth -= 1;
+                    }
+                    false
+                })?
+                .0
+        };
+        let mut depth = 0usize;
+        let end = self.lines[start + open.len_utf8()..]
+            .char_indices()
+            .find(|(_, c)| {
+                if *c == open {
+                    depth += 1;
+                } else if *c == close {
+                    if depth == 0 {
+                        return true;
+                    }
+                    depth -= 1;
+                }
+                false
+            })?
+            .0
+            + start
+            + open.len_utf8();
+
+        if around {
+            Some(start..end + close.len_utf8())
+        } else {
+            Some(start + open.len_utf8()..end)
+        }
+    }
+
+    /// The lines of the paragraph at the cursor, or the blank lines at the cursor.
+    /// `around` adds the following blank lines or paragraph respectively
+    fn paragraph_object_range(&self, around: bool) -> Option<Range<usize>> {
+        let mut offset = 0;
+        let lines: Vec<Range<usize>> = self
+            .lines
+            .split_inclusive('\n')
+            .map(|line| {
+                offset += line.len();
+                offset - line.len()..offset
+            })
+            .collect();
+        let blank = |index: usize| self.lines[lines[index].clone()].trim().is_empty();
+
+        let index = lines
+            .iter()
+            .position(|line| line.contains(&self.insertion_point))
+            .or_else(|| lines.len().checked_sub(1))?;
+        let is_blank = blank(index);
+        let mut first = index;
+        while first > 0 && blank(first - 1) == is_blank {
+            first -= 1;
+        }
+        let mut last = index;
+        while last + 1 < lines.len() && blank(last + 1) == is_blank {
+            last += 1;
+        }
+
+        if around {
+            if last + 1 < lines.len() {
+                last += 1;
+                while last + 1 < lines.len() && blank(last + 1) != is_blank {
+                    last += 1;
+                }
+            } else if !is_blank {
+                while first > 0 && blank(first - 1) {
+                    first -= 1;
+                }
+            }
+        }
+        Some(lines[first].start..lines[last].end)
+    }
+
+    /// The comma separated argument at the cursor inside the closest enclosing brackets,
+    /// `around` adds the following (or else preceding) comma and whitespace
+    fn argument_object_range(&self, around: bool) -> Option<Range<usize>> {
+        let mut depth = 0usize;
+        let open = self.lines[..self.insertion_point]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| match c {
+                ')' | ']' | '}' => {
+                    depth += 1;
+                    false
+                }
+                '(' | '[' | '{' if depth == 0 => true,
+                '(' | '[' | '{' => {
+                    depth -= 1;
+                    false
+                }
+                _ => false,
+            })?
+            .0;
+
+        let mut separators = vec![open];
+        let mut depth = 0usize;
+        let close = self.lines[open + 1..].char_indices().find_map(|(i, c)| {
+            match c {
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' if depth == 0 => return Some(open + 1 + i),
+                ')' | ']' | '}' => depth -= 1,
+                ',' if depth == 0 => separators.push(open + 1 + i),
+                _ => {}
+            }
+            None
+        })?;
+        separators.push(close);
+
+        let index = separators
+            .windows(2)
+            .position(|pair| self.insertion_point <= pair[1])?;
+        let (start, end) = (separators[index] + 1, separators[index + 1]);
+        let content = &self.lines[start..end];
+        let inner_start = start + content.len() - content.trim_start().len();
+        let inner_end = (start + content.trim_end().len()).max(inner_start);
+        if !around {
+            return Some(inner_start..inner_end);
+        }
+
+        if end != close {
+            let next = &self.lines[end + 1..close];
+            Some(inner_start..end + 1 + next.len() - next.trim_start().len())
+        } else if index > 0 {
+            Some(separators[index]..inner_end)
+        } else {
+            Some(inner_start..inner_end)
+        }
     }
 
     /// Finds index for the first occurrence of a char to the right of offset
@@ -769,6 +1268,16 @@ impl LineBuffer {
     }
 }
 
+/// The opening and closing bracket of the pair `c` belongs to
+fn bracket_pair(c: char) -> Option<(char, char)> {
+    match c {
+        '(' | ')' => Some(('(', ')')),
+        '[' | ']' => Some(('[', ']')),
+        '{' | '}' => Some(('{', '}')),
+        _ => None,
+    }
+}
+
 /// Match any sequence of characters that are considered a word boundary
 fn is_whitespace_str(s: &str) -> bool {
     s.chars().all(char::is_whitespace)
@@ -849,6 +1358,26 @@ mod test {
         line_buffer.assert_valid();
     }
 
+    #[test]
+    fn take_change_covers_all_edits_since_the_last_call() {
+        let mut line_buffer = buffer_with("one two three");
+        line_buffer.track_change();
+        assert_eq!(line_buffer.take_change(), None);
+
+        line_buffer.replace_range(4..7, "2");
+        line_buffer.move_to_end();
+        line_buffer.insert_str("!");
+        line_buffer.replace_range(0..3, "1");
+        assert_eq!(line_buffer.get_buffer(), "1 2 three!");
+        assert_eq!(
+            line_buffer.take_change(),
+            Some((0..10, "one two three".into()))
+        );
+
+        line_buffer.clear_range(1..2);
+        assert_eq!(line_buffer.take_change(), Some((1..1, " ".into())));
+    }
+
     #[rstest]
     #[case("This is a test", "This is a tes")]
     #[case("This is a test 😊", "This is a test ")]
@@ -1068,6 +1597,108 @@ mod test {
         line_buffer.assert_valid();
     }
 
+    #[rstest]
+    #[case("this is a TEST", 10, "this is a Test", 14)]
+    #[case("this is a test", 7, "this is A test", 9)]
+    #[case("one\n  two", 3, "one\n  Two", 9)]
+    #[case("élan", 0, "Élan", 5)]
+    #[case("", 0, "", 0)]
+    fn capitalize_word_works(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+        line_buffer.capitalize_word();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("one\ntwo\nthree", 5, "two\none\nthree", 8)]
+    #[case("one\ntwo\nthree", 0, "two\none\nthree", 8)]
+    #[case("one\ntwo\nthree", 10, "one\nthree\ntwo", 13)]
+    #[case("one\r\ntwo", 6, "two\r\none", 8)]
+    #[case("one\n", 1, "\none", 4)]
+    #[case("one", 1, "one", 1)]
+    fn transpose_lines_works(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+        line_buffer.transpose_lines();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("a  \t b", 3, "ab", 1, "a b", 2)]
+    #[case("a b", 1, "ab", 1, "a b", 2)]
+    #[case("ab", 1, "ab", 1, "a b", 2)]
+    #[case("a \n  b", 4, "a \nb", 3, "a \n b", 4)]
+    #[case("  ", 2, "", 0, " ", 1)]
+    fn horizontal_space_works(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] deleted: &str,
+        #[case] deleted_location: usize,
+        #[case] one_space: &str,
+        #[case] one_space_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+        line_buffer.delete_horizontal_space();
+        let mut expected = buffer_with(deleted);
+        expected.set_insertion_point(deleted_location);
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+        line_buffer.just_one_space();
+        let mut expected = buffer_with(one_space);
+        expected.set_insertion_point(one_space_location);
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("a\n\n \n\nb", 3, "a\n\nb", 2)]
+    #[case("a\n\nb", 2, "a\nb", 2)]
+    #[case("a\n\n\t\nb", 1, "a\nb", 1)]
+    #[case("a\nb", 1, "a\nb", 1)]
+    #[case("a\n\n  ", 3, "a\n", 2)]
+    #[case("\r\n\r\na", 0, "\r\na", 0)]
+    fn delete_blank_lines_works(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+        line_buffer.delete_blank_lines();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
     #[rstest]
     #[case("line 1\nline 2", 7, 0)]
     #[case("line 1\nline 2", 8, 1)]
@@ -1597,4 +2228,134 @@ mod test {
 
         assert_eq!(index, expected);
     }
+
+    #[rstest]
+    #[case("(a [b] c)", 0, Some(8))]
+    #[case("(a [b] c)", 8, Some(0))]
+    #[case("(a [b] c)", 3, Some(5))]
+    #[case("{\n  (x)\n}", 8, Some(0))]
+    #[case("((a)", 0, None)]
+    #[case("(a)", 1, None)]
+    #[case("(a)", 3, None)]
+    fn test_matching_bracket(
+        #[case] input: &str,
+        #[case] offset: usize,
+        #[case] expected: Option<usize>,
+    ) {
+        let line_buffer = buffer_with(input);
+
+        assert_eq!(line_buffer.matching_bracket(offset), expected);
+    }
+
+    #[rstest]
+    #[case("(a [b] c)", 0, 0, Some(8))]
+    #[case("x\n(a)\ny", 4, 0, Some(2))]
+    #[case("x\n(a)\ny", 2, 0, Some(4))]
+    #[case("{\n  (x)\n}", 0, 0, None)]
+    #[case("{\n  (x)\n}", 0, 1, None)]
+    #[case("{\n  (x)\n}", 0, 2, Some(8))]
+    #[case("{\n  (x)\n}", 8, 2, Some(0))]
+    #[case("{\n  (x)\n}", 8, 1, None)]
+    #[case("{\n  (x)\n}", 4, 0, Some(6))]
+    #[case("{\r\n}", 0, 1, Some(3))]
+    fn test_matching_bracket_within_lines(
+        #[case] input: &str,
+        #[case] offset: usize,
+        #[case] lines: usize,
+        #[case] expected: Option<usize>,
+    ) {
+        let line_buffer = buffer_with(input);
+
+        assert_eq!(
+            line_buffer.matching_bracket_within_lines(offset, lines),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case("ls (a) b", 0, Some(5))]
+    #[case("ls (a) b", 5, Some(3))]
+    #[case("ls (a) b", 6, None)]
+    #[case("f {\n}\n", 0, Some(4))]
+    #[case("a\n(b)", 0, None)]
+    fn test_matching_bracket_index(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: Option<usize>,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+
+        assert_eq!(line_buffer.matching_bracket_index(), expected);
+    }
+    #[rstest]
+    #[case("foo bar  baz", 5, TextObjectKind::Word, "bar", "bar  ")]
+    #[case("foo bar", 5, TextObjectKind::Word, "bar", " bar")]
+    #[case("foo   bar", 4, TextObjectKind::Word, "   ", "   bar")]
+    #[case("foo.bar baz", 1, TextObjectKind::Word, "foo.bar", "foo.bar ")]
+    #[case("a\nls -la /tmp", 6, TextObjectKind::BigWord, "-la", "-la ")]
+    #[case("echo 'a b' c", 7, TextObjectKind::Quote('\''), "a b", "'a b' ")]
+    #[case("echo 'a b'", 0, TextObjectKind::Quote('\''), "a b", " 'a b'")]
+    #[case(
+        "f(a, (b), c)",
+        3,
+        TextObjectKind::Bracket('('),
+        "a, (b), c",
+        "(a, (b), c)"
+    )]
+    #[case("f(a, (b), c)", 5, TextObjectKind::Bracket('('), "b", "(b)")]
+    #[case(
+        "f(a, (b), c)",
+        11,
+        TextObjectKind::Bracket('('),
+        "a, (b), c",
+        "(a, (b), c)"
+    )]
+    #[case("{\n  x\n}", 4, TextObjectKind::Bracket('{'), "\n  x\n", "{\n  x\n}")]
+    #[case("a\nb\n\nc\n", 2, TextObjectKind::Paragraph, "a\nb\n", "a\nb\n\n")]
+    #[case("a\n\n\nc", 2, TextObjectKind::Paragraph, "\n\n", "\n\nc")]
+    #[case("a\n\nc", 3, TextObjectKind::Paragraph, "c", "\nc")]
+    #[case(
+        "f(a, g(b, c), d)",
+        6,
+        TextObjectKind::Argument,
+        "g(b, c)",
+        "g(b, c), "
+    )]
+    #[case("f(a, g(b, c), d)", 10, TextObjectKind::Argument, "c", ", c")]
+    #[case("f( a )", 3, TextObjectKind::Argument, "a", "a")]
+    fn test_text_object_range(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] kind: TextObjectKind,
+        #[case] inner: &str,
+        #[case] around: &str,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+
+        for (scope, expected) in [
+            (TextObjectScope::Inner, inner),
+            (TextObjectScope::Around, around),
+        ] {
+            let range = line_buffer.text_object_range(TextObject { scope, kind });
+            assert_eq!(range.map(|range| &input[range]), Some(expected));
+        }
+    }
+
+    #[rstest]
+    #[case("", TextObjectKind::Word)]
+    #[case("a 'b", TextObjectKind::Quote('\''))]
+    #[case("a (b) c", TextObjectKind::Bracket('('))]
+    #[case("a, b", TextObjectKind::Argument)]
+    fn test_text_object_range_not_found(#[case] input: &str, #[case] kind: TextObjectKind) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(0);
+
+        let text_object = TextObject {
+            scope: TextObjectScope::Inner,
+            kind,
+        };
+        assert_eq!(line_buffer.text_object_range(text_object), None);
+    }
 }
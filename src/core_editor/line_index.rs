@@ -0,0 +1,154 @@
+use std::ops::Range;
+
+/// Offsets of the line feeds of a text, kept up to date with every edit of the text
+///
+/// Edits usually happen next to each other. Like the gap of a gap buffer, the offsets behind
+/// the most recent edit are stored without the change in length caused by the edits
+/// (`shift`), which is only applied once an edit happens further in front. Repeated edits on
+/// the same line thus cost the same no matter how many lines follow.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LineIndex {
+    newlines: Vec<usize>,
+    // Index of the first entry of `newlines` still lacking the `shift`
+    shifted_from: usize,
+    shift: isize,
+}
+
+impl LineIndex {
+    pub(crate) fn new(text: &str) -> Self {
+        LineIndex {
+            newlines: newline_offsets(text, 0).collect(),
+            shifted_from: 0,
+            shift: 0,
+        }
+    }
+
+    /// Number of line feeds in the text
+    pub(crate) fn len(&self) -> usize {
+        self.newlines.len()
+    }
+
+    /// Offset of the line feed with the given index
+    pub(crate) fn get(&self, index: usize) -> usize {
+        let offset = self.newlines[index];
+        if index >= self.shifted_from {
+            offset.wrapping_add(self.shift as usize)
+        } else {
+            offset
+        }
+    }
+
+    /// Number of line feeds in front of `offset`, which is the index of its line
+    pub(crate) fn count_before(&self, offset: usize) -> usize {
+        let (mut low, mut high) = (0, self.newlines.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.get(mid) < offset {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+
+    /// Offset of the first line feed at or behind `offset`
+    pub(crate) fn next(&self, offset: usize) -> Option<usize> {
+        let index = self.count_before(offset);
+        (index < self.len()).then(|| self.get(index))
+    }
+
+    /// Offset of the last line feed in front of `offset`
+    pub(crate) fn previous(&self, offset: usize) -> Option<usize> {
+        self.count_before(offset)
+            .checked_sub(1)
+            .map(|index| self.get(index))
+    }
+
+    /// Track the replacement of `range` of the text with `text`
+    pub(crate) fn replace(&mut self, range: Range<usize>, text: &str) {
+        let first = self.count_before(range.start);
+        let last = self.count_before(range.end);
+        self.move_gap(last);
+
+        let inserted: Vec<usize> = newline_offsets(text, range.start).collect();
+        self.shifted_from = first + inserted.len();
+        self.newlines.splice(first..last, inserted);
+        self.shift += text.len() as isize - range.len() as isize;
+    }
+
+    /// Make the `shift` apply from the entry with the given index on
+    fn move_gap(&mut self, index: usize) {
+        let shift = self.shift as usize;
+        if index > self.shifted_from {
+            for offset in &mut self.newlines[self.shifted_from..index] {
+                *offset = offset.wrapping_add(shift);
+            }
+        } else {
+            for offset in &mut self.newlines[index..self.shifted_from] {
+                *offset = offset.wrapping_sub(shift);
+            }
+        }
+        self.shifted_from = index;
+    }
+}
+
+fn newline_offsets(text: &str, start: usize) -> impl Iterator<Item = usize> + '_ {
+    text.match_indices('\n').map(move |(i, _)| start + i)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn offsets(index: &LineIndex) -> Vec<usize> {
+        (0..index.len()).map(|i| index.get(i)).collect()
+    }
+
+    #[rstest]
+    #[case("", vec![])]
+    #[case("abc", vec![])]
+    #[case("a\nb\r\nc\n", vec![1, 4, 6])]
+    fn new_finds_line_feeds(#[case] text: &str, #[case] expected: Vec<usize>) {
+        assert_eq!(offsets(&LineIndex::new(text)), expected);
+    }
+
+    #[rstest]
+    #[case(0, None, Some(2))]
+    #[case(2, None, Some(2))]
+    #[case(3, Some(2), Some(5))]
+    #[case(8, Some(5), None)]
+    fn neighbouring_line_feeds(
+        #[case] offset: usize,
+        #[case] previous: Option<usize>,
+        #[case] next: Option<usize>,
+    ) {
+        let index = LineIndex::new("ab\ncd\nef");
+        assert_eq!(index.previous(offset), previous);
+        assert_eq!(index.next(offset), next);
+    }
+
+    #[test]
+    fn replacements_anywhere_keep_offsets_in_sync() {
+        let mut text = String::from("one\ntwo\nthree\nfour\n");
+        let mut index = LineIndex::new(&text);
+        let edits: [(Range<usize>, &str); 8] = [
+            (5..5, "xx"),
+            (6..6, "y"),
+            (0..0, "zero\n"),
+            (20..24, ""),
+            (2..9, "\n\n"),
+            (text.len() - 8..text.len() - 8, "a\nb"),
+            (0..3, ""),
+            (1..4, "long replacement without line feed"),
+        ];
+        for (range, replacement) in edits {
+            let range = range.start.min(text.len())..range.end.min(text.len());
+            text.replace_range(range.clone(), replacement);
+            index.replace(range, replacement);
+            assert_eq!(offsets(&index), offsets(&LineIndex::new(&text)));
+        }
+    }
+}
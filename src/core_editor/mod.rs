@@ -1,10 +1,16 @@
+mod auto_pair;
 mod clip_buffer;
-mod edit_stack;
 mod editor;
 mod line_buffer;
+mod line_index;
+mod undo_tree;
+mod word_definition;
 
+pub use auto_pair::{AutoPair, AutoPairRule};
 #[cfg(feature = "system_clipboard")]
 pub(crate) use clip_buffer::get_system_clipboard;
-pub(crate) use clip_buffer::{get_local_clipboard, Clipboard, ClipboardMode};
+pub(crate) use clip_buffer::{Clipboard, ClipboardMode, KillDirection, LocalClipboard};
 pub use editor::Editor;
 pub use line_buffer::LineBuffer;
+pub use undo_tree::{UndoNode, UndoStateId, UndoStep, UndoTree};
+pub use word_definition::WordDefinition;
@@ -0,0 +1,453 @@
+use std::{
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+/// Identifier of a state in the [`UndoTree`]
+///
+/// States are numbered in the order they were created, the initial state is `0`.
+pub type UndoStateId = usize;
+
+/// A single recorded state of the [`UndoTree`]
+#[derive(Debug, Clone)]
+pub struct UndoNode<T> {
+    value: T,
+    parent: Option<UndoStateId>,
+    children: Vec<UndoStateId>,
+    // Child that `redo` will move to, the most recently created or left one
+    redo_child: Option<UndoStateId>,
+    timestamp: Instant,
+}
+
+impl<T> UndoNode<T> {
+    /// The recorded value
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// The state this one was derived from, `None` for the initial state
+    pub fn parent(&self) -> Option<UndoStateId> {
+        self.parent
+    }
+
+    /// All states derived from this one, in the order they were created
+    pub fn children(&self) -> &[UndoStateId] {
+        &self.children
+    }
+
+    /// When the state was first recorded, amending it keeps the time
+    pub fn timestamp(&self) -> Instant {
+        self.timestamp
+    }
+}
+
+/// Change of the text leading from the parent state to a state of the [`UndoTree`]
+///
+/// Only the replaced text is kept instead of the whole buffer, so recording an edit does not
+/// get slower as the buffer grows.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UndoStep {
+    start: usize,
+    removed: String,
+    inserted: String,
+    insertion_point: usize,
+}
+
+impl UndoStep {
+    pub(crate) fn new(
+        start: usize,
+        removed: String,
+        inserted: String,
+        insertion_point: usize,
+    ) -> Self {
+        UndoStep {
+            start,
+            removed,
+            inserted,
+            insertion_point,
+        }
+    }
+
+    /// Offset of the replaced text
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Text of the parent state replaced by the step
+    pub fn removed(&self) -> &str {
+        &self.removed
+    }
+
+    /// Text replacing it in the state
+    pub fn inserted(&self) -> &str {
+        &self.inserted
+    }
+
+    /// Position of the cursor in the state
+    pub fn insertion_point(&self) -> usize {
+        self.insertion_point
+    }
+
+    pub(crate) fn set_insertion_point(&mut self, insertion_point: usize) {
+        self.insertion_point = insertion_point;
+    }
+
+    /// Append the change of the `range` of the `text`, which replaced `removed`
+    ///
+    /// `text` is the text after both changes.
+    pub(crate) fn compose(&mut self, text: &str, range: Range<usize>, removed: &str) {
+        let changed = self.start..self.start + self.inserted.len();
+        let removed_range = range.start..range.start + removed.len();
+        if changed.start <= removed_range.start && removed_range.end <= changed.end {
+            // Within the text inserted so far, e.g. typing on
+            self.inserted.replace_range(
+                removed_range.start - changed.start..removed_range.end - changed.start,
+                &text[range],
+            );
+            return;
+        }
+        let start = changed.start.min(range.start);
+        let end = changed.end.max(removed_range.end);
+        // The text covered by both changes before the later one
+        let between = [
+            &text[start..range.start],
+            removed,
+            &text[range.end..end - removed.len() + range.len()],
+        ]
+        .concat();
+        self.removed = [
+            &between[..changed.start - start],
+            &self.removed,
+            &between[changed.end - start..],
+        ]
+        .concat();
+        self.inserted = text[start..end - removed.len() + range.len()].to_owned();
+        self.start = start;
+    }
+}
+
+/// Undo history that keeps every branch
+///
+/// Undoing and then making a new edit starts a new branch instead of discarding the redo
+/// states. [`UndoTree::undo`]/[`UndoTree::redo`] walk along the tree, while
+/// [`UndoTree::earlier`]/[`UndoTree::later`] travel through the states in the order they were
+/// created (like Vim's `g-`/`g+`), reaching states on every branch.
+#[derive(Debug, Clone)]
+pub struct UndoTree<T> {
+    nodes: Vec<UndoNode<T>>,
+    current: UndoStateId,
+}
+
+impl<T> UndoTree<T> {
+    pub(crate) fn new() -> Self
+    where
+        T: Default,
+    {
+        UndoTree {
+            nodes: vec![UndoNode {
+                value: T::default(),
+                parent: None,
+                children: Vec::new(),
+                redo_child: None,
+                timestamp: Instant::now(),
+            }],
+            current: 0,
+        }
+    }
+
+    /// Id of the state the editor is currently at
+    pub fn current_id(&self) -> UndoStateId {
+        self.current
+    }
+
+    /// Number of recorded states, including the initial state
+    pub fn num_states(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Look up a recorded state
+    pub fn node(&self, id: UndoStateId) -> Option<&UndoNode<T>> {
+        self.nodes.get(id)
+    }
+
+    /// The states left going from `from` up to the closest common ancestor with `to`,
+    /// followed by the states entered going down from there to `to`
+    pub(crate) fn path(
+        &self,
+        mut from: UndoStateId,
+        mut to: UndoStateId,
+    ) -> (Vec<UndoStateId>, Vec<UndoStateId>) {
+        let mut up = Vec::new();
+        let mut down = Vec::new();
+        // Parents are always created before their children
+        while from != to {
+            if from > to {
+                up.push(from);
+                from = self.nodes[from].parent.unwrap_or_default();
+            } else {
+                down.push(to);
+                to = self.nodes[to].parent.unwrap_or_default();
+            }
+        }
+        down.reverse();
+        (up, down)
+    }
+
+    /// Go back to the parent state. If present on the initial state do nothing
+    pub(crate) fn undo(&mut self) -> &T {
+        if let Some(parent) = self.nodes[self.current].parent {
+            self.nodes[parent].redo_child = Some(self.current);
+            self.current = parent;
+        }
+        &self.nodes[self.current].value
+    }
+
+    /// Go forward along the most recently used branch. If present on a leaf do nothing
+    pub(crate) fn redo(&mut self) -> &T {
+        if let Some(child) = self.nodes[self.current].redo_child {
+            self.current = child;
+        }
+        &self.nodes[self.current].value
+    }
+
+    /// Go to the state created before the current one, regardless of the branch
+    pub(crate) fn earlier(&mut self) -> &T {
+        self.current = self.current.saturating_sub(1);
+        &self.nodes[self.current].value
+    }
+
+    /// Go to the state created after the current one, regardless of the branch
+    pub(crate) fn later(&mut self) -> &T {
+        self.current = (self.current + 1).min(self.nodes.len() - 1);
+        &self.nodes[self.current].value
+    }
+
+    /// Go to the newest state recorded at least `duration` before the current one
+    pub(crate) fn earlier_by(&mut self, duration: Duration) -> &T {
+        let current_time = self.nodes[self.current].timestamp;
+        self.current = match current_time.checked_sub(duration) {
+            Some(target) => self.nodes[..self.current]
+                .iter()
+                .rposition(|node| node.timestamp <= target)
+                .unwrap_or(0),
+            None => 0,
+        };
+        &self.nodes[self.current].value
+    }
+
+    /// Go to the newest state recorded at most `duration` after the current one
+    pub(crate) fn later_by(&mut self, duration: Duration) -> &T {
+        let target = self.nodes[self.current].timestamp + duration;
+        self.current = self
+            .nodes
+            .iter()
+            .rposition(|node| node.timestamp <= target)
+            .map_or(self.current, |id| id.max(self.current));
+        &self.nodes[self.current].value
+    }
+
+    /// Record a new state as a child of the current one
+    pub(crate) fn insert(&mut self, value: T) {
+        self.insert_at(value, Instant::now());
+    }
+
+    fn insert_at(&mut self, value: T, timestamp: Instant) {
+        let id = self.nodes.len();
+        self.nodes.push(UndoNode {
+            value,
+            parent: Some(self.current),
+            children: Vec::new(),
+            redo_child: None,
+            timestamp,
+        });
+        let parent = &mut self.nodes[self.current];
+        parent.children.push(id);
+        parent.redo_child = Some(id);
+        self.current = id;
+    }
+
+    /// Change the value of the current state, used to group edits into a single undo step
+    ///
+    /// The state keeps its timestamp, so the states stay ordered by time for
+    /// [`UndoTree::earlier_by`]/[`UndoTree::later_by`].
+    pub(crate) fn amend(&mut self) -> &mut T {
+        &mut self.nodes[self.current].value
+    }
+
+    /// Reset the tree to the initial state
+    pub(crate) fn reset(&mut self)
+    where
+        T: Default,
+    {
+        *self = Self::new();
+    }
+
+    /// Return the entry currently being pointed to
+    pub(crate) fn current(&self) -> &T {
+        &self.nodes[self.current].value
+    }
+
+    /// Whether states were derived from the current one
+    pub(crate) fn current_has_children(&self) -> bool {
+        !self.nodes[self.current].children.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn undo_tree(values: &[isize]) -> UndoTree<isize> {
+        let mut tree = UndoTree::new();
+        *tree.amend() = values[0];
+        for value in &values[1..] {
+            tree.insert(*value);
+        }
+        tree
+    }
+
+    #[rstest]
+    #[case(&[1, 2, 3], 1, 2)]
+    #[case(&[1, 2, 3], 3, 1)]
+    #[case(&[1], 1, 1)]
+    fn undo_works(#[case] values: &[isize], #[case] undos: usize, #[case] expected: isize) {
+        let mut tree = undo_tree(values);
+        for _ in 0..undos {
+            tree.undo();
+        }
+        assert_eq!(*tree.current(), expected);
+    }
+
+    #[test]
+    fn redo_works() {
+        let mut tree = undo_tree(&[1, 2, 3]);
+        tree.undo();
+        tree.undo();
+        assert_eq!(*tree.redo(), 2);
+        assert_eq!(*tree.redo(), 3);
+        assert_eq!(*tree.redo(), 3);
+    }
+
+    #[test]
+    fn insert_after_undo_keeps_old_branch() {
+        let mut tree = undo_tree(&[1, 2, 3]);
+        tree.undo();
+        tree.insert(4);
+
+        assert_eq!(tree.num_states(), 4);
+        assert_eq!(tree.node(1).unwrap().children(), &[2, 3]);
+        // Redo follows the newest branch
+        tree.undo();
+        assert_eq!(*tree.redo(), 4);
+        // The discarded branch is still reachable chronologically
+        assert_eq!(*tree.earlier(), 3);
+        assert_eq!(*tree.earlier(), 2);
+        assert_eq!(*tree.later(), 3);
+        assert_eq!(*tree.later(), 4);
+        assert_eq!(*tree.later(), 4);
+    }
+
+    #[test]
+    fn redo_returns_to_branch_left_by_undo() {
+        let mut tree = undo_tree(&[1, 2, 3]);
+        tree.undo();
+        tree.insert(4);
+        tree.earlier();
+        assert_eq!(*tree.current(), 3);
+        tree.undo();
+        assert_eq!(*tree.redo(), 3);
+    }
+
+    #[test]
+    fn amend_keeps_children() {
+        let mut tree = undo_tree(&[1, 2, 3]);
+        tree.undo();
+        *tree.amend() = 5;
+        assert_eq!(*tree.redo(), 3);
+        assert_eq!(*tree.undo(), 5);
+    }
+
+    #[test]
+    fn path_goes_through_the_common_ancestor() {
+        let mut tree = undo_tree(&[1, 2, 3]);
+        tree.undo();
+        tree.insert(4);
+        tree.insert(5);
+
+        assert_eq!(tree.path(2, 4), (vec![2], vec![3, 4]));
+        assert_eq!(tree.path(4, 2), (vec![4, 3], vec![2]));
+        assert_eq!(tree.path(0, 4), (vec![], vec![1, 3, 4]));
+        assert_eq!(tree.path(3, 3), (vec![], vec![]));
+    }
+
+    #[rstest]
+    // Typing on
+    #[case(UndoStep::new(1, "".into(), "b".into(), 0), "abc", 2..3, "", "a")]
+    // Backspacing further
+    #[case(UndoStep::new(1, "c".into(), "".into(), 0), "", 0..0, "a", "ac")]
+    // Deleting further
+    #[case(UndoStep::new(1, "b".into(), "".into(), 0), "a", 1..1, "c", "abc")]
+    // Replacing across the step
+    #[case(UndoStep::new(1, "b".into(), "x".into(), 0), "Y", 0..1, "axc", "abc")]
+    // Apart from the step
+    #[case(UndoStep::new(0, "a".into(), "x".into(), 0), "xbY", 2..3, "c", "abc")]
+    #[case(UndoStep::new(2, "c".into(), "Y".into(), 0), "XbY", 0..1, "a", "abc")]
+    fn compose_works(
+        #[case] mut step: UndoStep,
+        #[case] text: &str,
+        #[case] range: Range<usize>,
+        #[case] removed: &str,
+        #[case] parent_text: &str,
+    ) {
+        step.compose(text, range, removed);
+
+        let mut undone = text.to_owned();
+        undone.replace_range(
+            step.start()..step.start() + step.inserted().len(),
+            step.removed(),
+        );
+        assert_eq!(undone, parent_text);
+        let mut redone = parent_text.to_owned();
+        redone.replace_range(
+            step.start()..step.start() + step.removed().len(),
+            step.inserted(),
+        );
+        assert_eq!(redone, text);
+    }
+
+    #[test]
+    fn time_travel_by_duration() {
+        let start = Instant::now();
+        let mut tree = UndoTree::new();
+        tree.nodes[0].timestamp = start;
+        tree.insert_at(1, start + Duration::from_secs(5));
+        tree.insert_at(2, start + Duration::from_secs(10));
+        tree.insert_at(3, start + Duration::from_secs(30));
+
+        assert_eq!(*tree.earlier_by(Duration::from_secs(15)), 2);
+        assert_eq!(*tree.earlier_by(Duration::from_secs(1)), 1);
+        assert_eq!(*tree.earlier_by(Duration::from_secs(60)), 0);
+        assert_eq!(*tree.later_by(Duration::from_secs(12)), 2);
+        assert_eq!(*tree.later_by(Duration::from_secs(60)), 3);
+    }
+
+    #[test]
+    fn amending_an_older_state_keeps_time_travel_ordered() {
+        let start = Instant::now();
+        let mut tree = UndoTree::new();
+        tree.nodes[0].timestamp = start;
+        tree.insert_at(1, start + Duration::from_secs(5));
+        tree.insert_at(2, start + Duration::from_secs(10));
+        tree.undo();
+        *tree.amend() = 3;
+        assert_eq!(
+            tree.node(1).unwrap().timestamp(),
+            start + Duration::from_secs(5)
+        );
+
+        assert_eq!(*tree.later_by(Duration::from_secs(5)), 2);
+        assert_eq!(*tree.earlier_by(Duration::from_secs(5)), 3);
+    }
+}
@@ -0,0 +1,168 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Defines where words start and end for the word based motions and cuts
+///
+/// Applies to the word commands like [`EditCommand::MoveWordLeft`](crate::EditCommand::MoveWordLeft)
+/// or [`EditCommand::CutWordRight`](crate::EditCommand::CutWordRight).
+/// The WORD variants (e.g. [`EditCommand::MoveBigWordLeft`](crate::EditCommand::MoveBigWordLeft))
+/// always split at whitespace only.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum WordDefinition {
+    /// Word boundaries as defined by the Unicode text segmentation (UAX #29)
+    #[default]
+    Unicode,
+    /// Unicode word boundaries, additionally splitting words at each of the given characters
+    ///
+    /// E.g. `WordDefinition::Separators("/-.=".into())` moves through the components of
+    /// paths and `--option=value` arguments.
+    Separators(String),
+    /// Words consist of alphanumeric characters and the given characters, like `WORDCHARS`
+    /// in zsh. Every other character that is not whitespace forms a word on its own.
+    WordChars(String),
+}
+
+impl WordDefinition {
+    /// Split `text` into words and the whitespace between them
+    ///
+    /// Mirrors [`UnicodeSegmentation::split_word_bound_indices`]: the segments cover all of
+    /// `text` and are returned together with their offset.
+    pub(crate) fn split_word_bound_indices<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> Box<dyn DoubleEndedIterator<Item = (usize, &'a str)> + 'a> {
+        match self {
+            WordDefinition::Unicode => Box::new(text.split_word_bound_indices()),
+            WordDefinition::Separators(separators) => Box::new(
+                text.split_word_bound_indices()
+                    .flat_map(move |(i, word)| split_at_separators(i, word, separators)),
+            ),
+            WordDefinition::WordChars(word_chars) => Box::new(WordCharSegments {
+                text,
+                front: 0,
+                back: text.len(),
+                word_chars,
+            }),
+        }
+    }
+}
+
+fn split_at_separators<'a>(
+    offset: usize,
+    word: &'a str,
+    separators: &str,
+) -> std::vec::IntoIter<(usize, &'a str)> {
+    let mut segments = Vec::new();
+    let mut segment_start = 0;
+    for (i, c) in word.char_indices() {
+        if separators.contains(c) {
+            if segment_start < i {
+                segments.push((offset + segment_start, &word[segment_start..i]));
+            }
+            segment_start = i + c.len_utf8();
+            segments.push((offset + i, &word[i..segment_start]));
+        }
+    }
+    if segment_start < word.len() {
+        segments.push((offset + segment_start, &word[segment_start..]));
+    }
+    segments.into_iter()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Whitespace,
+    Word,
+    Other,
+}
+
+/// Segments of [`WordDefinition::WordChars`] between `front` and `back`
+struct WordCharSegments<'a> {
+    text: &'a str,
+    front: usize,
+    back: usize,
+    word_chars: &'a str,
+}
+
+impl WordCharSegments<'_> {
+    fn class(&self, grapheme: &str) -> CharClass {
+        match grapheme.chars().next() {
+            Some(c) if c.is_whitespace() => CharClass::Whitespace,
+            Some(c) if c.is_alphanumeric() || self.word_chars.contains(c) => CharClass::Word,
+            _ => CharClass::Other,
+        }
+    }
+}
+
+impl<'a> Iterator for WordCharSegments<'a> {
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut graphemes = self.text[self.front..self.back].graphemes(true);
+        let first = graphemes.next()?;
+        let class = self.class(first);
+        let start = self.front;
+        let mut end = start + first.len();
+        if class != CharClass::Other {
+            end += graphemes
+                .take_while(|grapheme| self.class(grapheme) == class)
+                .map(str::len)
+                .sum::<usize>();
+        }
+        self.front = end;
+        Some((start, &self.text[start..end]))
+    }
+}
+
+impl DoubleEndedIterator for WordCharSegments<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let mut graphemes = self.text[self.front..self.back].graphemes(true);
+        let last = graphemes.next_back()?;
+        let class = self.class(last);
+        let end = self.back;
+        let mut start = end - last.len();
+        if class != CharClass::Other {
+            start -= graphemes
+                .rev()
+                .take_while(|grapheme| self.class(grapheme) == class)
+                .map(str::len)
+                .sum::<usize>();
+        }
+        self.back = start;
+        Some((start, &self.text[start..end]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(WordDefinition::Unicode, "cd ~/src/re-line", vec!["cd", " ", "~", "/", "src", "/", "re", "-", "line"])]
+    #[case(WordDefinition::Unicode, "a.b", vec!["a.b"])]
+    #[case(WordDefinition::Separators(".".into()), "a.b c..d", vec!["a", ".", "b", " ", "c", ".", ".", "d"])]
+    #[case(WordDefinition::WordChars("-./".into()), "cd ~/src/re-line", vec!["cd", " ", "~", "/src/re-line"])]
+    #[case(WordDefinition::WordChars(String::new()), "a=b  e\u{301}!!", vec!["a", "=", "b", "  ", "e\u{301}", "!", "!"])]
+    fn segments_cover_text(
+        #[case] word_definition: WordDefinition,
+        #[case] text: &str,
+        #[case] expected: Vec<&str>,
+    ) {
+        let segments: Vec<(usize, &str)> = word_definition.split_word_bound_indices(text).collect();
+        assert_eq!(
+            segments.iter().map(|(_, s)| *s).collect::<Vec<_>>(),
+            expected
+        );
+        for (i, segment) in &segments {
+            assert_eq!(&text[*i..*i + segment.len()], *segment);
+        }
+
+        let mut reversed: Vec<(usize, &str)> = word_definition
+            .split_word_bound_indices(text)
+            .rev()
+            .collect();
+        reversed.reverse();
+        assert_eq!(reversed, segments);
+    }
+}
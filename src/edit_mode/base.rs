@@ -2,6 +2,7 @@ use crate::{
     enums::{ReedlineEvent, ReedlineRawEvent},
     PromptEditMode,
 };
+use std::time::Duration;
 
 /// Define the style of parsing for the edit events
 /// Available default options:
@@ -13,4 +14,28 @@ pub trait EditMode: Send {
 
     /// What to display in the prompt indicator
     fn edit_mode(&self) -> PromptEditMode;
+
+    /// How much longer to wait for the key continuing the pending key sequence
+    ///
+    /// `Some` while the keys pressed so far are bound themselves but also start longer
+    /// bindings. Once the time is up [`EditMode::expire_pending_sequence`] is called.
+    fn pending_sequence_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// The event of the pending key sequence that was not continued in time
+    fn expire_pending_sequence(&mut self) -> ReedlineEvent {
+        ReedlineEvent::None
+    }
+
+    /// Whether consecutive cuts are joined into one entry of the kill ring, as in Emacs
+    fn joins_kills(&self) -> bool {
+        false
+    }
+
+    /// Called by the engine whenever it starts or stops recording a macro
+    ///
+    /// The engine keeps the recording, edit modes only need this for keys that depend on
+    /// it, like Vi's `q`.
+    fn set_macro_recording(&mut self, _recording: bool) {}
 }
@@ -1,6 +1,6 @@
 use crossterm::cursor::SetCursorStyle;
 
-/// Maps cursor shapes to each edit mode (emacs, vi normal & vi insert).
+/// Maps cursor shapes to each edit mode (emacs, vi normal, vi insert & vi replace).
 /// If any of the fields is `None`, the cursor won't get changed by Reedline for that mode.
 #[derive(Default)]
 pub struct CursorConfig {
@@ -8,6 +8,8 @@ pub struct CursorConfig {
     pub vi_insert: Option<SetCursorStyle>,
     /// The cursor to be used when in vi normal mode
     pub vi_normal: Option<SetCursorStyle>,
+    /// The cursor to be used when in vi replace mode
+    pub vi_replace: Option<SetCursorStyle>,
     /// The cursor to be used when in emacs mode
     pub emacs: Option<SetCursorStyle>,
 }
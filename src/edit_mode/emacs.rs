@@ -2,7 +2,7 @@ use crate::{
     edit_mode::{
         keybindings::{
             add_common_control_bindings, add_common_edit_bindings, add_common_navigation_bindings,
-            add_common_selection_bindings, edit_bind, Keybindings,
+            add_common_selection_bindings, edit_bind, KeyCombination, Keybindings,
         },
         EditMode,
     },
@@ -10,8 +10,13 @@ use crate::{
     PromptEditMode,
 };
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use std::time::{Duration, Instant};
 
 /// Returns the current default emacs keybindings
+///
+/// `Ctrl-u` starts a numeric argument like Emacs `C-u`, so `Ctrl-u 4 Ctrl-f` moves four
+/// characters right. Cutting to the start of the buffer, which `Ctrl-u` does in readline,
+/// moves to `Ctrl-x Backspace`.
 pub fn default_emacs_keybindings() -> Keybindings {
     use EditCommand as EC;
     use KeyCode as KC;
@@ -53,10 +58,72 @@ pub fn default_emacs_keybindings() -> Keybindings {
     );
     kb.add_binding(KM::CONTROL, KC::Char('w'), edit_bind(EC::CutWordLeft));
     kb.add_binding(KM::CONTROL, KC::Char('k'), edit_bind(EC::CutToLineEnd));
-    kb.add_binding(KM::CONTROL, KC::Char('u'), edit_bind(EC::CutFromStart));
     kb.add_binding(KM::ALT, KC::Char('d'), edit_bind(EC::CutWordRight));
+    kb.add_binding(KM::ALT, KC::Char('y'), edit_bind(EC::YankPop));
+    kb.add_binding(
+        KM::CONTROL | KM::SHIFT,
+        KC::Backspace,
+        edit_bind(EC::KillWholeLine),
+    );
     // Edits
     kb.add_binding(KM::CONTROL, KC::Char('t'), edit_bind(EC::SwapGraphemes));
+    // Numeric argument, also typed with Alt and the digits
+    kb.add_binding(KM::CONTROL, KC::Char('u'), ReedlineEvent::UniversalArgument);
+    // Ctrl-x sequences
+    let ctrl_x = KeyCombination {
+        modifier: KM::CONTROL,
+        key_code: KC::Char('x'),
+    };
+    kb.add_sequence_binding(
+        &[
+            ctrl_x,
+            KeyCombination {
+                modifier: KM::CONTROL,
+                key_code: KC::Char('e'),
+            },
+        ],
+        ReedlineEvent::OpenEditor,
+    );
+    kb.add_sequence_binding(
+        &[
+            ctrl_x,
+            KeyCombination {
+                modifier: KM::NONE,
+                key_code: KC::Char('u'),
+            },
+        ],
+        edit_bind(EC::Undo),
+    );
+    kb.add_sequence_binding(
+        &[
+            ctrl_x,
+            KeyCombination {
+                modifier: KM::NONE,
+                key_code: KC::Backspace,
+            },
+        ],
+        edit_bind(EC::CutFromStart),
+    );
+    kb.add_sequence_binding(
+        &[
+            ctrl_x,
+            KeyCombination {
+                modifier: KM::CONTROL,
+                key_code: KC::Char('t'),
+            },
+        ],
+        edit_bind(EC::TransposeLines),
+    );
+    kb.add_sequence_binding(
+        &[
+            ctrl_x,
+            KeyCombination {
+                modifier: KM::CONTROL,
+                key_code: KC::Char('o'),
+            },
+        ],
+        edit_bind(EC::DeleteBlankLines),
+    );
 
     // *** ALT ***
     // Moves
@@ -97,21 +164,193 @@ pub fn default_emacs_keybindings() -> Keybindings {
     // Case changes
     kb.add_binding(KM::ALT, KC::Char('u'), edit_bind(EC::UppercaseWord));
     kb.add_binding(KM::ALT, KC::Char('l'), edit_bind(EC::LowercaseWord));
-    kb.add_binding(KM::ALT, KC::Char('c'), edit_bind(EC::CapitalizeChar));
+    kb.add_binding(KM::ALT, KC::Char('c'), edit_bind(EC::CapitalizeWord));
+    // Whitespace
+    kb.add_binding(
+        KM::ALT,
+        KC::Char('\\'),
+        edit_bind(EC::DeleteHorizontalSpace),
+    );
+    kb.add_binding(KM::ALT, KC::Char(' '), edit_bind(EC::JustOneSpace));
 
     kb
 }
 
+/// How long [`Emacs`] waits by default for the key continuing a sequence that is also
+/// bound by itself
+const DEFAULT_SEQUENCE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// The largest numeric argument, bounding how often a command is repeated
+const MAX_NUMERIC_ARGUMENT: usize = 1000;
+
+/// The numeric argument typed before a command, like `Alt-3`, or `4` after the universal argument
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct NumericArgument {
+    /// The digits typed so far
+    digits: Option<usize>,
+    /// The argument without digits, four to the power of the universal arguments typed
+    multiplier: usize,
+    negative: bool,
+    /// Whether digits and `-` are typed without `Alt`, after the universal argument
+    universal: bool,
+}
+
+impl Default for NumericArgument {
+    fn default() -> Self {
+        NumericArgument {
+            digits: None,
+            multiplier: 1,
+            negative: false,
+            universal: false,
+        }
+    }
+}
+
+impl NumericArgument {
+    /// Add the typed digit or `-` to the argument, `false` if it does not continue it
+    fn push(&mut self, c: char) -> bool {
+        match (c.to_digit(10), self.digits) {
+            (Some(digit), digits) => {
+                let digits = digits.unwrap_or(0) * 10 + digit as usize;
+                self.digits = Some(digits.min(MAX_NUMERIC_ARGUMENT));
+                true
+            }
+            (None, None) if c == '-' && !self.negative => {
+                self.negative = true;
+                true
+            }
+            (None, _) => false,
+        }
+    }
+
+    fn universal(&mut self) {
+        if self.digits.is_none() {
+            self.multiplier = (self.multiplier * 4).min(MAX_NUMERIC_ARGUMENT);
+        }
+        self.universal = true;
+    }
+
+    /// Repeat the edits and moves of the event, reversing their direction when negative
+    fn apply(self, event: ReedlineEvent) -> ReedlineEvent {
+        // A `-` without digits stands for -1, also after the universal argument
+        let count = match (self.digits, self.negative) {
+            (Some(digits), _) => digits,
+            (None, true) => 1,
+            (None, false) => self.multiplier,
+        };
+        repeat_event(event, count, self.negative)
+    }
+}
+
+fn repeat_event(event: ReedlineEvent, count: usize, reverse: bool) -> ReedlineEvent {
+    let repeated = |event: ReedlineEvent| ReedlineEvent::Multiple(vec![event; count]);
+    match event {
+        ReedlineEvent::Edit(commands) => {
+            let commands: Vec<EditCommand> = commands
+                .into_iter()
+                .map(|command| {
+                    if reverse {
+                        reverse_command(command)
+                    } else {
+                        command
+                    }
+                })
+                .collect();
+            ReedlineEvent::Edit(
+                commands
+                    .iter()
+                    .cycle()
+                    .take(commands.len() * count)
+                    .cloned()
+                    .collect(),
+            )
+        }
+        ReedlineEvent::Left | ReedlineEvent::Right if reverse => {
+            repeated(if event == ReedlineEvent::Left {
+                ReedlineEvent::Right
+            } else {
+                ReedlineEvent::Left
+            })
+        }
+        ReedlineEvent::MenuLeft | ReedlineEvent::MenuRight if reverse => {
+            repeated(if event == ReedlineEvent::MenuLeft {
+                ReedlineEvent::MenuRight
+            } else {
+                ReedlineEvent::MenuLeft
+            })
+        }
+        ReedlineEvent::Left
+        | ReedlineEvent::Right
+        | ReedlineEvent::Up
+        | ReedlineEvent::Down
+        | ReedlineEvent::MenuLeft
+        | ReedlineEvent::MenuRight
+        | ReedlineEvent::MenuUp
+        | ReedlineEvent::MenuDown => repeated(event),
+        ReedlineEvent::UntilFound(events) => ReedlineEvent::UntilFound(
+            events
+                .into_iter()
+                .map(|event| repeat_event(event, count, reverse))
+                .collect(),
+        ),
+        ReedlineEvent::Multiple(events) => ReedlineEvent::Multiple(
+            events
+                .into_iter()
+                .map(|event| repeat_event(event, count, reverse))
+                .collect(),
+        ),
+        event => event,
+    }
+}
+
+/// The command acting in the opposite direction, for negative numeric arguments
+fn reverse_command(command: EditCommand) -> EditCommand {
+    use EditCommand as EC;
+
+    match command {
+        EC::MoveLeft { select } => EC::MoveRight { select },
+        EC::MoveRight { select } => EC::MoveLeft { select },
+        EC::MoveWordLeft { select } => EC::MoveWordRight { select },
+        EC::MoveWordRight { select } => EC::MoveWordLeft { select },
+        EC::MoveBigWordLeft { select } => EC::MoveBigWordRightStart { select },
+        EC::MoveBigWordRightStart { select } => EC::MoveBigWordLeft { select },
+        // The word ends and starts to the right all reverse to the word start to the left
+        EC::MoveWordRightStart { select } | EC::MoveWordRightEnd { select } => {
+            EC::MoveWordLeft { select }
+        }
+        EC::MoveBigWordRightEnd { select } => EC::MoveBigWordLeft { select },
+        EC::Backspace => EC::Delete,
+        EC::Delete => EC::Backspace,
+        EC::BackspaceWord => EC::DeleteWord,
+        EC::DeleteWord => EC::BackspaceWord,
+        EC::CutWordLeft => EC::CutWordRight,
+        EC::CutWordRight => EC::CutWordLeft,
+        EC::CutBigWordLeft => EC::CutBigWordRight,
+        EC::CutBigWordRight => EC::CutBigWordLeft,
+        EC::CutWordRightToNext => EC::CutWordLeft,
+        EC::CutBigWordRightToNext => EC::CutBigWordLeft,
+        EC::CutFromLineStart => EC::CutToLineEnd,
+        EC::CutToLineEnd => EC::CutFromLineStart,
+        EC::CutFromStart => EC::CutToEnd,
+        EC::CutToEnd => EC::CutFromStart,
+        // Line moves and whole line edits have no direction along the line to reverse
+        command => command,
+    }
+}
+
 /// This parses the incoming Events like a emacs style-editor
 pub struct Emacs {
     keybindings: Keybindings,
+    // The keys pressed so far of a bound key sequence, like the `Ctrl-x` of `Ctrl-x Ctrl-e`
+    pending_sequence: Vec<KeyCombination>,
+    pending_since: Option<Instant>,
+    sequence_timeout: Duration,
+    numeric_argument: Option<NumericArgument>,
 }
 
 impl Default for Emacs {
     fn default() -> Self {
-        Emacs {
-            keybindings: default_emacs_keybindings(),
-        }
+        Emacs::new(default_emacs_keybindings())
     }
 }
 
@@ -120,49 +359,17 @@ impl EditMode for Emacs {
         match event.into() {
             Event::Key(KeyEvent {
                 code, modifiers, ..
-            }) => match (modifiers, code) {
-                (modifier, KeyCode::Char(c)) => {
-                    // Note. The modifier can also be a combination of modifiers, for
-                    // example:
-                    //     KeyModifiers::CONTROL | KeyModifiers::ALT
-                    //     KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT
-                    //
-                    // Mixed modifiers are used by non american keyboards that have extra
-                    // keys like 'alt gr'. Keep this in mind if in the future there are
-                    // cases where an event is not being captured
-                    let c = match modifier {
-                        KeyModifiers::NONE => c,
-                        _ => c.to_ascii_lowercase(),
-                    };
-
-                    self.keybindings
-                        .find_binding(modifier, KeyCode::Char(c))
-                        .unwrap_or_else(|| {
-                            if modifier == KeyModifiers::NONE
-                                || modifier == KeyModifiers::SHIFT
-                                || modifier == KeyModifiers::CONTROL | KeyModifiers::ALT
-                                || modifier
-                                    == KeyModifiers::CONTROL
-                                        | KeyModifiers::ALT
-                                        | KeyModifiers::SHIFT
-                            {
-                                ReedlineEvent::Edit(vec![EditCommand::InsertChar(
-                                    if modifier == KeyModifiers::SHIFT {
-                                        c.to_ascii_uppercase()
-                                    } else {
-                                        c
-                                    },
-                                )])
-                            } else {
-                                ReedlineEvent::None
-                            }
-                        })
-                }
-                _ => self
-                    .keybindings
-                    .find_binding(modifiers, code)
-                    .unwrap_or(ReedlineEvent::None),
-            },
+            }) => {
+                let key_code = match (modifiers, code) {
+                    (KeyModifiers::NONE, _) => code,
+                    (_, KeyCode::Char(c)) => KeyCode::Char(c.to_ascii_lowercase()),
+                    _ => code,
+                };
+                self.parse_key(KeyCombination {
+                    modifier: modifiers,
+                    key_code,
+                })
+            }
 
             Event::Mouse(_) => ReedlineEvent::Mouse,
             Event::Resize(width, height) => ReedlineEvent::Resize(width, height),
@@ -177,12 +384,174 @@ impl EditMode for Emacs {
     fn edit_mode(&self) -> PromptEditMode {
         PromptEditMode::Emacs
     }
+
+    fn pending_sequence_timeout(&self) -> Option<Duration> {
+        self.keybindings
+            .find_sequence_binding(&self.pending_sequence)
+            .and(self.pending_since)
+            .map(|since| self.sequence_timeout.saturating_sub(since.elapsed()))
+    }
+
+    fn expire_pending_sequence(&mut self) -> ReedlineEvent {
+        let event = self.take_pending_sequence();
+        self.apply_numeric_argument(event)
+    }
+
+    fn joins_kills(&self) -> bool {
+        true
+    }
 }
 
 impl Emacs {
     /// Emacs style input parsing constructor if you want to use custom keybindings
     pub const fn new(keybindings: Keybindings) -> Self {
-        Emacs { keybindings }
+        Emacs {
+            keybindings,
+            pending_sequence: Vec::new(),
+            pending_since: None,
+            sequence_timeout: DEFAULT_SEQUENCE_TIMEOUT,
+            numeric_argument: None,
+        }
+    }
+
+    /// How long to wait for the key continuing a key sequence whose keys so far are bound
+    /// themselves, e.g. `Ctrl-x` bound along with `Ctrl-x Ctrl-e`
+    ///
+    /// Defaults to 500 ms. Sequences whose keys so far are not bound wait for the next key
+    /// indefinitely.
+    #[must_use]
+    pub fn with_sequence_timeout(mut self, timeout: Duration) -> Self {
+        self.sequence_timeout = timeout;
+        self
+    }
+
+    /// The event of the key, taking the numeric argument typed before it into account
+    fn parse_key(&mut self, combination: KeyCombination) -> ReedlineEvent {
+        if self.pending_sequence.is_empty() && self.parse_numeric_argument(combination) {
+            return ReedlineEvent::None;
+        }
+        let event = self.parse_binding(combination);
+        if event == ReedlineEvent::UniversalArgument {
+            self.numeric_argument
+                .get_or_insert_with(NumericArgument::default)
+                .universal();
+            return ReedlineEvent::None;
+        }
+        if !self.pending_sequence.is_empty() {
+            return event;
+        }
+        self.apply_numeric_argument(event)
+    }
+
+    /// Continue the numeric argument with an `Alt` digit, or a digit after the universal argument
+    fn parse_numeric_argument(&mut self, combination: KeyCombination) -> bool {
+        let c = match combination.key_code {
+            KeyCode::Char(c @ ('0'..='9' | '-')) => c,
+            _ => return false,
+        };
+        let continues = match (combination.modifier, self.numeric_argument) {
+            (KeyModifiers::ALT, _) => self
+                .keybindings
+                .find_binding(combination.modifier, combination.key_code)
+                .is_none(),
+            (KeyModifiers::NONE, Some(argument)) => argument.universal,
+            _ => false,
+        };
+        continues
+            && self
+                .numeric_argument
+                .get_or_insert_with(NumericArgument::default)
+                .push(c)
+    }
+
+    fn apply_numeric_argument(&mut self, event: ReedlineEvent) -> ReedlineEvent {
+        match self.numeric_argument.take() {
+            Some(argument) => argument.apply(event),
+            None => event,
+        }
+    }
+
+    fn take_pending_sequence(&mut self) -> ReedlineEvent {
+        let event = self
+            .keybindings
+            .find_sequence_binding(&self.pending_sequence)
+            .unwrap_or(ReedlineEvent::None);
+        self.pending_sequence.clear();
+        self.pending_since = None;
+        event
+    }
+
+    /// Look the key up in the prefix tree of key sequences, continuing the pending sequence
+    fn parse_binding(&mut self, combination: KeyCombination) -> ReedlineEvent {
+        if !self.pending_sequence.is_empty() {
+            if self.pending_sequence_timeout() == Some(Duration::ZERO) {
+                let event = self.take_pending_sequence();
+                return ReedlineEvent::Multiple(vec![event, self.parse_key(combination)]);
+            }
+            self.pending_sequence.push(combination);
+            if self.keybindings.is_sequence_prefix(&self.pending_sequence) {
+                self.pending_since = Some(Instant::now());
+                return ReedlineEvent::None;
+            }
+            if let Some(event) = self
+                .keybindings
+                .find_sequence_binding(&self.pending_sequence)
+            {
+                self.pending_sequence.clear();
+                self.pending_since = None;
+                return event;
+            }
+            // The keys so far take effect on their own if bound, the key is handled anew
+            self.pending_sequence.pop();
+            return match self.take_pending_sequence() {
+                ReedlineEvent::None => ReedlineEvent::None,
+                event => ReedlineEvent::Multiple(vec![event, self.parse_key(combination)]),
+            };
+        }
+
+        if self.keybindings.is_sequence_prefix(&[combination]) {
+            self.pending_sequence.push(combination);
+            self.pending_since = Some(Instant::now());
+            return ReedlineEvent::None;
+        }
+
+        let KeyCombination { modifier, key_code } = combination;
+        match key_code {
+            KeyCode::Char(c) => {
+                // Note. The modifier can also be a combination of modifiers, for
+                // example:
+                //     KeyModifiers::CONTROL | KeyModifiers::ALT
+                //     KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT
+                //
+                // Mixed modifiers are used by non american keyboards that have extra
+                // keys like 'alt gr'. Keep this in mind if in the future there are
+                // cases where an event is not being captured
+                self.keybindings
+                    .find_binding(modifier, key_code)
+                    .unwrap_or_else(|| {
+                        if modifier == KeyModifiers::NONE
+                            || modifier == KeyModifiers::SHIFT
+                            || modifier == KeyModifiers::CONTROL | KeyModifiers::ALT
+                            || modifier
+                                == KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT
+                        {
+                            ReedlineEvent::Edit(vec![EditCommand::InsertChar(
+                                if modifier == KeyModifiers::SHIFT {
+                                    c.to_ascii_uppercase()
+                                } else {
+                                    c
+                                },
+                            )])
+                        } else {
+                            ReedlineEvent::None
+                        }
+                    })
+            }
+            _ => self
+                .keybindings
+                .find_binding(modifier, key_code)
+                .unwrap_or(ReedlineEvent::None),
+        }
     }
 }
 
@@ -190,6 +559,7 @@ impl Emacs {
 mod test {
     use super::*;
     use pretty_assertions::assert_eq;
+    use rstest::rstest;
 
     #[test]
     fn ctrl_l_leads_to_clear_screen_event() {
@@ -288,4 +658,246 @@ mod test {
             ReedlineEvent::Edit(vec![EditCommand::InsertChar('😀')])
         );
     }
+
+    fn key(modifier: KeyModifiers, c: char) -> KeyCombination {
+        KeyCombination {
+            modifier,
+            key_code: KeyCode::Char(c),
+        }
+    }
+
+    fn press(emacs: &mut Emacs, combination: KeyCombination) -> ReedlineEvent {
+        emacs.parse_event(
+            ReedlineRawEvent::try_from(Event::Key(KeyEvent::new(
+                combination.key_code,
+                combination.modifier,
+            )))
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn key_sequences_wait_for_their_last_key() {
+        let mut emacs = Emacs::default();
+        let ctrl_x = key(KeyModifiers::CONTROL, 'x');
+
+        assert_eq!(press(&mut emacs, ctrl_x), ReedlineEvent::None);
+        assert_eq!(emacs.pending_sequence_timeout(), None);
+        assert_eq!(
+            press(&mut emacs, key(KeyModifiers::CONTROL, 'e')),
+            ReedlineEvent::OpenEditor
+        );
+
+        // A key not continuing the sequence is dropped along with it
+        assert_eq!(press(&mut emacs, ctrl_x), ReedlineEvent::None);
+        assert_eq!(
+            press(&mut emacs, key(KeyModifiers::NONE, 'z')),
+            ReedlineEvent::None
+        );
+        assert_eq!(
+            press(&mut emacs, key(KeyModifiers::NONE, 'z')),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('z')])
+        );
+    }
+
+    #[test]
+    fn bound_prefix_of_a_key_sequence_takes_effect_when_not_continued() {
+        let mut keybindings = default_emacs_keybindings();
+        let ctrl_c = key(KeyModifiers::CONTROL, 'c');
+        keybindings.add_sequence_binding(&[ctrl_c, ctrl_c], ReedlineEvent::Submit);
+        let mut emacs = Emacs::new(keybindings).with_sequence_timeout(Duration::from_secs(60));
+
+        assert_eq!(press(&mut emacs, ctrl_c), ReedlineEvent::None);
+        assert!(emacs.pending_sequence_timeout().is_some());
+        assert_eq!(press(&mut emacs, ctrl_c), ReedlineEvent::Submit);
+
+        assert_eq!(press(&mut emacs, ctrl_c), ReedlineEvent::None);
+        assert_eq!(
+            press(&mut emacs, key(KeyModifiers::NONE, 'a')),
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::CtrlC,
+                ReedlineEvent::Edit(vec![EditCommand::InsertChar('a')])
+            ])
+        );
+
+        assert_eq!(press(&mut emacs, ctrl_c), ReedlineEvent::None);
+        assert_eq!(emacs.expire_pending_sequence(), ReedlineEvent::CtrlC);
+        assert_eq!(emacs.pending_sequence_timeout(), None);
+    }
+
+    #[test]
+    fn bound_prefix_of_a_key_sequence_times_out() {
+        let mut keybindings = default_emacs_keybindings();
+        let ctrl_c = key(KeyModifiers::CONTROL, 'c');
+        keybindings.add_sequence_binding(&[ctrl_c, ctrl_c], ReedlineEvent::Submit);
+        let mut emacs = Emacs::new(keybindings).with_sequence_timeout(Duration::ZERO);
+
+        assert_eq!(press(&mut emacs, ctrl_c), ReedlineEvent::None);
+        assert_eq!(emacs.pending_sequence_timeout(), Some(Duration::ZERO));
+        assert_eq!(
+            press(&mut emacs, ctrl_c),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::CtrlC, ReedlineEvent::None])
+        );
+    }
+
+    #[test]
+    fn removing_key_sequences_keeps_the_others() {
+        let mut keybindings = default_emacs_keybindings();
+        let ctrl_x = key(KeyModifiers::CONTROL, 'x');
+        let ctrl_e = key(KeyModifiers::CONTROL, 'e');
+        let u = key(KeyModifiers::NONE, 'u');
+
+        assert_eq!(
+            keybindings.remove_sequence_binding(&[ctrl_x, ctrl_e]),
+            Some(ReedlineEvent::OpenEditor)
+        );
+        assert_eq!(keybindings.find_sequence_binding(&[ctrl_x, ctrl_e]), None);
+        assert!(keybindings.is_sequence_prefix(&[ctrl_x]));
+
+        assert_eq!(
+            keybindings.remove_sequence_binding(&[ctrl_x, u]),
+            Some(ReedlineEvent::Edit(vec![EditCommand::Undo]))
+        );
+        assert!(keybindings.is_sequence_prefix(&[ctrl_x]));
+
+        let remaining: Vec<Vec<KeyCombination>> = keybindings
+            .get_keybinding_sequences()
+            .into_iter()
+            .map(|(sequence, _)| sequence)
+            .filter(|sequence| sequence.len() > 1)
+            .collect();
+        for sequence in remaining {
+            assert!(keybindings.remove_sequence_binding(&sequence).is_some());
+        }
+        assert!(!keybindings.is_sequence_prefix(&[ctrl_x]));
+        assert!(keybindings
+            .get_keybinding_sequences()
+            .iter()
+            .all(|(sequence, _)| sequence.len() == 1));
+    }
+
+    #[test]
+    fn alt_digits_repeat_the_following_edit() {
+        let mut emacs = Emacs::default();
+
+        assert_eq!(
+            press(&mut emacs, key(KeyModifiers::ALT, '1')),
+            ReedlineEvent::None
+        );
+        assert_eq!(
+            press(&mut emacs, key(KeyModifiers::ALT, '2')),
+            ReedlineEvent::None
+        );
+        assert_eq!(
+            press(&mut emacs, key(KeyModifiers::NONE, 'a')),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('a'); 12])
+        );
+        // The argument only applies to the next command
+        assert_eq!(
+            press(&mut emacs, key(KeyModifiers::NONE, 'a')),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('a')])
+        );
+    }
+
+    #[test]
+    fn ctrl_u_multiplies_by_four_unless_followed_by_digits() {
+        let mut emacs = Emacs::default();
+        let ctrl_u = key(KeyModifiers::CONTROL, 'u');
+
+        press(&mut emacs, ctrl_u);
+        press(&mut emacs, ctrl_u);
+        assert_eq!(
+            press(
+                &mut emacs,
+                KeyCombination {
+                    modifier: KeyModifiers::NONE,
+                    key_code: KeyCode::Backspace,
+                }
+            ),
+            ReedlineEvent::Edit(vec![EditCommand::Backspace; 16])
+        );
+
+        press(&mut emacs, ctrl_u);
+        press(&mut emacs, key(KeyModifiers::NONE, '3'));
+        assert_eq!(
+            press(&mut emacs, key(KeyModifiers::CONTROL, 'f')),
+            ReedlineEvent::UntilFound(vec![
+                ReedlineEvent::HistoryHintComplete,
+                ReedlineEvent::Multiple(vec![ReedlineEvent::MenuRight; 3]),
+                ReedlineEvent::Multiple(vec![ReedlineEvent::Right; 3]),
+            ])
+        );
+    }
+
+    #[test]
+    fn negative_arguments_reverse_the_direction() {
+        let mut emacs = Emacs::default();
+
+        press(&mut emacs, key(KeyModifiers::ALT, '-'));
+        press(&mut emacs, key(KeyModifiers::ALT, '2'));
+        assert_eq!(
+            press(&mut emacs, key(KeyModifiers::ALT, 'b')),
+            ReedlineEvent::Edit(vec![EditCommand::MoveWordRight { select: false }; 2])
+        );
+
+        press(&mut emacs, key(KeyModifiers::CONTROL, 'u'));
+        press(&mut emacs, key(KeyModifiers::NONE, '-'));
+        assert_eq!(
+            press(&mut emacs, key(KeyModifiers::CONTROL, 'b')),
+            ReedlineEvent::UntilFound(vec![
+                ReedlineEvent::Multiple(vec![ReedlineEvent::MenuRight]),
+                ReedlineEvent::Multiple(vec![ReedlineEvent::Right]),
+            ])
+        );
+
+        press(&mut emacs, key(KeyModifiers::ALT, '-'));
+        assert_eq!(
+            press(&mut emacs, key(KeyModifiers::CONTROL, 'k')),
+            ReedlineEvent::Edit(vec![EditCommand::CutFromLineStart])
+        );
+    }
+
+    #[rstest]
+    #[case(EditCommand::MoveWordRightEnd { select: false }, EditCommand::MoveWordLeft { select: false })]
+    #[case(EditCommand::MoveBigWordRightEnd { select: true }, EditCommand::MoveBigWordLeft { select: true })]
+    #[case(EditCommand::CutWordRightToNext, EditCommand::CutWordLeft)]
+    #[case(EditCommand::CutBigWordRightToNext, EditCommand::CutBigWordLeft)]
+    #[case(EditCommand::CutFromStart, EditCommand::CutToEnd)]
+    #[case(EditCommand::CutCurrentLine, EditCommand::CutCurrentLine)]
+    fn test_reverse_command(#[case] command: EditCommand, #[case] expected: EditCommand) {
+        assert_eq!(reverse_command(command), expected);
+    }
+
+    #[test]
+    fn ctrl_x_backspace_cuts_to_the_start() {
+        let mut emacs = Emacs::default();
+
+        press(&mut emacs, key(KeyModifiers::CONTROL, 'x'));
+        assert_eq!(
+            press(
+                &mut emacs,
+                KeyCombination {
+                    modifier: KeyModifiers::NONE,
+                    key_code: KeyCode::Backspace,
+                }
+            ),
+            ReedlineEvent::Edit(vec![EditCommand::CutFromStart])
+        );
+    }
+
+    #[test]
+    fn digits_after_a_key_sequence_prefix_are_not_an_argument() {
+        let mut emacs = Emacs::default();
+
+        press(&mut emacs, key(KeyModifiers::CONTROL, 'u'));
+        press(&mut emacs, key(KeyModifiers::CONTROL, 'x'));
+        assert_eq!(
+            press(&mut emacs, key(KeyModifiers::NONE, 'u')),
+            ReedlineEvent::Edit(vec![EditCommand::Undo; 4])
+        );
+        assert_eq!(
+            press(&mut emacs, key(KeyModifiers::NONE, '5')),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('5')])
+        );
+    }
 }
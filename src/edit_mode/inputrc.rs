@@ -0,0 +1,904 @@
+//! Import of GNU Readline `inputrc` files, see [`Inputrc`]
+use crate::{
+    default_emacs_keybindings, default_vi_insert_keybindings, default_vi_normal_keybindings,
+    edit_mode::keybindings::{edit_bind, KeyCombination, Keybindings},
+    EditCommand, EditMode, Emacs, KeymapError, ReedlineEvent, Vi,
+};
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::path::PathBuf;
+
+/// How deep `$include` directives may nest, guarding against files including themselves
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// The editing mode chosen by `set editing-mode`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputrcEditingMode {
+    /// `set editing-mode emacs`, the default
+    #[default]
+    Emacs,
+    /// `set editing-mode vi`
+    Vi,
+}
+
+/// Keybindings and editing mode imported from a GNU Readline `inputrc` file
+///
+/// The bindings of the file are added to the default keybindings:
+///
+/// - Key sequences like `"\C-x\C-e"` or `"\e[A"` and key names like `Control-u` or
+///   `M-DEL`, bound to readline functions or to macros inserting text
+/// - `set editing-mode` and `set keymap` choosing the keybindings that are changed
+/// - `$if mode=`, `$if term=` and `$if` with the name of the application, `$else` and
+///   `$endif`
+/// - `$include` reading another file
+///
+/// Readline functions and variables without a counterpart are reported in
+/// [`Inputrc::issues`] along with invalid lines, which are skipped.
+///
+/// ```
+/// use reedline::{Inputrc, InputrcEditingMode, Reedline};
+///
+/// let inputrc = Inputrc::parse(
+///     r#"
+/// set editing-mode vi
+/// $if mode=vi
+/// "\C-l": clear-screen
+/// $endif
+/// "#,
+///     "myshell",
+/// );
+/// assert_eq!(inputrc.editing_mode, InputrcEditingMode::Vi);
+/// assert!(inputrc.issues.is_empty());
+/// let line_editor = Reedline::create().with_edit_mode(inputrc.edit_mode());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Inputrc {
+    /// The editing mode chosen by the last `set editing-mode`
+    pub editing_mode: InputrcEditingMode,
+    /// Keybindings of the `emacs` keymap
+    pub emacs_keybindings: Keybindings,
+    /// Keybindings of the `vi-insert` keymap
+    pub vi_insert_keybindings: Keybindings,
+    /// Keybindings of the `vi-command` keymap
+    pub vi_normal_keybindings: Keybindings,
+    /// Unsupported functions, variables and directives and invalid lines of the file
+    ///
+    /// Issues of an included file are reported at the line of its `$include`, their
+    /// message names the file and the line within it.
+    pub issues: Vec<KeymapError>,
+}
+
+impl Inputrc {
+    /// Import the contents of an `inputrc` file
+    ///
+    /// `application` is the name `$if` directives compare against, like `Bash`.
+    pub fn parse(inputrc: &str, application: &str) -> Self {
+        let mut importer = Importer {
+            inputrc: Inputrc {
+                editing_mode: InputrcEditingMode::Emacs,
+                emacs_keybindings: default_emacs_keybindings(),
+                vi_insert_keybindings: default_vi_insert_keybindings(),
+                vi_normal_keybindings: default_vi_normal_keybindings(),
+                issues: vec![],
+            },
+            application,
+            keymap: Keymap::Emacs,
+            depth: 0,
+        };
+        importer.import(inputrc);
+        importer.inputrc
+    }
+
+    /// The edit mode of the chosen editing mode with the imported keybindings
+    pub fn edit_mode(&self) -> Box<dyn EditMode> {
+        match self.editing_mode {
+            InputrcEditingMode::Emacs => Box::new(Emacs::new(self.emacs_keybindings.clone())),
+            InputrcEditingMode::Vi => Box::new(Vi::new(
+                self.vi_insert_keybindings.clone(),
+                self.vi_normal_keybindings.clone(),
+            )),
+        }
+    }
+}
+
+/// The keymap bindings are added to, chosen by `set keymap`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Keymap {
+    Emacs,
+    /// The keys following `Esc`, bound with `Alt` in reedline
+    EmacsMeta,
+    /// The keys following `Ctrl-x`
+    EmacsCtlx,
+    ViInsert,
+    ViCommand,
+}
+
+struct Importer<'a> {
+    inputrc: Inputrc,
+    application: &'a str,
+    keymap: Keymap,
+    depth: usize,
+}
+
+/// Whether the lines of an `$if` are read
+struct Condition {
+    /// Number of the line of the `$if`
+    line: usize,
+    active: bool,
+    /// Whether the `$if` or `$else` being read is the one taking effect
+    holds: bool,
+}
+
+impl Importer<'_> {
+    fn import(&mut self, inputrc: &str) {
+        let mut conditions: Vec<Condition> = vec![];
+        for (index, line) in inputrc.lines().enumerate() {
+            let line = line.trim();
+            let active = conditions.last().map_or(true, |condition| condition.holds);
+            let result = if let Some(directive) = line.strip_prefix('$') {
+                let (name, argument) = split_word(directive);
+                match name {
+                    "if" => {
+                        let holds = active && self.condition_holds(argument);
+                        conditions.push(Condition {
+                            line: index + 1,
+                            active,
+                            holds,
+                        });
+                        Ok(())
+                    }
+                    "else" => match conditions.last_mut() {
+                        Some(condition) => {
+                            condition.holds = condition.active && !condition.holds;
+                            Ok(())
+                        }
+                        None => Err("`$else` without `$if`".to_string()),
+                    },
+                    "endif" => match conditions.pop() {
+                        Some(_) => Ok(()),
+                        None => Err("`$endif` without `$if`".to_string()),
+                    },
+                    "include" if active => self.include(argument, index + 1),
+                    "include" => Ok(()),
+                    _ => Err(format!("unknown directive `${name}`")),
+                }
+            } else if !active || line.is_empty() || line.starts_with('#') {
+                Ok(())
+            } else if let Some(variable) = line.strip_prefix("set ") {
+                self.set_variable(variable)
+            } else {
+                self.bind(line)
+            };
+            if let Err(message) = result {
+                self.inputrc.issues.push(KeymapError {
+                    line: index + 1,
+                    message,
+                });
+            }
+        }
+        for condition in conditions {
+            self.inputrc.issues.push(KeymapError {
+                line: condition.line,
+                message: "`$if` without `$endif`".to_string(),
+            });
+        }
+    }
+
+    fn condition_holds(&self, condition: &str) -> bool {
+        match condition.split_once('=') {
+            Some((name, value)) if !name.ends_with(['=', '!', '<', '>']) => {
+                match name.trim() {
+                    "mode" => match value.trim() {
+                        "emacs" => self.inputrc.editing_mode == InputrcEditingMode::Emacs,
+                        "vi" => self.inputrc.editing_mode == InputrcEditingMode::Vi,
+                        _ => false,
+                    },
+                    // Like readline, either the whole terminal name or the part before `-`
+                    "term" => std::env::var("TERM").map_or(false, |term| {
+                        let value = value.trim();
+                        term == value || term.split('-').next() == Some(value)
+                    }),
+                    _ => false,
+                }
+            }
+            _ => condition.trim().eq_ignore_ascii_case(self.application),
+        }
+    }
+
+    /// Import the file, reporting its issues at the line of the `$include` with the name of
+    /// the file and the line within it
+    fn include(&mut self, path: &str, line: usize) -> Result<(), String> {
+        if self.depth >= MAX_INCLUDE_DEPTH {
+            return Err(format!("`$include {path}` is nested too deeply"));
+        }
+        let path = match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+            (Some(path), Some(home)) => PathBuf::from(home).join(path),
+            _ => PathBuf::from(path),
+        };
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|err| format!("cannot include `{}`: {err}", path.display()))?;
+        let issues = std::mem::take(&mut self.inputrc.issues);
+        self.depth += 1;
+        self.import(&contents);
+        self.depth -= 1;
+        let included = std::mem::replace(&mut self.inputrc.issues, issues);
+        self.inputrc
+            .issues
+            .extend(included.into_iter().map(|issue| KeymapError {
+                line,
+                message: format!(
+                    "`{}` line {}: {}",
+                    path.display(),
+                    issue.line,
+                    issue.message
+                ),
+            }));
+        Ok(())
+    }
+
+    fn set_variable(&mut self, variable: &str) -> Result<(), String> {
+        let (name, value) = split_word(variable.trim());
+        match (name, value.to_ascii_lowercase().as_str()) {
+            ("editing-mode", "emacs") => {
+                self.inputrc.editing_mode = InputrcEditingMode::Emacs;
+                self.keymap = Keymap::Emacs;
+            }
+            ("editing-mode", "vi") => {
+                self.inputrc.editing_mode = InputrcEditingMode::Vi;
+                self.keymap = Keymap::ViInsert;
+            }
+            ("keymap", "emacs" | "emacs-standard") => self.keymap = Keymap::Emacs,
+            ("keymap", "emacs-meta") => self.keymap = Keymap::EmacsMeta,
+            ("keymap", "emacs-ctlx") => self.keymap = Keymap::EmacsCtlx,
+            ("keymap", "vi-insert") => self.keymap = Keymap::ViInsert,
+            ("keymap", "vi" | "vi-command" | "vi-move") => self.keymap = Keymap::ViCommand,
+            ("editing-mode" | "keymap", _) => {
+                return Err(format!("unknown {name} `{value}`"));
+            }
+            _ => return Err(format!("unsupported variable `{name}`")),
+        }
+        Ok(())
+    }
+
+    /// Add the binding of a `keys: function` or `keys: "macro"` line
+    fn bind(&mut self, line: &str) -> Result<(), String> {
+        let (mut sequence, value) = if let Some(quoted) = line.strip_prefix('"') {
+            let end = closing_quote(quoted, '"')
+                .ok_or_else(|| "unterminated key sequence".to_string())?;
+            let value = quoted[end + 1..]
+                .trim_start()
+                .strip_prefix(':')
+                .ok_or_else(|| "expected `:` after the key sequence".to_string())?;
+            (key_sequence(&unescape(&quoted[..end])?)?, value)
+        } else {
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| "expected `:` after the key name".to_string())?;
+            (vec![key_name(name.trim())?], value)
+        };
+        let value = value.trim();
+
+        let event = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let end = closing_quote(&value[1..], quote)
+                    .ok_or_else(|| "unterminated macro".to_string())?;
+                macro_event(&unescape(&value[1..end + 1])?)?
+            }
+            Some(_) => {
+                let (function, _) = split_word(value);
+                function_event(function)
+                    .ok_or_else(|| format!("unsupported function `{function}`"))?
+            }
+            None => return Err("expected a function or macro after `:`".to_string()),
+        };
+
+        let keybindings = match self.keymap {
+            Keymap::Emacs => &mut self.inputrc.emacs_keybindings,
+            Keymap::EmacsMeta => {
+                sequence[0].modifier |= KeyModifiers::ALT;
+                &mut self.inputrc.emacs_keybindings
+            }
+            Keymap::EmacsCtlx => {
+                sequence.insert(
+                    0,
+                    KeyCombination {
+                        modifier: KeyModifiers::CONTROL,
+                        key_code: KeyCode::Char('x'),
+                    },
+                );
+                &mut self.inputrc.emacs_keybindings
+            }
+            Keymap::ViInsert | Keymap::ViCommand => {
+                // `Vi` only looks up single keys, and treats plain characters as text or commands
+                if let [combination] = sequence.as_slice() {
+                    if matches!(combination.key_code, KeyCode::Char(_))
+                        && (combination.modifier == KeyModifiers::NONE
+                            || combination.modifier == KeyModifiers::SHIFT)
+                    {
+                        return Err(
+                            "unsupported binding of a plain character in a vi keymap".to_string()
+                        );
+                    }
+                } else {
+                    return Err("unsupported key sequence in a vi keymap".to_string());
+                }
+                if self.keymap == Keymap::ViInsert {
+                    &mut self.inputrc.vi_insert_keybindings
+                } else {
+                    &mut self.inputrc.vi_normal_keybindings
+                }
+            }
+        };
+        keybindings.add_sequence_binding(&sequence, event);
+        Ok(())
+    }
+}
+
+fn split_word(text: &str) -> (&str, &str) {
+    let text = text.trim();
+    match text.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim()),
+        None => (text, ""),
+    }
+}
+
+/// Index of the quote closing the quoted text, skipping escaped quotes
+fn closing_quote(text: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    text.char_indices().find_map(|(index, c)| {
+        let closes = !escaped && c == quote;
+        escaped = !escaped && c == '\\';
+        closes.then_some(index)
+    })
+}
+
+/// The characters of a key sequence or macro with the readline escapes resolved
+///
+/// `\C-` turns the next character into its control character and `\M-` prefixes it with
+/// `Esc`, like terminals send them.
+fn unescape(text: &str) -> Result<Vec<char>, String> {
+    let mut chars = text.chars().peekable();
+    let mut unescaped = vec![];
+    let mut control = false;
+    let mut meta = false;
+    while let Some(c) = chars.next() {
+        let c = if c == '\\' {
+            let escaped = chars
+                .next()
+                .ok_or_else(|| "unterminated escape".to_string())?;
+            match escaped {
+                'C' | 'M' if chars.peek() == Some(&'-') => {
+                    let _ = chars.next();
+                    if escaped == 'C' {
+                        control = true;
+                    } else {
+                        meta = true;
+                    }
+                    continue;
+                }
+                'e' => '\u{1b}',
+                'a' => '\u{7}',
+                'b' => '\u{8}',
+                'd' => '\u{7f}',
+                'f' => '\u{c}',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'v' => '\u{b}',
+                'x' => {
+                    let mut hex = String::new();
+                    while hex.len() < 2 && chars.peek().map_or(false, char::is_ascii_hexdigit) {
+                        hex.extend(chars.next());
+                    }
+                    u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| "invalid escape `\\x`".to_string())?
+                }
+                '0'..='7' => {
+                    let mut octal = escaped.to_string();
+                    while octal.len() < 3 && chars.peek().map_or(false, |c| ('0'..='7').contains(c))
+                    {
+                        octal.extend(chars.next());
+                    }
+                    u32::from_str_radix(&octal, 8)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| format!("invalid escape `\\{octal}`"))?
+                }
+                _ => escaped,
+            }
+        } else {
+            c
+        };
+        let c = if control { control_char(c) } else { c };
+        if meta {
+            unescaped.push('\u{1b}');
+        }
+        unescaped.push(c);
+        control = false;
+        meta = false;
+    }
+    Ok(unescaped)
+}
+
+/// The control character sent for `Ctrl` and the character, `\C-?` being `Delete`
+fn control_char(c: char) -> char {
+    match c {
+        '?' => '\u{7f}',
+        '@'..='_' | 'a'..='z' => char::from(c as u8 & 0x1f),
+        _ => c,
+    }
+}
+
+/// Escape sequences sent by terminals for keys without a character
+const ESCAPE_SEQUENCES: [(&str, KeyModifiers, KeyCode); 26] = [
+    ("[A", KeyModifiers::NONE, KeyCode::Up),
+    ("[B", KeyModifiers::NONE, KeyCode::Down),
+    ("[C", KeyModifiers::NONE, KeyCode::Right),
+    ("[D", KeyModifiers::NONE, KeyCode::Left),
+    ("[H", KeyModifiers::NONE, KeyCode::Home),
+    ("[F", KeyModifiers::NONE, KeyCode::End),
+    ("OA", KeyModifiers::NONE, KeyCode::Up),
+    ("OB", KeyModifiers::NONE, KeyCode::Down),
+    ("OC", KeyModifiers::NONE, KeyCode::Right),
+    ("OD", KeyModifiers::NONE, KeyCode::Left),
+    ("OH", KeyModifiers::NONE, KeyCode::Home),
+    ("OF", KeyModifiers::NONE, KeyCode::End),
+    ("[1~", KeyModifiers::NONE, KeyCode::Home),
+    ("[2~", KeyModifiers::NONE, KeyCode::Insert),
+    ("[3~", KeyModifiers::NONE, KeyCode::Delete),
+    ("[4~", KeyModifiers::NONE, KeyCode::End),
+    ("[5~", KeyModifiers::NONE, KeyCode::PageUp),
+    ("[6~", KeyModifiers::NONE, KeyCode::PageDown),
+    ("[Z", KeyModifiers::SHIFT, KeyCode::BackTab),
+    ("[1;5A", KeyModifiers::CONTROL, KeyCode::Up),
+    ("[1;5B", KeyModifiers::CONTROL, KeyCode::Down),
+    ("[1;5C", KeyModifiers::CONTROL, KeyCode::Right),
+    ("[1;5D", KeyModifiers::CONTROL, KeyCode::Left),
+    ("[1;3C", KeyModifiers::ALT, KeyCode::Right),
+    ("[1;3D", KeyModifiers::ALT, KeyCode::Left),
+    ("[3;5~", KeyModifiers::CONTROL, KeyCode::Delete),
+];
+
+/// The key combinations of the characters of a key sequence
+fn key_sequence(chars: &[char]) -> Result<Vec<KeyCombination>, String> {
+    let mut sequence = vec![];
+    let mut rest = chars;
+    while let Some((&c, after)) = rest.split_first() {
+        rest = after;
+        if c != '\u{1b}' || rest.is_empty() {
+            sequence.push(char_combination(c));
+            continue;
+        }
+        let escape_sequence = ESCAPE_SEQUENCES.iter().find(|(suffix, _, _)| {
+            rest.len() >= suffix.chars().count() && suffix.chars().zip(rest).all(|(a, &b)| a == b)
+        });
+        if let Some((suffix, modifier, key_code)) = escape_sequence {
+            rest = &rest[suffix.chars().count()..];
+            sequence.push(KeyCombination {
+                modifier: *modifier,
+                key_code: *key_code,
+            });
+        } else {
+            // `Esc` followed by a key is how terminals send it with `Alt`
+            let (&c, after) = rest.split_first().expect("checked to be not empty");
+            rest = after;
+            let mut combination = char_combination(c);
+            combination.modifier |= KeyModifiers::ALT;
+            sequence.push(combination);
+        }
+    }
+    if sequence.is_empty() {
+        return Err("empty key sequence".to_string());
+    }
+    Ok(sequence)
+}
+
+/// The key combination sending the character, like reedline receives it
+fn char_combination(c: char) -> KeyCombination {
+    let (modifier, key_code) = match c {
+        '\r' => (KeyModifiers::NONE, KeyCode::Enter),
+        '\t' => (KeyModifiers::NONE, KeyCode::Tab),
+        '\u{1b}' => (KeyModifiers::NONE, KeyCode::Esc),
+        '\u{7f}' => (KeyModifiers::NONE, KeyCode::Backspace),
+        '\0' => (KeyModifiers::CONTROL, KeyCode::Char(' ')),
+        '\u{1}'..='\u{1f}' => (
+            KeyModifiers::CONTROL,
+            KeyCode::Char(char::from(c as u8 | 0x60)),
+        ),
+        'A'..='Z' => (KeyModifiers::SHIFT, KeyCode::Char(c.to_ascii_lowercase())),
+        _ => (KeyModifiers::NONE, KeyCode::Char(c)),
+    };
+    KeyCombination { modifier, key_code }
+}
+
+/// The key combination of an unquoted key name like `Control-u` or `M-DEL`
+fn key_name(name: &str) -> Result<KeyCombination, String> {
+    let mut modifier = KeyModifiers::NONE;
+    let mut key = name;
+    while let Some((prefix, rest)) = key.split_once('-') {
+        let flag = match prefix.to_ascii_lowercase().as_str() {
+            "c" | "control" => KeyModifiers::CONTROL,
+            "m" | "meta" => KeyModifiers::ALT,
+            _ => break,
+        };
+        if rest.is_empty() {
+            break;
+        }
+        modifier |= flag;
+        key = rest;
+    }
+
+    let mut combination = match key.to_ascii_uppercase().as_str() {
+        "DEL" | "RUBOUT" => char_combination('\u{7f}'),
+        "ESC" | "ESCAPE" => char_combination('\u{1b}'),
+        "LFD" | "NEWLINE" => char_combination('\n'),
+        "RET" | "RETURN" => char_combination('\r'),
+        "SPACE" | "SPC" => char_combination(' '),
+        "TAB" => char_combination('\t'),
+        _ => {
+            let mut chars = key.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if modifier.contains(KeyModifiers::CONTROL) => {
+                    modifier.remove(KeyModifiers::CONTROL);
+                    char_combination(control_char(c))
+                }
+                (Some(c), None) => char_combination(c),
+                _ => return Err(format!("unknown key name `{name}`")),
+            }
+        }
+    };
+    combination.modifier |= modifier;
+    Ok(combination)
+}
+
+/// The events of a macro: text is inserted and line endings accept the line
+fn macro_event(chars: &[char]) -> Result<ReedlineEvent, String> {
+    let mut events = vec![];
+    let mut text = String::new();
+    for &c in chars {
+        match c {
+            '\r' | '\n' => {
+                if !text.is_empty() {
+                    events.push(edit_bind(EditCommand::InsertString(std::mem::take(
+                        &mut text,
+                    ))));
+                }
+                events.push(ReedlineEvent::Enter);
+            }
+            c if c.is_control() => {
+                return Err(format!(
+                    "unsupported macro with the control character {c:?}"
+                ))
+            }
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        events.push(edit_bind(EditCommand::InsertString(text)));
+    }
+    match events.len() {
+        0 => Err("empty macro".to_string()),
+        1 => Ok(events.remove(0)),
+        _ => Ok(ReedlineEvent::Multiple(events)),
+    }
+}
+
+/// The event of the readline function, `None` if reedline has no counterpart
+fn function_event(function: &str) -> Option<ReedlineEvent> {
+    use EditCommand as EC;
+
+    let event = match function {
+        // Moving
+        "beginning-of-line" => edit_bind(EC::MoveToLineStart { select: false }),
+        "end-of-line" => edit_bind(EC::MoveToLineEnd { select: false }),
+        "forward-char" => {
+            ReedlineEvent::UntilFound(vec![ReedlineEvent::MenuRight, ReedlineEvent::Right])
+        }
+        "backward-char" => {
+            ReedlineEvent::UntilFound(vec![ReedlineEvent::MenuLeft, ReedlineEvent::Left])
+        }
+        "forward-word" => edit_bind(EC::MoveWordRight { select: false }),
+        "backward-word" => edit_bind(EC::MoveWordLeft { select: false }),
+        "shell-forward-word" => edit_bind(EC::MoveBigWordRightEnd { select: false }),
+        "shell-backward-word" => edit_bind(EC::MoveBigWordLeft { select: false }),
+        "clear-screen" => ReedlineEvent::ClearScreen,
+        "clear-display" => ReedlineEvent::ClearScrollback,
+        "redraw-current-line" => ReedlineEvent::Repaint,
+        // History
+        "accept-line" => ReedlineEvent::Enter,
+        "previous-history" | "history-search-backward" | "history-substring-search-backward" => {
+            ReedlineEvent::UntilFound(vec![ReedlineEvent::MenuUp, ReedlineEvent::Up])
+        }
+        "next-history" | "history-search-forward" | "history-substring-search-forward" => {
+            ReedlineEvent::UntilFound(vec![ReedlineEvent::MenuDown, ReedlineEvent::Down])
+        }
+        "reverse-search-history" | "forward-search-history" => ReedlineEvent::SearchHistory,
+        // Changing text
+        "end-of-file" => ReedlineEvent::CtrlD,
+        "delete-char" => edit_bind(EC::Delete),
+        "backward-delete-char" => edit_bind(EC::Backspace),
+        "transpose-chars" => edit_bind(EC::SwapGraphemes),
+        "transpose-words" => edit_bind(EC::SwapWords),
+        "delete-horizontal-space" => edit_bind(EC::DeleteHorizontalSpace),
+        "upcase-word" => edit_bind(EC::UppercaseWord),
+        "downcase-word" => edit_bind(EC::LowercaseWord),
+        "capitalize-word" => edit_bind(EC::CapitalizeWord),
+        // Killing and yanking
+        "kill-line" => edit_bind(EC::CutToLineEnd),
+        "backward-kill-line" | "unix-line-discard" => edit_bind(EC::CutFromLineStart),
+        "kill-whole-line" => edit_bind(EC::KillWholeLine),
+        "kill-word" | "shell-kill-word" => edit_bind(EC::CutWordRight),
+        "backward-kill-word" | "shell-backward-kill-word" => edit_bind(EC::CutWordLeft),
+        "unix-word-rubout" => edit_bind(EC::CutBigWordLeft),
+        "kill-region" => edit_bind(EC::CutSelection),
+        "copy-region-as-kill" => edit_bind(EC::CopySelection),
+        "yank" => edit_bind(EC::PasteCutBufferBefore),
+        "yank-pop" => edit_bind(EC::YankPop),
+        // Completing
+        "complete" => ReedlineEvent::UntilFound(vec![
+            ReedlineEvent::Menu("completion_menu".to_string()),
+            ReedlineEvent::MenuNext,
+        ]),
+        "menu-complete" => ReedlineEvent::MenuNext,
+        "menu-complete-backward" => ReedlineEvent::MenuPrevious,
+        // Miscellaneous
+        "abort" => ReedlineEvent::Esc,
+        "undo" => edit_bind(EC::Undo),
+        "universal-argument" => ReedlineEvent::UniversalArgument,
+        "edit-and-execute-command" => ReedlineEvent::OpenEditor,
+        "start-kbd-macro" => ReedlineEvent::RecordMacro('0'),
+        "end-kbd-macro" => ReedlineEvent::StopMacroRecording,
+        "call-last-kbd-macro" => ReedlineEvent::ReplayMacro('0'),
+        _ => return None,
+    };
+    Some(event)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn combination(modifier: KeyModifiers, key_code: KeyCode) -> KeyCombination {
+        KeyCombination { modifier, key_code }
+    }
+
+    #[rstest]
+    #[case(r"\C-x\C-e", vec![
+        combination(KeyModifiers::CONTROL, KeyCode::Char('x')),
+        combination(KeyModifiers::CONTROL, KeyCode::Char('e')),
+    ])]
+    #[case(r"\e[A", vec![combination(KeyModifiers::NONE, KeyCode::Up)])]
+    #[case(r"\e[1;5C", vec![combination(KeyModifiers::CONTROL, KeyCode::Right)])]
+    #[case(r"\ef", vec![combination(KeyModifiers::ALT, KeyCode::Char('f'))])]
+    #[case(r"\M-\C-?", vec![combination(KeyModifiers::ALT, KeyCode::Backspace)])]
+    #[case(r"\C-m", vec![combination(KeyModifiers::NONE, KeyCode::Enter)])]
+    #[case(r"\t", vec![combination(KeyModifiers::NONE, KeyCode::Tab)])]
+    #[case(r"\e", vec![combination(KeyModifiers::NONE, KeyCode::Esc)])]
+    #[case(r"\x41\061", vec![
+        combination(KeyModifiers::SHIFT, KeyCode::Char('a')),
+        combination(KeyModifiers::NONE, KeyCode::Char('1')),
+    ])]
+    fn test_key_sequence(#[case] keys: &str, #[case] expected: Vec<KeyCombination>) {
+        assert_eq!(key_sequence(&unescape(keys).unwrap()), Ok(expected));
+    }
+
+    #[rstest]
+    #[case("Control-u", combination(KeyModifiers::CONTROL, KeyCode::Char('u')))]
+    #[case("C-M-f", combination(KeyModifiers::CONTROL | KeyModifiers::ALT, KeyCode::Char('f')))]
+    #[case("Meta-Rubout", combination(KeyModifiers::ALT, KeyCode::Backspace))]
+    #[case("M-DEL", combination(KeyModifiers::ALT, KeyCode::Backspace))]
+    #[case("SPC", combination(KeyModifiers::NONE, KeyCode::Char(' ')))]
+    #[case("C-m", combination(KeyModifiers::NONE, KeyCode::Enter))]
+    #[case("-", combination(KeyModifiers::NONE, KeyCode::Char('-')))]
+    fn test_key_name(#[case] name: &str, #[case] expected: KeyCombination) {
+        assert_eq!(key_name(name), Ok(expected));
+    }
+
+    #[test]
+    fn imports_bindings_and_macros_into_the_emacs_keymap() {
+        let inputrc = Inputrc::parse(
+            r#"
+# Emacs bindings
+"\C-x\C-r": re-read-init-file
+"\e[A": history-search-backward
+Control-w: unix-word-rubout
+"\C-xg": "git status\n"
+set bell-style none
+"#,
+            "Bash",
+        );
+
+        assert_eq!(inputrc.editing_mode, InputrcEditingMode::Emacs);
+        let issues: Vec<_> = inputrc.issues.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            issues,
+            vec![
+                "line 3: unsupported function `re-read-init-file`",
+                "line 7: unsupported variable `bell-style`",
+            ]
+        );
+
+        let keybindings = &inputrc.emacs_keybindings;
+        assert_eq!(
+            keybindings.find_binding(KeyModifiers::NONE, KeyCode::Up),
+            Some(ReedlineEvent::UntilFound(vec![
+                ReedlineEvent::MenuUp,
+                ReedlineEvent::Up
+            ]))
+        );
+        assert_eq!(
+            keybindings.find_binding(KeyModifiers::CONTROL, KeyCode::Char('w')),
+            Some(edit_bind(EditCommand::CutBigWordLeft))
+        );
+        assert_eq!(
+            keybindings.find_sequence_binding(&[
+                combination(KeyModifiers::CONTROL, KeyCode::Char('x')),
+                combination(KeyModifiers::NONE, KeyCode::Char('g')),
+            ]),
+            Some(ReedlineEvent::Multiple(vec![
+                edit_bind(EditCommand::InsertString("git status".to_string())),
+                ReedlineEvent::Enter
+            ]))
+        );
+    }
+
+    #[test]
+    fn conditionals_and_keymaps_choose_the_keybindings() {
+        let inputrc = Inputrc::parse(
+            r#"
+set editing-mode vi
+$if mode=emacs
+"\C-a": beginning-of-line
+$else
+"\C-a": end-of-line
+$endif
+$if Bash
+"\C-b": backward-word
+$endif
+$if myshell
+  $if mode=vi
+"\C-f": forward-word
+  $endif
+$endif
+set keymap vi-command
+"\C-e": edit-and-execute-command
+$endif
+"#,
+            "MyShell",
+        );
+
+        assert_eq!(inputrc.editing_mode, InputrcEditingMode::Vi);
+        let issues: Vec<_> = inputrc.issues.iter().map(ToString::to_string).collect();
+        assert_eq!(issues, vec!["line 18: `$endif` without `$if`"]);
+
+        let insert = &inputrc.vi_insert_keybindings;
+        assert_eq!(
+            insert.find_binding(KeyModifiers::CONTROL, KeyCode::Char('a')),
+            Some(edit_bind(EditCommand::MoveToLineEnd { select: false }))
+        );
+        assert_eq!(
+            insert.find_binding(KeyModifiers::CONTROL, KeyCode::Char('b')),
+            None
+        );
+        assert_eq!(
+            insert.find_binding(KeyModifiers::CONTROL, KeyCode::Char('f')),
+            Some(edit_bind(EditCommand::MoveWordRight { select: false }))
+        );
+        assert_eq!(
+            inputrc
+                .vi_normal_keybindings
+                .find_binding(KeyModifiers::CONTROL, KeyCode::Char('e')),
+            Some(ReedlineEvent::OpenEditor)
+        );
+        assert!(matches!(
+            inputrc.edit_mode().edit_mode(),
+            crate::PromptEditMode::Vi(crate::PromptViMode::Insert)
+        ));
+    }
+
+    #[test]
+    fn issues_of_included_files_name_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let included = dir.path().join("included");
+        std::fs::write(
+            &included,
+            "\"\\C-a\": beginning-of-line\n\"\\C-b\": no-such-function\n",
+        )
+        .unwrap();
+        let inputrc = Inputrc::parse(
+            &format!("# Shared bindings\n$include {}\n", included.display()),
+            "Bash",
+        );
+
+        assert_eq!(
+            inputrc.issues,
+            vec![KeymapError {
+                line: 2,
+                message: format!(
+                    "`{}` line 2: unsupported function `no-such-function`",
+                    included.display()
+                ),
+            }]
+        );
+        assert_eq!(
+            inputrc
+                .emacs_keybindings
+                .find_binding(KeyModifiers::CONTROL, KeyCode::Char('a')),
+            Some(edit_bind(EditCommand::MoveToLineStart { select: false }))
+        );
+    }
+
+    #[test]
+    fn unclosed_conditionals_are_reported() {
+        let inputrc = Inputrc::parse(
+            "$if Bash\n$if mode=vi\n$endif\n$if term=xterm\n\"\\C-a\": end-of-line\n",
+            "Bash",
+        );
+
+        let issues: Vec<_> = inputrc.issues.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            issues,
+            vec![
+                "line 1: `$if` without `$endif`",
+                "line 4: `$if` without `$endif`"
+            ]
+        );
+    }
+
+    #[test]
+    fn vi_keymaps_report_bindings_they_cannot_use() {
+        let inputrc = Inputrc::parse(
+            r#"
+set keymap vi-insert
+"\C-xa": beginning-of-line
+"jk": end-of-line
+set keymap vi-command
+"x": end-of-line
+"\ea": end-of-line
+"#,
+            "Bash",
+        );
+
+        let issues: Vec<_> = inputrc.issues.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            issues,
+            vec![
+                "line 3: unsupported key sequence in a vi keymap",
+                "line 4: unsupported key sequence in a vi keymap",
+                "line 6: unsupported binding of a plain character in a vi keymap",
+            ]
+        );
+        assert_eq!(
+            inputrc
+                .vi_normal_keybindings
+                .find_binding(KeyModifiers::ALT, KeyCode::Char('a')),
+            Some(edit_bind(EditCommand::MoveToLineEnd { select: false }))
+        );
+    }
+
+    #[rstest]
+    #[case("\"\\C-a\" beginning-of-line", "expected `:` after the key sequence")]
+    #[case("\"\\C-a: beginning-of-line", "unterminated key sequence")]
+    #[case("\"\\C-a\": \"abc", "unterminated macro")]
+    #[case("\"\\C-a\":", "expected a function or macro after `:`")]
+    #[case("Hyper-a: beginning-of-line", "unknown key name `Hyper-a`")]
+    #[case(
+        "\"\\C-a\": \"\\C-b\"",
+        "unsupported macro with the control character '\\u{2}'"
+    )]
+    #[case("set editing-mode ed", "unknown editing-mode `ed`")]
+    #[case("$unless Bash", "unknown directive `$unless`")]
+    fn test_invalid_lines(#[case] line: &str, #[case] message: &str) {
+        let inputrc = Inputrc::parse(line, "Bash");
+        assert_eq!(
+            inputrc.issues,
+            vec![KeymapError {
+                line: 1,
+                message: message.to_string()
+            }]
+        );
+    }
+}
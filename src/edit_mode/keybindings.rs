@@ -5,9 +5,12 @@ use {
     std::collections::HashMap,
 };
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Debug)]
+/// A key pressed together with its modifiers
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct KeyCombination {
+    /// The modifiers held down
     pub modifier: KeyModifiers,
+    /// The key pressed
     pub key_code: KeyCode,
 }
 
@@ -16,6 +19,61 @@ pub struct KeyCombination {
 pub struct Keybindings {
     /// Defines a keybinding for a reedline event
     pub bindings: HashMap<KeyCombination, ReedlineEvent>,
+    /// Bindings of sequences of two or more key combinations, like `Ctrl-x Ctrl-e`
+    #[serde(default)]
+    sequences: SequenceNode,
+}
+
+/// Node of the prefix tree of key sequences, reached by the combinations leading to it
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+struct SequenceNode {
+    event: Option<ReedlineEvent>,
+    children: HashMap<KeyCombination, SequenceNode>,
+}
+
+impl SequenceNode {
+    fn get(&self, sequence: &[KeyCombination]) -> Option<&SequenceNode> {
+        sequence
+            .iter()
+            .try_fold(self, |node, combination| node.children.get(combination))
+    }
+
+    fn insert(&mut self, sequence: &[KeyCombination], event: ReedlineEvent) {
+        let node = sequence.iter().fold(self, |node, combination| {
+            node.children.entry(*combination).or_default()
+        });
+        node.event = Some(event);
+    }
+
+    /// Remove the binding of the sequence along with the nodes no longer leading to one
+    fn remove(&mut self, sequence: &[KeyCombination]) -> Option<ReedlineEvent> {
+        match sequence.split_first() {
+            None => self.event.take(),
+            Some((combination, rest)) => {
+                let child = self.children.get_mut(combination)?;
+                let event = child.remove(rest);
+                if child.event.is_none() && child.children.is_empty() {
+                    self.children.remove(combination);
+                }
+                event
+            }
+        }
+    }
+
+    fn collect<'a>(
+        &'a self,
+        prefix: &mut Vec<KeyCombination>,
+        bindings: &mut Vec<(Vec<KeyCombination>, &'a ReedlineEvent)>,
+    ) {
+        if let Some(event) = &self.event {
+            bindings.push((prefix.clone(), event));
+        }
+        for (combination, child) in &self.children {
+            prefix.push(*combination);
+            child.collect(prefix, bindings);
+            prefix.pop();
+        }
+    }
 }
 
 impl Default for Keybindings {
@@ -29,6 +87,7 @@ impl Keybindings {
     pub fn new() -> Self {
         Self {
             bindings: HashMap::new(),
+            sequences: SequenceNode::default(),
         }
     }
 
@@ -81,6 +140,73 @@ impl Keybindings {
     pub fn get_keybindings(&self) -> &HashMap<KeyCombination, ReedlineEvent> {
         &self.bindings
     }
+
+    /// Adds a keybinding for a sequence of key combinations pressed one after the other
+    ///
+    /// Sequences are only recognized by the [`Emacs`](crate::Emacs) edit mode. A sequence
+    /// of a single combination is the same as [`Keybindings::add_binding`].
+    ///
+    /// # Panics
+    ///
+    /// If `sequence` is empty or `command` is an empty [`ReedlineEvent::UntilFound`]
+    pub fn add_sequence_binding(&mut self, sequence: &[KeyCombination], command: ReedlineEvent) {
+        match sequence {
+            [] => panic!("A key sequence should contain at least one key combination"),
+            [combination] => {
+                self.add_binding(combination.modifier, combination.key_code, command);
+            }
+            _ => {
+                if let ReedlineEvent::UntilFound(subcommands) = &command {
+                    assert!(
+                        !subcommands.is_empty(),
+                        "UntilFound should contain a series of potential events to handle"
+                    );
+                }
+                self.sequences.insert(sequence, command);
+            }
+        }
+    }
+
+    /// Find the keybinding of a sequence of key combinations
+    pub fn find_sequence_binding(&self, sequence: &[KeyCombination]) -> Option<ReedlineEvent> {
+        match sequence {
+            [] => None,
+            [combination] => self.bindings.get(combination).cloned(),
+            _ => self.sequences.get(sequence)?.event.clone(),
+        }
+    }
+
+    /// Remove the keybinding of a sequence of key combinations
+    ///
+    /// Returns `Some(ReedlineEvent)` if the sequence was previously bound to a particular [`ReedlineEvent`]
+    pub fn remove_sequence_binding(
+        &mut self,
+        sequence: &[KeyCombination],
+    ) -> Option<ReedlineEvent> {
+        match sequence {
+            [] => None,
+            [combination] => self.bindings.remove(combination),
+            _ => self.sequences.remove(sequence),
+        }
+    }
+
+    /// Whether longer key sequences starting with `sequence` are bound
+    pub fn is_sequence_prefix(&self, sequence: &[KeyCombination]) -> bool {
+        self.sequences
+            .get(sequence)
+            .map_or(false, |node| !node.children.is_empty())
+    }
+
+    /// Get every assigned keybinding as a sequence, the single key combinations included
+    pub fn get_keybinding_sequences(&self) -> Vec<(Vec<KeyCombination>, &ReedlineEvent)> {
+        let mut bindings: Vec<_> = self
+            .bindings
+            .iter()
+            .map(|(combination, event)| (vec![*combination], event))
+            .collect();
+        self.sequences.collect(&mut vec![], &mut bindings);
+        bindings
+    }
 }
 
 pub fn edit_bind(command: EditCommand) -> ReedlineEvent {
@@ -91,7 +217,8 @@ pub fn edit_bind(command: EditCommand) -> ReedlineEvent {
 ///
 /// `Ctrl-C`, `Ctrl-D`, `Ctrl-O`, `Ctrl-R`
 /// + `Esc`
-/// + `Ctrl-O` to open the external editor
+/// + `Ctrl-O` to open the external editor, which the Vi normal keybindings use for the
+///   jump list instead
 pub fn add_common_control_bindings(kb: &mut Keybindings) {
     use KeyCode as KC;
     use KeyModifiers as KM;
@@ -287,4 +414,21 @@ pub fn add_common_selection_bindings(kb: &mut Keybindings) {
         KC::Char('a'),
         edit_bind(EC::SelectAll),
     );
+    kb.add_binding(
+        KM::CONTROL | KM::ALT,
+        KC::Char('b'),
+        edit_bind(EC::ToggleBlockSelection),
+    );
+    // Multiple cursors
+    kb.add_binding(KM::CONTROL | KM::ALT, KC::Up, edit_bind(EC::AddCursorAbove));
+    kb.add_binding(
+        KM::CONTROL | KM::ALT,
+        KC::Down,
+        edit_bind(EC::AddCursorBelow),
+    );
+    kb.add_binding(
+        KM::CONTROL | KM::ALT,
+        KC::Char('n'),
+        edit_bind(EC::AddCursorAtNextMatch),
+    );
 }
@@ -0,0 +1,728 @@
+//! Keymap files binding key sequences to events, see [`Keybindings::load_keymap`]
+use crate::{
+    edit_mode::keybindings::{KeyCombination, Keybindings},
+    utils::{key_code_from_name, key_code_name},
+    ReedlineEvent,
+};
+use crossterm::event::KeyModifiers;
+use serde::{
+    de::{
+        self,
+        value::{MapDeserializer, SeqDeserializer, StringDeserializer},
+        DeserializeSeed, EnumAccess, IntoDeserializer, VariantAccess, Visitor,
+    },
+    forward_to_deserialize_any, Deserialize,
+};
+use thiserror::Error;
+
+/// Error of a keymap that could not be loaded
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("line {line}: {message}")]
+pub struct KeymapError {
+    /// Number of the offending line, starting at 1
+    pub line: usize,
+    /// What is wrong with the line
+    pub message: String,
+}
+
+const MODIFIER_NAMES: [(&str, KeyModifiers); 6] = [
+    ("ctrl", KeyModifiers::CONTROL),
+    ("alt", KeyModifiers::ALT),
+    ("shift", KeyModifiers::SHIFT),
+    ("super", KeyModifiers::SUPER),
+    ("hyper", KeyModifiers::HYPER),
+    ("meta", KeyModifiers::META),
+];
+
+impl Keybindings {
+    /// Add the bindings of a keymap, replacing the bindings of the same keys
+    ///
+    /// Every line binds a key sequence to an event, written like a TOML key and string:
+    ///
+    /// ```toml
+    /// # Comments and blank lines are ignored
+    /// "ctrl-x ctrl-e" = "OpenEditor"
+    /// "alt-b" = "Edit([MoveWordLeft { select: false }])"
+    /// "ctrl-up" = 'Edit([InsertString("↑")])'
+    /// ```
+    ///
+    /// The combinations of a sequence are separated by spaces. Each is written as its
+    /// modifiers `ctrl-`, `alt-`, `shift-`, `super-`, `hyper-` and `meta-` followed by the
+    /// name of the key as listed by [`get_reedline_keycodes`](crate::get_reedline_keycodes),
+    /// like `Enter` or `Space`, `F1` to `F24` or a single character.
+    ///
+    /// Events are written like their `Debug` output, named as listed by
+    /// [`get_reedline_reedline_events`](crate::get_reedline_reedline_events) and
+    /// [`get_reedline_edit_commands`](crate::get_reedline_edit_commands).
+    ///
+    /// Nothing is added if any line is invalid, the error names the first invalid line.
+    pub fn load_keymap(&mut self, keymap: &str) -> Result<(), KeymapError> {
+        let mut bindings = vec![];
+        for (index, line) in keymap.lines().enumerate() {
+            let binding = parse_line(line).map_err(|message| KeymapError {
+                line: index + 1,
+                message,
+            })?;
+            bindings.extend(binding);
+        }
+        for (sequence, event) in bindings {
+            self.add_sequence_binding(&sequence, event);
+        }
+        Ok(())
+    }
+
+    /// Write the bindings as a keymap read back by [`Keybindings::load_keymap`]
+    ///
+    /// Bindings of keys without a name, like media keys, are left out.
+    pub fn to_keymap(&self) -> String {
+        let mut lines: Vec<_> = self
+            .get_keybinding_sequences()
+            .into_iter()
+            .filter_map(|(sequence, event)| {
+                let keys = sequence
+                    .iter()
+                    .map(combination_name)
+                    .collect::<Option<Vec<_>>>()?;
+                Some(format!(
+                    "{} = {}\n",
+                    basic_string(&keys.join(" ")),
+                    toml_string(&format!("{event:?}"))
+                ))
+            })
+            .collect();
+        lines.sort();
+        lines.concat()
+    }
+}
+
+/// The binding of the line, `None` for blank lines and comments
+fn parse_line(line: &str) -> Result<Option<(Vec<KeyCombination>, ReedlineEvent)>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    if line.starts_with('[') {
+        return Err("tables are not supported".to_string());
+    }
+
+    let (keys, rest) = if line.starts_with(['"', '\'']) {
+        parse_toml_string(line)?
+    } else {
+        let end = line
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+            .unwrap_or(line.len());
+        (line[..end].to_string(), &line[end..])
+    };
+    let rest = rest
+        .trim_start()
+        .strip_prefix('=')
+        .ok_or_else(|| "expected `=` after the keys".to_string())?;
+    let (event, rest) = parse_toml_string(rest.trim_start())?;
+    let rest = rest.trim_start();
+    if !rest.is_empty() && !rest.starts_with('#') {
+        return Err(format!("unexpected `{rest}` after the event"));
+    }
+
+    let sequence = keys
+        .split_whitespace()
+        .map(parse_key_combination)
+        .collect::<Result<Vec<_>, _>>()?;
+    if sequence.is_empty() {
+        return Err("expected at least one key".to_string());
+    }
+    let event = parse_event(&event)?;
+    if matches!(&event, ReedlineEvent::UntilFound(events) if events.is_empty()) {
+        return Err("UntilFound should contain at least one event".to_string());
+    }
+    Ok(Some((sequence, event)))
+}
+
+/// Parse the basic or literal TOML string at the start of `text`, returning the rest
+fn parse_toml_string(text: &str) -> Result<(String, &str), String> {
+    let mut chars = text.char_indices();
+    match chars.next() {
+        Some((_, '\'')) => {
+            let end = text[1..]
+                .find('\'')
+                .ok_or_else(|| "unterminated string".to_string())?;
+            Ok((text[1..end + 1].to_string(), &text[end + 2..]))
+        }
+        Some((_, '"')) => {
+            let mut value = String::new();
+            while let Some((index, c)) = chars.next() {
+                match c {
+                    '"' => return Ok((value, &text[index + 1..])),
+                    '\\' => {
+                        let escaped = match chars.next() {
+                            Some((_, escaped)) => escaped,
+                            None => break,
+                        };
+                        value.push(match escaped {
+                            'n' => '\n',
+                            't' => '\t',
+                            'r' => '\r',
+                            'b' => '\u{8}',
+                            'f' => '\u{c}',
+                            '"' | '\\' => escaped,
+                            'u' | 'U' => {
+                                let len = if escaped == 'u' { 4 } else { 8 };
+                                let hex: String =
+                                    chars.by_ref().take(len).map(|(_, c)| c).collect();
+                                u32::from_str_radix(&hex, 16)
+                                    .ok()
+                                    .and_then(char::from_u32)
+                                    .ok_or_else(|| format!("invalid escape `\\{escaped}{hex}`"))?
+                            }
+                            _ => return Err(format!("invalid escape `\\{escaped}`")),
+                        });
+                    }
+                    _ => value.push(c),
+                }
+            }
+            Err("unterminated string".to_string())
+        }
+        _ => Err("expected a quoted string".to_string()),
+    }
+}
+
+/// Quote the text as a basic TOML string
+fn basic_string(text: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Quote the text as a TOML string, literally if that avoids escapes
+fn toml_string(text: &str) -> String {
+    if text.contains(['"', '\\']) && !text.contains(|c: char| c == '\'' || c.is_control()) {
+        format!("'{text}'")
+    } else {
+        basic_string(text)
+    }
+}
+
+fn parse_key_combination(combination: &str) -> Result<KeyCombination, String> {
+    let mut modifier = KeyModifiers::NONE;
+    let mut key = combination;
+    while let Some((name, rest)) = key.split_once('-') {
+        let flag = MODIFIER_NAMES
+            .iter()
+            .chain(&[("control", KeyModifiers::CONTROL)])
+            .find(|(modifier_name, _)| modifier_name.eq_ignore_ascii_case(name));
+        match flag {
+            Some((_, flag)) if !rest.is_empty() => {
+                modifier |= *flag;
+                key = rest;
+            }
+            _ => break,
+        }
+    }
+    let key_code =
+        key_code_from_name(key).ok_or_else(|| format!("unknown key `{key}` in `{combination}`"))?;
+    Ok(KeyCombination { modifier, key_code })
+}
+
+fn combination_name(combination: &KeyCombination) -> Option<String> {
+    let mut name = String::new();
+    for (modifier_name, flag) in MODIFIER_NAMES {
+        if combination.modifier.contains(flag) {
+            name.push_str(modifier_name);
+            name.push('-');
+        }
+    }
+    name.push_str(&key_code_name(combination.key_code)?);
+    Some(name)
+}
+
+fn parse_event(text: &str) -> Result<ReedlineEvent, String> {
+    let mut parser = ValueParser { text, pos: 0 };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos < text.len() {
+        return Err(parser.error("unexpected text"));
+    }
+    ReedlineEvent::deserialize(value).map_err(|err| format!("invalid event `{text}`: {err}"))
+}
+
+/// An event or one of its arguments written like its `Debug` output
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Bool(bool),
+    Int(i64),
+    Char(char),
+    Str(String),
+    List(Vec<Value>),
+    /// Unit variant like `Enter`
+    Ident(String),
+    /// Tuple variant like `Edit([...])`
+    Tuple(String, Vec<Value>),
+    /// Struct variant like `MoveLeft { select: false }`, or a struct
+    Struct(String, Vec<(String, Value)>),
+}
+
+struct ValueParser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl ValueParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += c.len_utf8();
+        }
+        found
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{c}`")))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map_or(false, char::is_whitespace) {
+            let _ = self.next();
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        match &self.text[self.pos..] {
+            "" => format!("{message} at the end of `{}`", self.text),
+            rest => format!("{message} at `{rest}`"),
+        }
+    }
+
+    fn ident(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .map_or(false, |c| c.is_alphanumeric() || c == '_')
+        {
+            let _ = self.next();
+        }
+        self.text[start..self.pos].to_string()
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('[') => {
+                self.pos += 1;
+                Ok(Value::List(self.values(']')?))
+            }
+            Some('"') => {
+                self.pos += 1;
+                let mut value = String::new();
+                loop {
+                    match self.next() {
+                        Some('"') => return Ok(Value::Str(value)),
+                        Some('\\') => value.push(self.escape()?),
+                        Some(c) => value.push(c),
+                        None => return Err(self.error("unterminated string")),
+                    }
+                }
+            }
+            Some('\'') => {
+                self.pos += 1;
+                let c = match self.next() {
+                    Some('\\') => self.escape()?,
+                    Some(c) => c,
+                    None => return Err(self.error("unterminated character")),
+                };
+                if self.next() == Some('\'') {
+                    Ok(Value::Char(c))
+                } else {
+                    Err(self.error("unterminated character"))
+                }
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.pos;
+                let _ = self.next();
+                while self.peek().map_or(false, |c| c.is_ascii_digit()) {
+                    let _ = self.next();
+                }
+                self.text[start..self.pos]
+                    .parse()
+                    .map(Value::Int)
+                    .map_err(|_| format!("invalid number `{}`", &self.text[start..self.pos]))
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let name = self.ident();
+                if self.eat('(') {
+                    Ok(Value::Tuple(name, self.values(')')?))
+                } else if self.eat('{') {
+                    Ok(Value::Struct(name, self.fields()?))
+                } else {
+                    Ok(match name.as_str() {
+                        "true" => Value::Bool(true),
+                        "false" => Value::Bool(false),
+                        _ => Value::Ident(name),
+                    })
+                }
+            }
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    /// The comma separated values up to `close`
+    fn values(&mut self, close: char) -> Result<Vec<Value>, String> {
+        let mut values = vec![];
+        loop {
+            if self.eat(close) {
+                return Ok(values);
+            }
+            values.push(self.value()?);
+            if !self.eat(',') {
+                self.expect(close)?;
+                return Ok(values);
+            }
+        }
+    }
+
+    /// The comma separated `name: value` fields up to the closing brace
+    fn fields(&mut self) -> Result<Vec<(String, Value)>, String> {
+        let mut fields = vec![];
+        loop {
+            if self.eat('}') {
+                return Ok(fields);
+            }
+            self.skip_whitespace();
+            let name = self.ident();
+            if name.is_empty() {
+                return Err(self.error("expected a field name"));
+            }
+            self.expect(':')?;
+            fields.push((name, self.value()?));
+            if !self.eat(',') {
+                self.expect('}')?;
+                return Ok(fields);
+            }
+        }
+    }
+
+    /// The character escaped by the backslash just read, like in Rust
+    fn escape(&mut self) -> Result<char, String> {
+        match self.next() {
+            Some('n') => Ok('\n'),
+            Some('r') => Ok('\r'),
+            Some('t') => Ok('\t'),
+            Some('0') => Ok('\0'),
+            Some(c @ ('\\' | '\'' | '"')) => Ok(c),
+            Some('u') => {
+                self.expect('{')?;
+                let start = self.pos;
+                while self.peek().map_or(false, |c| c.is_ascii_hexdigit()) {
+                    let _ = self.next();
+                }
+                let c = u32::from_str_radix(&self.text[start..self.pos], 16)
+                    .ok()
+                    .and_then(char::from_u32);
+                self.expect('}')?;
+                c.ok_or_else(|| self.error("invalid unicode escape"))
+            }
+            _ => Err(self.error("invalid escape")),
+        }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, de::value::Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Value::Bool(value) => visitor.visit_bool(value),
+            Value::Int(value) => visitor.visit_i64(value),
+            Value::Char(value) => visitor.visit_char(value),
+            Value::Str(value) | Value::Ident(value) => visitor.visit_string(value),
+            Value::List(values) | Value::Tuple(_, values) => {
+                SeqDeserializer::new(values.into_iter()).deserialize_any(visitor)
+            }
+            Value::Struct(_, fields) => {
+                MapDeserializer::new(fields.into_iter()).deserialize_any(visitor)
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Value::Ident(name) if name == "None" => visitor.visit_none(),
+            Value::Tuple(name, mut values) if name == "Some" && values.len() == 1 => {
+                visitor.visit_some(values.remove(0))
+            }
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_enum(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// What follows the name of an enum variant
+enum VariantPayload {
+    Unit,
+    Tuple(Vec<Value>),
+    Struct(Vec<(String, Value)>),
+}
+
+impl<'de> EnumAccess<'de> for Value {
+    type Error = de::value::Error;
+    type Variant = VariantPayload;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let (name, payload) = match self {
+            Value::Ident(name) | Value::Str(name) => (name, VariantPayload::Unit),
+            Value::Tuple(name, values) => (name, VariantPayload::Tuple(values)),
+            Value::Struct(name, fields) => (name, VariantPayload::Struct(fields)),
+            _ => return Err(de::Error::custom("expected the name of a variant")),
+        };
+        let name: StringDeserializer<Self::Error> = name.into_deserializer();
+        Ok((seed.deserialize(name)?, payload))
+    }
+}
+
+impl<'de> VariantAccess<'de> for VariantPayload {
+    type Error = de::value::Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        match self {
+            VariantPayload::Unit => Ok(()),
+            _ => Err(de::Error::custom("expected no arguments")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, Self::Error> {
+        match self {
+            VariantPayload::Tuple(mut values) if values.len() == 1 => {
+                seed.deserialize(values.remove(0))
+            }
+            _ => Err(de::Error::custom("expected one argument in parentheses")),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self {
+            VariantPayload::Tuple(values) => {
+                de::Deserializer::deserialize_any(SeqDeserializer::new(values.into_iter()), visitor)
+            }
+            _ => Err(de::Error::custom("expected arguments in parentheses")),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self {
+            VariantPayload::Struct(fields) => {
+                de::Deserializer::deserialize_any(MapDeserializer::new(fields.into_iter()), visitor)
+            }
+            _ => Err(de::Error::custom("expected fields in braces")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        default_emacs_keybindings, default_vi_insert_keybindings, default_vi_normal_keybindings,
+        EditCommand,
+    };
+    use crossterm::event::KeyCode;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("enter", KeyModifiers::NONE, KeyCode::Enter)]
+    #[case("ctrl-x", KeyModifiers::CONTROL, KeyCode::Char('x'))]
+    #[case("Control-Alt-Left", KeyModifiers::CONTROL | KeyModifiers::ALT, KeyCode::Left)]
+    #[case("shift-f12", KeyModifiers::SHIFT, KeyCode::F(12))]
+    #[case("alt-space", KeyModifiers::ALT, KeyCode::Char(' '))]
+    #[case("ctrl--", KeyModifiers::CONTROL, KeyCode::Char('-'))]
+    #[case("-", KeyModifiers::NONE, KeyCode::Char('-'))]
+    fn test_parse_key_combination(
+        #[case] name: &str,
+        #[case] modifier: KeyModifiers,
+        #[case] key_code: KeyCode,
+    ) {
+        assert_eq!(
+            parse_key_combination(name),
+            Ok(KeyCombination { modifier, key_code })
+        );
+    }
+
+    #[rstest]
+    #[case("OpenEditor", ReedlineEvent::OpenEditor)]
+    #[case(
+        "Edit([MoveWordLeft { select: false }, InsertChar('\\''), InsertString(\"a\\\"b\")])",
+        ReedlineEvent::Edit(vec![
+            EditCommand::MoveWordLeft { select: false },
+            EditCommand::InsertChar('\''),
+            EditCommand::InsertString("a\"b".to_string()),
+        ])
+    )]
+    #[case(
+        "UntilFound([Menu(\"completion_menu\"), MenuNext])",
+        ReedlineEvent::UntilFound(vec![
+            ReedlineEvent::Menu("completion_menu".to_string()),
+            ReedlineEvent::MenuNext,
+        ])
+    )]
+    #[case("Resize(80, 24)", ReedlineEvent::Resize(80, 24))]
+    fn test_parse_event(#[case] text: &str, #[case] expected: ReedlineEvent) {
+        assert_eq!(parse_event(text), Ok(expected));
+    }
+
+    #[test]
+    fn loads_bindings_of_key_sequences() {
+        let keymap = r#"
+# Editor
+"ctrl-x ctrl-e" = "OpenEditor" # opens $EDITOR
+alt-b = 'Edit([MoveWordLeft { select: false }])'
+"ctrl-up" = "Edit([InsertString(\"↑\")])"
+"#;
+        let mut keybindings = Keybindings::new();
+        keybindings.load_keymap(keymap).unwrap();
+
+        let ctrl = |c| KeyCombination {
+            modifier: KeyModifiers::CONTROL,
+            key_code: KeyCode::Char(c),
+        };
+        assert_eq!(
+            keybindings.find_sequence_binding(&[ctrl('x'), ctrl('e')]),
+            Some(ReedlineEvent::OpenEditor)
+        );
+        assert_eq!(
+            keybindings.find_binding(KeyModifiers::ALT, KeyCode::Char('b')),
+            Some(ReedlineEvent::Edit(vec![EditCommand::MoveWordLeft {
+                select: false
+            }]))
+        );
+        assert_eq!(
+            keybindings.find_binding(KeyModifiers::CONTROL, KeyCode::Up),
+            Some(ReedlineEvent::Edit(vec![EditCommand::InsertString(
+                "↑".to_string()
+            )]))
+        );
+    }
+
+    #[rstest]
+    #[case("\"ctrl-x\" = \"OpenEditor\"\n[table]", 2, "tables are not supported")]
+    #[case("\n\"ctrl-x\" \"OpenEditor\"", 2, "expected `=` after the keys")]
+    #[case("\"ctrl-x\" = OpenEditor", 1, "expected a quoted string")]
+    #[case("\"ctrl-x\" = \"OpenEditor", 1, "unterminated string")]
+    #[case("\"ctrl-x\" = \"OpenEditor\" x", 1, "unexpected `x` after the event")]
+    #[case("\"ctl-x\" = \"OpenEditor\"", 1, "unknown key `ctl-x` in `ctl-x`")]
+    #[case("\" \" = \"OpenEditor\"", 1, "expected at least one key")]
+    #[case(
+        "\"x\" = \"Edit([MoveLeft { select: false }\"",
+        1,
+        "expected `]` at the end of `Edit([MoveLeft { select: false }`"
+    )]
+    #[case(
+        "\"x\" = \"UntilFound([])\"",
+        1,
+        "UntilFound should contain at least one event"
+    )]
+    fn test_keymap_errors(#[case] keymap: &str, #[case] line: usize, #[case] message: &str) {
+        assert_eq!(
+            Keybindings::new().load_keymap(keymap),
+            Err(KeymapError {
+                line,
+                message: message.to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_events_are_reported_by_name() {
+        let err = Keybindings::new()
+            .load_keymap("\"x\" = \"Edit([Bogus])\"")
+            .unwrap_err();
+        assert_eq!(err.line, 1);
+        assert!(err.message.contains("unknown variant `Bogus`"));
+
+        let err = Keybindings::new()
+            .load_keymap("\"x\" = \"Edit([MoveLeft])\"")
+            .unwrap_err();
+        assert!(err.message.contains("expected fields in braces"));
+    }
+
+    #[rstest]
+    #[case(default_emacs_keybindings())]
+    #[case(default_vi_normal_keybindings())]
+    #[case(default_vi_insert_keybindings())]
+    fn printed_keymaps_load_back(#[case] keybindings: Keybindings) {
+        let keymap = keybindings.to_keymap();
+        let mut loaded = Keybindings::new();
+        loaded.load_keymap(&keymap).unwrap();
+
+        assert_eq!(loaded.to_keymap(), keymap);
+        assert_eq!(
+            loaded.get_keybinding_sequences().len(),
+            keybindings.get_keybinding_sequences().len()
+        );
+    }
+}
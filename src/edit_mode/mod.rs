@@ -1,11 +1,18 @@
 mod base;
 mod cursors;
 mod emacs;
+mod inputrc;
 mod keybindings;
+mod keymap;
 mod vi;
 
 pub use base::EditMode;
 pub use cursors::CursorConfig;
 pub use emacs::{default_emacs_keybindings, Emacs};
-pub use keybindings::Keybindings;
-pub use vi::{default_vi_insert_keybindings, default_vi_normal_keybindings, Vi};
+pub use inputrc::{Inputrc, InputrcEditingMode};
+pub use keybindings::{KeyCombination, Keybindings};
+pub use keymap::KeymapError;
+pub use vi::{
+    default_vi_insert_keybindings, default_vi_normal_keybindings, default_vi_replace_keybindings,
+    Vi,
+};
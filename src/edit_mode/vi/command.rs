@@ -1,28 +1,56 @@
-use super::{motion::Motion, motion::ViCharSearch, parser::ReedlineOption};
-use crate::{EditCommand, ReedlineEvent, Vi};
+use super::{
+    motion::ViCharSearch,
+    motion::{text_object_kind, Motion},
+    parser::{repeat, ReedlineOption},
+    ViMode,
+};
+use crate::{EditCommand, ReedlineEvent, TextObjectKind, Vi};
 use std::iter::Peekable;
 
-pub fn parse_command<'iter, I>(input: &mut Peekable<I>) -> Option<Command>
+pub fn parse_command<'iter, I>(input: &mut Peekable<I>, mode: ViMode) -> Option<Command>
 where
     I: Iterator<Item = &'iter char>,
 {
     match input.peek() {
+        Some('o') if mode.is_visual() => {
+            let _ = input.next();
+            Some(Command::SwapSelectionEnds)
+        }
+        Some('u') if mode.is_visual() => {
+            let _ = input.next();
+            Some(Command::Lowercase)
+        }
+        Some('U') if mode.is_visual() => {
+            let _ = input.next();
+            Some(Command::Uppercase)
+        }
         Some('d') => {
             let _ = input.next();
-            if let Some('i') = input.peek() {
-                let _ = input.next();
-                match input.next() {
-                    Some(c)
-                        if is_valid_change_inside_left(c) || is_valid_change_inside_right(c) =>
-                    {
-                        Some(Command::DeleteInside(*c))
+            match input.peek() {
+                Some('s') if !mode.is_visual() => {
+                    let _ = input.next();
+                    match input.next() {
+                        Some(c) => surrounding_kind(*c).map(Command::DeleteSurround),
+                        None => Some(Command::Incomplete),
                     }
-                    _ => None,
                 }
-            } else {
-                Some(Command::Delete)
+                _ => Some(Command::Delete),
             }
         }
+        Some('y') => {
+            let _ = input.next();
+            match input.peek() {
+                Some('s') if !mode.is_visual() => {
+                    let _ = input.next();
+                    Some(Command::Surround(None))
+                }
+                _ => Some(Command::Yank),
+            }
+        }
+        Some('Y') => {
+            let _ = input.next();
+            Some(Command::YankLine)
+        }
         Some('p') => {
             let _ = input.next();
             Some(Command::PasteAfter)
@@ -35,6 +63,10 @@ where
             let _ = input.next();
             Some(Command::EnterViInsert)
         }
+        Some('R') => {
+            let _ = input.next();
+            Some(Command::EnterViReplace)
+        }
         Some('a') => {
             let _ = input.next();
             Some(Command::EnterViAppend)
@@ -43,20 +75,29 @@ where
             let _ = input.next();
             Some(Command::Undo)
         }
+        Some('g') => {
+            let _ = input.next();
+            match input.next() {
+                Some('-') => Some(Command::UndoEarlier),
+                Some('+') => Some(Command::UndoLater),
+                Some(_) => None,
+                None => Some(Command::Incomplete),
+            }
+        }
         Some('c') => {
             let _ = input.next();
-            if let Some('i') = input.peek() {
-                let _ = input.next();
-                match input.next() {
-                    Some(c)
-                        if is_valid_change_inside_left(c) || is_valid_change_inside_right(c) =>
-                    {
-                        Some(Command::ChangeInside(*c))
+            match input.peek() {
+                Some('s') if !mode.is_visual() => {
+                    let _ = input.next();
+                    match (input.next(), input.next()) {
+                        (Some(from), Some(to)) => {
+                            surrounding_kind(*from).map(|kind| Command::ChangeSurround(kind, *to))
+                        }
+                        (Some(from), None) => surrounding_kind(*from).map(|_| Command::Incomplete),
+                        _ => Some(Command::Incomplete),
                     }
-                    _ => None,
                 }
-            } else {
-                Some(Command::Change)
+                _ => Some(Command::Change),
             }
         }
         Some('x') => {
@@ -74,9 +115,17 @@ where
             let _ = input.next();
             Some(Command::SubstituteCharWithInsert)
         }
+        Some('/') => {
+            let _ = input.next();
+            Some(Command::Search { backward: false })
+        }
         Some('?') => {
             let _ = input.next();
-            Some(Command::HistorySearch)
+            Some(Command::Search { backward: true })
+        }
+        Some(':') => {
+            let _ = input.next();
+            Some(Command::ExCommandLine)
         }
         Some('C') => {
             let _ = input.next();
@@ -94,6 +143,13 @@ where
             let _ = input.next();
             Some(Command::AppendToEnd)
         }
+        Some('S') if mode.is_visual() => {
+            let _ = input.next();
+            match input.next() {
+                Some(c) => Some(Command::Surround(Some(*c))),
+                None => Some(Command::Incomplete),
+            }
+        }
         Some('S') => {
             let _ = input.next();
             Some(Command::RewriteCurrentLine)
@@ -106,11 +162,66 @@ where
             let _ = input.next();
             Some(Command::RepeatLastAction)
         }
+        Some('>') => {
+            let _ = input.next();
+            Some(Command::Indent)
+        }
+        Some('<') => {
+            let _ = input.next();
+            Some(Command::Dedent)
+        }
+        Some('J') => {
+            let _ = input.next();
+            Some(Command::JoinLines)
+        }
+        Some('m') => {
+            let _ = input.next();
+            match input.next() {
+                Some(c) if c.is_ascii_lowercase() => Some(Command::SetMark(*c)),
+                Some(_) => None,
+                None => Some(Command::Incomplete),
+            }
+        }
+        Some('q') => {
+            let _ = input.next();
+            match input.next() {
+                Some(c) if c.is_ascii_alphanumeric() => Some(Command::RecordMacro(*c)),
+                Some(_) => None,
+                None => Some(Command::Incomplete),
+            }
+        }
+        Some('@') => {
+            let _ = input.next();
+            match input.next() {
+                Some(c) if c.is_ascii_alphanumeric() || *c == '@' => Some(Command::ReplayMacro(*c)),
+                Some(_) => None,
+                None => Some(Command::Incomplete),
+            }
+        }
         _ => None,
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// The quotes or brackets `ds` and `cs` act on, named like their text objects
+fn surrounding_kind(c: char) -> Option<TextObjectKind> {
+    text_object_kind(c)
+        .filter(|kind| matches!(kind, TextObjectKind::Quote(_) | TextObjectKind::Bracket(_)))
+}
+
+/// The characters put around the text by the surround commands
+///
+/// Either bracket of a pair surrounds with the pair, as do `b` for parentheses and `B`
+/// for braces like their text objects. Other characters surround with themselves.
+fn surround_pair(c: char) -> (char, char) {
+    match c {
+        'b' => ('(', ')'),
+        'B' => ('{', '}'),
+        ')' | ']' | '}' | '>' => (bracket_for(&c), c),
+        _ => (c, bracket_for(&c)),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Command {
     Incomplete,
     Delete,
@@ -121,18 +232,35 @@ pub enum Command {
     PasteBefore,
     EnterViAppend,
     EnterViInsert,
+    EnterViReplace,
     Undo,
+    UndoEarlier,
+    UndoLater,
     ChangeToLineEnd,
     DeleteToEnd,
     AppendToEnd,
     PrependToStart,
     RewriteCurrentLine,
     Change,
-    HistorySearch,
+    Search { backward: bool },
+    ExCommandLine,
     Switchcase,
     RepeatLastAction,
-    ChangeInside(char),
-    DeleteInside(char),
+    Yank,
+    YankLine,
+    Indent,
+    Dedent,
+    JoinLines,
+    SwapSelectionEnds,
+    Lowercase,
+    Uppercase,
+    SetMark(char),
+    RecordMacro(char),
+    ReplayMacro(char),
+    // `ys` with a motion and `S` in visual mode, waiting for the character until known
+    Surround(Option<char>),
+    DeleteSurround(TextObjectKind),
+    ChangeSurround(TextObjectKind, char),
 }
 
 impl Command {
@@ -140,23 +268,121 @@ impl Command {
         match self {
             Command::Delete => Some('d'),
             Command::Change => Some('c'),
+            Command::Yank => Some('y'),
+            Command::Indent => Some('>'),
+            Command::Dedent => Some('<'),
+            Command::Surround(None) => Some('s'),
             _ => None,
         }
     }
 
     pub fn requires_motion(&self) -> bool {
-        matches!(self, Command::Delete | Command::Change)
+        matches!(
+            self,
+            Command::Delete
+                | Command::Change
+                | Command::Yank
+                | Command::Indent
+                | Command::Dedent
+                | Command::Surround(None)
+        )
+    }
+
+    /// The edits of the command repeated or extended according to `count`, as in Vim
+    ///
+    /// Commands entering insert mode ignore the count, as do the operators acting on the
+    /// visual selection.
+    pub fn to_reedline(&self, vi_state: &mut Vi, count: usize) -> Vec<ReedlineOption> {
+        match self {
+            Self::Change if vi_state.mode.is_visual() => vec![
+                ReedlineOption::Edit(EditCommand::CutSelection),
+                ReedlineOption::Event(ReedlineEvent::Repaint),
+            ],
+            Self::Switchcase if vi_state.mode.is_visual() => {
+                vec![ReedlineOption::Edit(EditCommand::SwitchcaseSelection)]
+            }
+            Self::ReplaceChar(c) if vi_state.mode.is_visual() => {
+                vec![ReedlineOption::Edit(EditCommand::ReplaceSelectedChars(*c))]
+            }
+            Self::RecordMacro(name) => {
+                vec![ReedlineOption::Event(ReedlineEvent::RecordMacro(*name))]
+            }
+            // `@@` replays the macro replayed last
+            Self::ReplayMacro(name) => {
+                let name = if *name == '@' {
+                    match vi_state.last_macro {
+                        Some(name) => name,
+                        None => return vec![],
+                    }
+                } else {
+                    *name
+                };
+                vi_state.last_macro = Some(name);
+                repeat(
+                    vec![ReedlineOption::Event(ReedlineEvent::ReplayMacro(name))],
+                    count,
+                )
+            }
+            Self::Search { backward } if vi_state.buffer_search => {
+                vec![ReedlineOption::Event(ReedlineEvent::SearchBuffer {
+                    backward: *backward,
+                })]
+            }
+            // `3J` joins three lines
+            Self::JoinLines if count > 2 && !vi_state.mode.is_visual() => {
+                repeat(self.to_reedline_once(), count - 1)
+            }
+            Self::DeleteChar | Self::SubstituteCharWithInsert if count > 1 => {
+                let mut options = repeat(
+                    vec![ReedlineOption::Edit(EditCommand::MoveRight {
+                        select: true,
+                    })],
+                    count,
+                );
+                options.push(ReedlineOption::Edit(EditCommand::CutSelection));
+                options
+            }
+            Self::DeleteToEnd | Self::ChangeToLineEnd if count > 1 => {
+                let mut options = repeat(
+                    vec![ReedlineOption::Edit(EditCommand::MoveLineDown {
+                        select: true,
+                    })],
+                    count - 1,
+                );
+                options.push(ReedlineOption::Edit(EditCommand::MoveToLineEnd {
+                    select: true,
+                }));
+                options.push(ReedlineOption::Edit(EditCommand::CutSelection));
+                options
+            }
+            Self::RewriteCurrentLine if count > 1 => change_lines(&Motion::Line, count),
+            Self::YankLine => Self::Yank
+                .to_reedline_with_motion(&Motion::Line, vi_state, count)
+                .unwrap_or_default(),
+            Self::PasteAfter
+            | Self::PasteBefore
+            | Self::Undo
+            | Self::UndoEarlier
+            | Self::UndoLater
+            | Self::ReplaceChar(_)
+            | Self::Switchcase => repeat(self.to_reedline_once(), count),
+            _ => self.to_reedline_once(),
+        }
     }
 
-    pub fn to_reedline(&self, vi_state: &mut Vi) -> Vec<ReedlineOption> {
+    fn to_reedline_once(&self) -> Vec<ReedlineOption> {
         match self {
-            Self::EnterViInsert => vec![ReedlineOption::Event(ReedlineEvent::Repaint)],
+            Self::EnterViInsert | Self::EnterViReplace => {
+                vec![ReedlineOption::Event(ReedlineEvent::Repaint)]
+            }
             Self::EnterViAppend => vec![ReedlineOption::Edit(EditCommand::MoveRight {
                 select: false,
             })],
             Self::PasteAfter => vec![ReedlineOption::Edit(EditCommand::PasteCutBufferAfter)],
             Self::PasteBefore => vec![ReedlineOption::Edit(EditCommand::PasteCutBufferBefore)],
             Self::Undo => vec![ReedlineOption::Edit(EditCommand::Undo)],
+            Self::UndoEarlier => vec![ReedlineOption::Edit(EditCommand::UndoEarlier)],
+            Self::UndoLater => vec![ReedlineOption::Edit(EditCommand::UndoLater)],
             Self::ChangeToLineEnd => vec![ReedlineOption::Edit(EditCommand::ClearToLineEnd)],
             Self::DeleteToEnd => vec![ReedlineOption::Edit(EditCommand::CutToLineEnd)],
             Self::AppendToEnd => vec![ReedlineOption::Edit(EditCommand::MoveToLineEnd {
@@ -171,53 +397,124 @@ impl Command {
                 vec![ReedlineOption::Edit(EditCommand::ReplaceChar(*c))]
             }
             Self::SubstituteCharWithInsert => vec![ReedlineOption::Edit(EditCommand::CutChar)],
-            Self::HistorySearch => vec![ReedlineOption::Event(ReedlineEvent::SearchHistory)],
+            Self::Search { .. } => vec![ReedlineOption::Event(ReedlineEvent::SearchHistory)],
+            Self::ExCommandLine => vec![ReedlineOption::Event(ReedlineEvent::ExCommandLine)],
             Self::Switchcase => vec![ReedlineOption::Edit(EditCommand::SwitchcaseChar)],
             // Whenever a motion is required to finish the command we must be in visual mode
             Self::Delete | Self::Change => vec![ReedlineOption::Edit(EditCommand::CutSelection)],
-            Self::Incomplete => vec![ReedlineOption::Incomplete],
-            Self::RepeatLastAction => match &vi_state.previous {
-                Some(event) => vec![ReedlineOption::Event(event.clone())],
-                None => vec![],
-            },
-            Self::ChangeInside(left) if is_valid_change_inside_left(left) => {
-                let right = bracket_for(left);
-                vec![
-                    ReedlineOption::Edit(EditCommand::CutLeftBefore(*left)),
-                    ReedlineOption::Edit(EditCommand::CutRightBefore(right)),
-                ]
+            Self::Yank => vec![ReedlineOption::Edit(EditCommand::CopySelectionAndCollapse)],
+            Self::Indent => vec![ReedlineOption::Edit(EditCommand::Indent)],
+            Self::Dedent => vec![ReedlineOption::Edit(EditCommand::Dedent)],
+            Self::JoinLines => vec![ReedlineOption::Edit(EditCommand::JoinLines)],
+            Self::SwapSelectionEnds => vec![ReedlineOption::Edit(EditCommand::SwapSelectionEnds)],
+            Self::Lowercase => vec![ReedlineOption::Edit(EditCommand::LowercaseSelection)],
+            Self::Uppercase => vec![ReedlineOption::Edit(EditCommand::UppercaseSelection)],
+            Self::SetMark(name) => vec![ReedlineOption::Edit(EditCommand::SetMark(*name))],
+            Self::RecordMacro(name) => {
+                vec![ReedlineOption::Event(ReedlineEvent::RecordMacro(*name))]
             }
-            Self::ChangeInside(right) if is_valid_change_inside_right(right) => {
-                let left = bracket_for(right);
-                vec![
-                    ReedlineOption::Edit(EditCommand::CutLeftBefore(left)),
-                    ReedlineOption::Edit(EditCommand::CutRightBefore(*right)),
-                ]
+            Self::ReplayMacro(name) => {
+                vec![ReedlineOption::Event(ReedlineEvent::ReplayMacro(*name))]
             }
-            Self::ChangeInside(_) => {
-                vec![]
+            Self::Surround(c) => match c {
+                Some(c) => {
+                    let (left, right) = surround_pair(*c);
+                    vec![ReedlineOption::Edit(EditCommand::SurroundSelection {
+                        left,
+                        right,
+                    })]
+                }
+                None => vec![ReedlineOption::Incomplete],
+            },
+            Self::DeleteSurround(kind) => {
+                vec![ReedlineOption::Edit(EditCommand::DeleteSurrounding(*kind))]
             }
-            Self::DeleteInside(left) if is_valid_change_inside_left(left) => {
-                let right = bracket_for(left);
-                vec![
-                    ReedlineOption::Edit(EditCommand::CutLeftBefore(*left)),
-                    ReedlineOption::Edit(EditCommand::CutRightBefore(right)),
-                ]
+            Self::ChangeSurround(kind, c) => {
+                let (left, right) = surround_pair(*c);
+                vec![ReedlineOption::Edit(EditCommand::ChangeSurrounding {
+                    kind: *kind,
+                    left,
+                    right,
+                })]
             }
-            Self::DeleteInside(right) if is_valid_change_inside_right(right) => {
-                let left = bracket_for(right);
-                vec![
-                    ReedlineOption::Edit(EditCommand::CutLeftBefore(left)),
-                    ReedlineOption::Edit(EditCommand::CutRightBefore(*right)),
-                ]
+            Self::YankLine => vec![ReedlineOption::Edit(EditCommand::CopyCurrentLine)],
+            Self::Incomplete => vec![ReedlineOption::Incomplete],
+            // Replays the previous sequence, see `ParsedViSequence::to_reedline_event`
+            Self::RepeatLastAction => vec![],
+        }
+    }
+
+    /// The edits applying the operator `count` times the motion, as in Vim
+    ///
+    /// With a count other than one the operators act on the selection made by the repeated
+    /// motion. `dd`, `yy`, `j` and `k` act on whole lines, text objects and `%` ignore
+    /// the count.
+    pub fn to_reedline_with_motion(
+        &self,
+        motion: &Motion,
+        vi_state: &mut Vi,
+        count: usize,
+    ) -> Option<Vec<ReedlineOption>> {
+        let selection_edit = match self {
+            Self::Delete | Self::Change => EditCommand::CutSelection,
+            Self::Yank => EditCommand::CopySelectionAndCollapse,
+            Self::Indent => EditCommand::Indent,
+            Self::Dedent => EditCommand::Dedent,
+            Self::Surround(Some(c)) => {
+                let (left, right) = surround_pair(*c);
+                EditCommand::SurroundSelection { left, right }
             }
-            Self::DeleteInside(_) => {
-                vec![]
+            _ => return None,
+        };
+        let single_motion = matches!(self, Self::Delete | Self::Change | Self::Yank)
+            && ((count == 1
+                && !matches!(
+                    motion,
+                    Motion::Up | Motion::Down | Motion::Mark { .. } | Motion::SearchMatch { .. }
+                ))
+                || matches!(motion, Motion::TextObject(_) | Motion::MatchingBracket));
+
+        let mut options = match (self, motion) {
+            _ if single_motion => return self.to_reedline_with_single_motion(motion, vi_state),
+            (_, Motion::TextObject(text_object)) => vec![
+                ReedlineOption::Edit(EditCommand::SelectTextObject(*text_object)),
+                ReedlineOption::Edit(selection_edit),
+            ],
+            // Like in Vim `'` makes the operator act on whole lines
+            (_, Motion::Mark { name, line: true }) => vec![
+                ReedlineOption::Edit(EditCommand::ToggleLineSelection),
+                ReedlineOption::Edit(EditCommand::MoveToMark {
+                    name: *name,
+                    line: true,
+                    select: true,
+                }),
+                ReedlineOption::Edit(selection_edit),
+            ],
+            (Self::Change, Motion::Line | Motion::Up | Motion::Down) => change_lines(motion, count),
+            (_, Motion::Line | Motion::Up | Motion::Down) => {
+                let mut options = select_lines(motion, count);
+                options.push(ReedlineOption::Edit(selection_edit));
+                options
             }
+            _ => {
+                // Like `ce`, `cw` keeps the whitespace behind the word
+                let motion = match (self, motion) {
+                    (Self::Change, Motion::NextWord) => &Motion::NextWordEnd,
+                    (Self::Change, Motion::NextBigWord) => &Motion::NextBigWordEnd,
+                    _ => motion,
+                };
+                let mut options = motion.to_selection(vi_state, count)?;
+                options.push(ReedlineOption::Edit(selection_edit));
+                options
+            }
+        };
+        if *self == Self::Change {
+            options.push(ReedlineOption::Event(ReedlineEvent::Repaint));
         }
+        Some(options)
     }
 
-    pub fn to_reedline_with_motion(
+    fn to_reedline_with_single_motion(
         &self,
         motion: &Motion,
         vi_state: &mut Vi,
@@ -259,8 +556,15 @@ impl Command {
                 Motion::Start => Some(vec![ReedlineOption::Edit(EditCommand::CutFromLineStart)]),
                 Motion::Left => Some(vec![ReedlineOption::Edit(EditCommand::Backspace)]),
                 Motion::Right => Some(vec![ReedlineOption::Edit(EditCommand::Delete)]),
-                Motion::Up => None,
-                Motion::Down => None,
+                Motion::Up | Motion::Down | Motion::Mark { .. } | Motion::SearchMatch { .. } => {
+                    None
+                }
+                Motion::MatchingBracket => Some(vec![ReedlineOption::Edit(
+                    EditCommand::CutToMatchingBracket,
+                )]),
+                Motion::TextObject(text_object) => Some(vec![ReedlineOption::Edit(
+                    EditCommand::CutTextObject(*text_object),
+                )]),
                 Motion::ReplayCharSearch => vi_state
                     .last_char_search
                     .as_ref()
@@ -314,8 +618,16 @@ impl Command {
                     }
                     Motion::Left => Some(vec![ReedlineOption::Edit(EditCommand::Backspace)]),
                     Motion::Right => Some(vec![ReedlineOption::Edit(EditCommand::Delete)]),
-                    Motion::Up => None,
-                    Motion::Down => None,
+                    Motion::Up
+                    | Motion::Down
+                    | Motion::Mark { .. }
+                    | Motion::SearchMatch { .. } => None,
+                    Motion::MatchingBracket => Some(vec![ReedlineOption::Edit(
+                        EditCommand::CutToMatchingBracket,
+                    )]),
+                    Motion::TextObject(text_object) => Some(vec![ReedlineOption::Edit(
+                        EditCommand::CutTextObject(*text_object),
+                    )]),
                     Motion::ReplayCharSearch => vi_state
                         .last_char_search
                         .as_ref()
@@ -332,12 +644,63 @@ impl Command {
                     vec
                 })
             }
+            Self::Yank => match motion {
+                Motion::Line => Some(vec![ReedlineOption::Edit(EditCommand::CopyCurrentLine)]),
+                Motion::TextObject(text_object) => Some(vec![ReedlineOption::Edit(
+                    EditCommand::CopyTextObject(*text_object),
+                )]),
+                _ => motion.to_selection(vi_state, 1).map(|mut options| {
+                    options.push(ReedlineOption::Edit(EditCommand::CopySelectionAndCollapse));
+                    options
+                }),
+            },
             _ => None,
         }
     }
 }
 
-fn bracket_for(c: &char) -> char {
+/// Select the whole lines the vertical motion moves over
+fn select_lines(motion: &Motion, count: usize) -> Vec<ReedlineOption> {
+    let (line_move, count) = match motion {
+        Motion::Up => (EditCommand::MoveLineUp { select: true }, count),
+        Motion::Down => (EditCommand::MoveLineDown { select: true }, count),
+        _ => (EditCommand::MoveLineDown { select: true }, count - 1),
+    };
+    let mut options = vec![ReedlineOption::Edit(EditCommand::ToggleLineSelection)];
+    options.extend(repeat(vec![ReedlineOption::Edit(line_move)], count));
+    options
+}
+
+/// Cut the text of the lines the vertical motion moves over, keeping the last line ending
+fn change_lines(motion: &Motion, count: usize) -> Vec<ReedlineOption> {
+    let (first, line_move, last, count) = match motion {
+        Motion::Up => (
+            EditCommand::MoveToLineEnd { select: false },
+            EditCommand::MoveLineUp { select: true },
+            EditCommand::MoveToLineStart { select: true },
+            count,
+        ),
+        Motion::Down => (
+            EditCommand::MoveToLineStart { select: false },
+            EditCommand::MoveLineDown { select: true },
+            EditCommand::MoveToLineEnd { select: true },
+            count,
+        ),
+        _ => (
+            EditCommand::MoveToLineStart { select: false },
+            EditCommand::MoveLineDown { select: true },
+            EditCommand::MoveToLineEnd { select: true },
+            count - 1,
+        ),
+    };
+    let mut options = vec![ReedlineOption::Edit(first)];
+    options.extend(repeat(vec![ReedlineOption::Edit(line_move)], count));
+    options.push(ReedlineOption::Edit(last));
+    options.push(ReedlineOption::Edit(EditCommand::CutSelection));
+    options
+}
+
+pub(crate) fn bracket_for(c: &char) -> char {
     match *c {
         '(' => ')',
         '[' => ']',
@@ -4,9 +4,13 @@ mod parser;
 mod vi_keybindings;
 
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
-pub use vi_keybindings::{default_vi_insert_keybindings, default_vi_normal_keybindings};
+pub use vi_keybindings::{
+    default_vi_insert_keybindings, default_vi_normal_keybindings, default_vi_replace_keybindings,
+};
 
-use self::motion::ViCharSearch;
+use self::{
+    motion::ViCharSearch, parser::ParsedViSequence, vi_keybindings::replace_keybindings_from,
+};
 
 use super::EditMode;
 use crate::{
@@ -20,6 +24,16 @@ enum ViMode {
     Normal,
     Insert,
     Visual,
+    VisualLine,
+    Replace,
+    // Typing into the command line of a buffer search or of `:`
+    CommandLine,
+}
+
+impl ViMode {
+    fn is_visual(self) -> bool {
+        matches!(self, ViMode::Visual | ViMode::VisualLine)
+    }
 }
 
 /// This parses incoming input `Event`s like a Vi-Style editor
@@ -27,10 +41,20 @@ pub struct Vi {
     cache: Vec<char>,
     insert_keybindings: Keybindings,
     normal_keybindings: Keybindings,
+    replace_keybindings: Keybindings,
     mode: ViMode,
-    previous: Option<ReedlineEvent>,
+    // last sequence changing the buffer, for .
+    previous: Option<ParsedViSequence>,
     // last f, F, t, T motion for ; and ,
     last_char_search: Option<ViCharSearch>,
+    // whether / and ? search the buffer instead of the history
+    buffer_search: bool,
+    // mode to return to after typing into the command line
+    mode_before_command_line: ViMode,
+    // whether the engine records a macro, stopped by the next q
+    recording_macro: bool,
+    // last macro replayed, for @@
+    last_macro: Option<char>,
 }
 
 impl Default for Vi {
@@ -38,23 +62,71 @@ impl Default for Vi {
         Vi {
             insert_keybindings: default_vi_insert_keybindings(),
             normal_keybindings: default_vi_normal_keybindings(),
+            replace_keybindings: default_vi_replace_keybindings(),
             cache: Vec::new(),
             mode: ViMode::Insert,
             previous: None,
             last_char_search: None,
+            buffer_search: false,
+            mode_before_command_line: ViMode::Normal,
+            recording_macro: false,
+            last_macro: None,
         }
     }
 }
 
 impl Vi {
     /// Creates Vi editor using defined keybindings
+    ///
+    /// The replace mode uses the insert keybindings, except for Backspace restoring the
+    /// overwritten text. [`Vi::with_replace_keybindings`] overrides them.
     pub fn new(insert_keybindings: Keybindings, normal_keybindings: Keybindings) -> Self {
         Self {
+            replace_keybindings: replace_keybindings_from(insert_keybindings.clone()),
             insert_keybindings,
             normal_keybindings,
             ..Default::default()
         }
     }
+
+    /// Use the keybindings in replace mode, entered with `R`
+    #[must_use]
+    pub fn with_replace_keybindings(mut self, replace_keybindings: Keybindings) -> Self {
+        self.replace_keybindings = replace_keybindings;
+        self
+    }
+
+    /// Search the buffer with `/` and `?` instead of the history
+    ///
+    /// `n` and `N` move to the next and previous match of the last buffer search.
+    #[must_use]
+    pub fn with_buffer_search(mut self, buffer_search: bool) -> Self {
+        self.buffer_search = buffer_search;
+        self
+    }
+
+    fn parse_command_line_key(&mut self, modifiers: KeyModifiers, code: KeyCode) -> ReedlineEvent {
+        match (modifiers, code) {
+            (KeyModifiers::NONE, KeyCode::Char(c)) => {
+                ReedlineEvent::Edit(vec![EditCommand::InsertChar(c)])
+            }
+            (KeyModifiers::SHIFT, KeyCode::Char(c)) => {
+                ReedlineEvent::Edit(vec![EditCommand::InsertChar(c.to_ascii_uppercase())])
+            }
+            (KeyModifiers::NONE, KeyCode::Backspace) => {
+                ReedlineEvent::Edit(vec![EditCommand::Backspace])
+            }
+            (KeyModifiers::NONE, KeyCode::Enter) => {
+                self.mode = self.mode_before_command_line;
+                ReedlineEvent::Enter
+            }
+            (KeyModifiers::NONE, KeyCode::Esc) | (KeyModifiers::CONTROL, KeyCode::Char('c')) => {
+                self.mode = self.mode_before_command_line;
+                ReedlineEvent::Multiple(vec![ReedlineEvent::Esc, ReedlineEvent::Repaint])
+            }
+            _ => ReedlineEvent::None,
+        }
+    }
 }
 
 impl EditMode for Vi {
@@ -63,12 +135,55 @@ impl EditMode for Vi {
             Event::Key(KeyEvent {
                 code, modifiers, ..
             }) => match (self.mode, modifiers, code) {
+                (ViMode::CommandLine, _, _) => self.parse_command_line_key(modifiers, code),
                 (ViMode::Normal, KeyModifiers::NONE, KeyCode::Char('v')) => {
                     self.cache.clear();
                     self.mode = ViMode::Visual;
                     ReedlineEvent::Multiple(vec![ReedlineEvent::Esc, ReedlineEvent::Repaint])
                 }
-                (ViMode::Normal | ViMode::Visual, modifier, KeyCode::Char(c)) => {
+                (ViMode::Normal, KeyModifiers::CONTROL, KeyCode::Char('v')) => {
+                    self.cache.clear();
+                    self.mode = ViMode::Visual;
+                    ReedlineEvent::Multiple(vec![
+                        ReedlineEvent::Esc,
+                        ReedlineEvent::Edit(vec![EditCommand::ToggleBlockSelection]),
+                        ReedlineEvent::Repaint,
+                    ])
+                }
+                (
+                    ViMode::Normal | ViMode::Visual,
+                    KeyModifiers::NONE | KeyModifiers::SHIFT,
+                    KeyCode::Char('V'),
+                ) if self.cache.is_empty() => {
+                    let mut events = vec![
+                        ReedlineEvent::Edit(vec![EditCommand::ToggleLineSelection]),
+                        ReedlineEvent::Repaint,
+                    ];
+                    if self.mode == ViMode::Normal {
+                        events.insert(0, ReedlineEvent::Esc);
+                    }
+                    self.mode = ViMode::VisualLine;
+                    ReedlineEvent::Multiple(events)
+                }
+                (ViMode::Normal, KeyModifiers::NONE, KeyCode::Char('q'))
+                    if self.recording_macro && self.cache.is_empty() =>
+                {
+                    ReedlineEvent::StopMacroRecording
+                }
+                (ViMode::VisualLine, KeyModifiers::NONE, KeyCode::Char('v'))
+                    if self.cache.is_empty() =>
+                {
+                    self.mode = ViMode::Visual;
+                    ReedlineEvent::Multiple(vec![
+                        ReedlineEvent::Edit(vec![EditCommand::ToggleLineSelection]),
+                        ReedlineEvent::Repaint,
+                    ])
+                }
+                (
+                    ViMode::Normal | ViMode::Visual | ViMode::VisualLine,
+                    modifier,
+                    KeyCode::Char(c),
+                ) => {
                     let c = c.to_ascii_lowercase();
 
                     if let Some(event) = self
@@ -83,17 +198,22 @@ impl EditMode for Vi {
                             c
                         });
 
-                        let res = parse(&mut self.cache.iter().peekable());
+                        let res = parse(&mut self.cache.iter().peekable(), self.mode);
 
                         if !res.is_valid() {
                             self.cache.clear();
                             ReedlineEvent::None
                         } else if res.is_complete(self.mode) {
-                            if let Some(mode) = res.changes_mode() {
-                                self.mode = mode;
-                            }
-
+                            // The edits depend on the mode the sequence was typed in
                             let event = res.to_reedline_event(self);
+                            match res.changes_mode(self) {
+                                Some(ViMode::CommandLine) => {
+                                    self.mode_before_command_line = self.mode;
+                                    self.mode = ViMode::CommandLine;
+                                }
+                                Some(mode) => self.mode = mode,
+                                None => {}
+                            }
                             self.cache.clear();
                             event
                         } else {
@@ -103,7 +223,7 @@ impl EditMode for Vi {
                         ReedlineEvent::None
                     }
                 }
-                (ViMode::Insert, modifier, KeyCode::Char(c)) => {
+                (ViMode::Insert | ViMode::Replace, modifier, KeyCode::Char(c)) => {
                     // Note. The modifier can also be a combination of modifiers, for
                     // example:
                     //     KeyModifiers::CONTROL | KeyModifiers::ALT
@@ -117,7 +237,12 @@ impl EditMode for Vi {
                         _ => c.to_ascii_lowercase(),
                     };
 
-                    self.insert_keybindings
+                    let keybindings = if self.mode == ViMode::Replace {
+                        &self.replace_keybindings
+                    } else {
+                        &self.insert_keybindings
+                    };
+                    keybindings
                         .find_binding(modifier, KeyCode::Char(c))
                         .unwrap_or_else(|| {
                             if modifier == KeyModifiers::NONE
@@ -128,13 +253,16 @@ impl EditMode for Vi {
                                         | KeyModifiers::ALT
                                         | KeyModifiers::SHIFT
                             {
-                                ReedlineEvent::Edit(vec![EditCommand::InsertChar(
-                                    if modifier == KeyModifiers::SHIFT {
-                                        c.to_ascii_uppercase()
-                                    } else {
-                                        c
-                                    },
-                                )])
+                                let c = if modifier == KeyModifiers::SHIFT {
+                                    c.to_ascii_uppercase()
+                                } else {
+                                    c
+                                };
+                                ReedlineEvent::Edit(vec![if self.mode == ViMode::Replace {
+                                    EditCommand::OverwriteChar(c)
+                                } else {
+                                    EditCommand::InsertChar(c)
+                                }])
                             } else {
                                 ReedlineEvent::None
                             }
@@ -149,7 +277,7 @@ impl EditMode for Vi {
                     self.mode = ViMode::Insert;
                     ReedlineEvent::Enter
                 }
-                (ViMode::Normal | ViMode::Visual, _, _) => self
+                (ViMode::Normal | ViMode::Visual | ViMode::VisualLine, _, _) => self
                     .normal_keybindings
                     .find_binding(modifiers, code)
                     .unwrap_or(ReedlineEvent::None),
@@ -157,6 +285,10 @@ impl EditMode for Vi {
                     .insert_keybindings
                     .find_binding(modifiers, code)
                     .unwrap_or(ReedlineEvent::None),
+                (ViMode::Replace, _, _) => self
+                    .replace_keybindings
+                    .find_binding(modifiers, code)
+                    .unwrap_or(ReedlineEvent::None),
             },
 
             Event::Mouse(_) => ReedlineEvent::Mouse,
@@ -171,10 +303,17 @@ impl EditMode for Vi {
 
     fn edit_mode(&self) -> PromptEditMode {
         match self.mode {
-            ViMode::Normal | ViMode::Visual => PromptEditMode::Vi(PromptViMode::Normal),
+            ViMode::Normal | ViMode::CommandLine => PromptEditMode::Vi(PromptViMode::Normal),
             ViMode::Insert => PromptEditMode::Vi(PromptViMode::Insert),
+            ViMode::Visual => PromptEditMode::Vi(PromptViMode::Visual),
+            ViMode::VisualLine => PromptEditMode::Vi(PromptViMode::VisualLine),
+            ViMode::Replace => PromptEditMode::Vi(PromptViMode::Replace),
         }
     }
+
+    fn set_macro_recording(&mut self, recording: bool) {
+        self.recording_macro = recording;
+    }
 }
 
 #[cfg(test)]
@@ -182,6 +321,280 @@ mod test {
     use super::*;
     use pretty_assertions::assert_eq;
 
+    #[test]
+    fn ctrl_v_starts_block_selection() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        let ctrl_v = ReedlineRawEvent::try_from(Event::Key(KeyEvent::new(
+            KeyCode::Char('v'),
+            KeyModifiers::CONTROL,
+        )))
+        .unwrap();
+        let result = vi.parse_event(ctrl_v);
+
+        assert_eq!(
+            result,
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Esc,
+                ReedlineEvent::Edit(vec![EditCommand::ToggleBlockSelection]),
+                ReedlineEvent::Repaint
+            ])
+        );
+        assert!(matches!(vi.mode, ViMode::Visual));
+    }
+
+    fn key_press(vi: &mut Vi, code: KeyCode, modifiers: KeyModifiers) -> ReedlineEvent {
+        vi.parse_event(
+            ReedlineRawEvent::try_from(Event::Key(KeyEvent::new(code, modifiers))).unwrap(),
+        )
+    }
+
+    #[test]
+    fn shift_v_starts_line_selection() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        let result = key_press(&mut vi, KeyCode::Char('V'), KeyModifiers::SHIFT);
+
+        assert_eq!(
+            result,
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Esc,
+                ReedlineEvent::Edit(vec![EditCommand::ToggleLineSelection]),
+                ReedlineEvent::Repaint
+            ])
+        );
+        assert!(matches!(
+            vi.edit_mode(),
+            PromptEditMode::Vi(PromptViMode::VisualLine)
+        ));
+
+        let result = key_press(&mut vi, KeyCode::Char('v'), KeyModifiers::NONE);
+
+        assert_eq!(
+            result,
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Edit(vec![EditCommand::ToggleLineSelection]),
+                ReedlineEvent::Repaint
+            ])
+        );
+        assert!(matches!(
+            vi.edit_mode(),
+            PromptEditMode::Vi(PromptViMode::Visual)
+        ));
+    }
+
+    #[test]
+    fn visual_operator_leads_to_normal_mode() {
+        let mut vi = Vi {
+            mode: ViMode::VisualLine,
+            ..Default::default()
+        };
+        let result = key_press(&mut vi, KeyCode::Char('~'), KeyModifiers::NONE);
+
+        assert_eq!(
+            result,
+            ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+                EditCommand::SwitchcaseSelection
+            ])])
+        );
+        assert!(matches!(vi.mode, ViMode::Normal));
+    }
+
+    #[test]
+    fn visual_change_leads_to_insert_mode() {
+        let mut vi = Vi {
+            mode: ViMode::Visual,
+            ..Default::default()
+        };
+        let _ = key_press(&mut vi, KeyCode::Char('c'), KeyModifiers::NONE);
+
+        assert!(matches!(vi.mode, ViMode::Insert));
+    }
+
+    #[test]
+    fn replace_mode_overwrites_text() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        let _ = key_press(&mut vi, KeyCode::Char('R'), KeyModifiers::SHIFT);
+        assert!(matches!(
+            vi.edit_mode(),
+            PromptEditMode::Vi(PromptViMode::Replace)
+        ));
+
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Char('a'), KeyModifiers::NONE),
+            ReedlineEvent::Edit(vec![EditCommand::OverwriteChar('a')])
+        );
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Backspace, KeyModifiers::NONE),
+            ReedlineEvent::Edit(vec![EditCommand::BackspaceOverwrite])
+        );
+
+        let _ = key_press(&mut vi, KeyCode::Esc, KeyModifiers::NONE);
+        assert!(matches!(vi.mode, ViMode::Normal));
+    }
+
+    #[test]
+    fn replace_mode_uses_the_given_insert_keybindings() {
+        let mut insert_keybindings = default_vi_insert_keybindings();
+        insert_keybindings.add_binding(
+            KeyModifiers::CONTROL,
+            KeyCode::Char('t'),
+            ReedlineEvent::Edit(vec![EditCommand::SwapGraphemes]),
+        );
+        let mut vi = Vi::new(insert_keybindings, default_vi_normal_keybindings());
+        vi.mode = ViMode::Replace;
+
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Char('t'), KeyModifiers::CONTROL),
+            ReedlineEvent::Edit(vec![EditCommand::SwapGraphemes])
+        );
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Backspace, KeyModifiers::NONE),
+            ReedlineEvent::Edit(vec![EditCommand::BackspaceOverwrite])
+        );
+    }
+
+    #[test]
+    fn ctrl_o_navigates_the_jump_list_in_normal_mode_and_opens_the_editor_in_insert_mode() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Char('o'), KeyModifiers::CONTROL),
+            ReedlineEvent::Edit(vec![EditCommand::MoveToOlderJump])
+        );
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Char('i'), KeyModifiers::CONTROL),
+            ReedlineEvent::Edit(vec![EditCommand::MoveToNewerJump])
+        );
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Tab, KeyModifiers::NONE),
+            ReedlineEvent::None
+        );
+
+        vi.mode = ViMode::Insert;
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Char('o'), KeyModifiers::CONTROL),
+            ReedlineEvent::OpenEditor
+        );
+    }
+
+    #[test]
+    fn slash_searches_the_buffer_when_configured() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Char('/'), KeyModifiers::NONE),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::SearchHistory])
+        );
+        assert!(matches!(vi.mode, ViMode::Insert));
+
+        let mut vi = Vi {
+            mode: ViMode::Visual,
+            ..Default::default()
+        }
+        .with_buffer_search(true);
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Char('?'), KeyModifiers::NONE),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::SearchBuffer { backward: true }])
+        );
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Char('n'), KeyModifiers::NONE),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('n')])
+        );
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Enter, KeyModifiers::NONE),
+            ReedlineEvent::Enter
+        );
+        assert!(matches!(vi.mode, ViMode::Visual));
+
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Char('N'), KeyModifiers::SHIFT),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+                EditCommand::RepeatBufferSearch {
+                    reverse: true,
+                    select: true
+                }
+            ])])
+        );
+    }
+
+    #[test]
+    fn colon_opens_the_ex_command_line() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Char(':'), KeyModifiers::SHIFT),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::ExCommandLine])
+        );
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Char('w'), KeyModifiers::NONE),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('w')])
+        );
+        let _ = key_press(&mut vi, KeyCode::Esc, KeyModifiers::NONE);
+        assert!(matches!(vi.mode, ViMode::Normal));
+    }
+
+    #[test]
+    fn q_records_and_at_replays_macros() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        let _ = key_press(&mut vi, KeyCode::Char('q'), KeyModifiers::NONE);
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Char('a'), KeyModifiers::NONE),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::RecordMacro('a')])
+        );
+        // Only the engine starting the recording makes `q` stop it
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Char('q'), KeyModifiers::NONE),
+            ReedlineEvent::None
+        );
+        let _ = key_press(&mut vi, KeyCode::Esc, KeyModifiers::NONE);
+        vi.set_macro_recording(true);
+        let _ = key_press(&mut vi, KeyCode::Char('x'), KeyModifiers::NONE);
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Char('q'), KeyModifiers::NONE),
+            ReedlineEvent::StopMacroRecording
+        );
+        vi.set_macro_recording(false);
+
+        let _ = key_press(&mut vi, KeyCode::Char('2'), KeyModifiers::NONE);
+        let _ = key_press(&mut vi, KeyCode::Char('@'), KeyModifiers::SHIFT);
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Char('a'), KeyModifiers::NONE),
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::ReplayMacro('a'),
+                ReedlineEvent::ReplayMacro('a')
+            ])
+        );
+        let _ = key_press(&mut vi, KeyCode::Char('@'), KeyModifiers::SHIFT);
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Char('@'), KeyModifiers::SHIFT),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::ReplayMacro('a')])
+        );
+
+        // `.` still repeats the `x`
+        assert_eq!(
+            key_press(&mut vi, KeyCode::Char('.'), KeyModifiers::NONE),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutChar])])
+        );
+    }
+
     #[test]
     fn esc_leads_to_normal_mode_test() {
         let mut vi = Vi::default();
@@ -1,8 +1,9 @@
 use std::iter::Peekable;
 
-use crate::{edit_mode::vi::ViMode, EditCommand, ReedlineEvent, Vi};
+use crate::{EditCommand, ReedlineEvent, TextObject, TextObjectKind, TextObjectScope, Vi};
 
-use super::parser::{ParseResult, ReedlineOption};
+use super::command::{bracket_for, is_valid_change_inside_left, is_valid_change_inside_right};
+use super::parser::{repeat, ParseResult, ReedlineOption};
 
 pub fn parse_motion<'iter, I>(
     input: &mut Peekable<I>,
@@ -104,10 +105,50 @@ where
             let _ = input.next();
             ParseResult::Valid(Motion::ReplayCharSearch)
         }
+        Some(&&jump @ ('`' | '\'')) => {
+            let _ = input.next();
+            match input.next() {
+                Some(name) if name.is_ascii_lowercase() => ParseResult::Valid(Motion::Mark {
+                    name: *name,
+                    line: jump == '\'',
+                }),
+                Some(_) => ParseResult::Invalid,
+                None => ParseResult::Incomplete,
+            }
+        }
+        Some('%') => {
+            let _ = input.next();
+            ParseResult::Valid(Motion::MatchingBracket)
+        }
+        Some('n') => {
+            let _ = input.next();
+            ParseResult::Valid(Motion::SearchMatch { reverse: false })
+        }
+        Some('N') => {
+            let _ = input.next();
+            ParseResult::Valid(Motion::SearchMatch { reverse: true })
+        }
         Some(',') => {
             let _ = input.next();
             ParseResult::Valid(Motion::ReverseCharSearch)
         }
+        Some(&&scope @ ('i' | 'a')) => {
+            let _ = input.next();
+            let scope = if scope == 'i' {
+                TextObjectScope::Inner
+            } else {
+                TextObjectScope::Around
+            };
+            match input.next() {
+                Some(c) => match text_object_kind(*c) {
+                    Some(kind) => {
+                        ParseResult::Valid(Motion::TextObject(TextObject { scope, kind }))
+                    }
+                    None => ParseResult::Invalid,
+                },
+                None => ParseResult::Incomplete,
+            }
+        }
         ch if ch == command_char.as_ref().as_ref() && command_char.is_some() => {
             let _ = input.next();
             ParseResult::Valid(Motion::Line)
@@ -117,7 +158,22 @@ where
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+pub fn text_object_kind(c: char) -> Option<TextObjectKind> {
+    match c {
+        'w' => Some(TextObjectKind::Word),
+        'W' => Some(TextObjectKind::BigWord),
+        'p' => Some(TextObjectKind::Paragraph),
+        'a' => Some(TextObjectKind::Argument),
+        'b' => Some(TextObjectKind::Bracket('(')),
+        'B' => Some(TextObjectKind::Bracket('{')),
+        '"' | '\'' | '`' => Some(TextObjectKind::Quote(c)),
+        c if is_valid_change_inside_left(&c) => Some(TextObjectKind::Bracket(c)),
+        c if is_valid_change_inside_right(&c) => Some(TextObjectKind::Bracket(bracket_for(&c))),
+        _ => None,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Motion {
     Left,
     Right,
@@ -138,11 +194,84 @@ pub enum Motion {
     LeftBefore(char),
     ReplayCharSearch,
     ReverseCharSearch,
+    MatchingBracket,
+    TextObject(TextObject),
+    Mark { name: char, line: bool },
+    SearchMatch { reverse: bool },
 }
 
 impl Motion {
-    pub fn to_reedline(&self, vi_state: &mut Vi) -> Vec<ReedlineOption> {
-        let select_mode = vi_state.mode == ViMode::Visual;
+    /// The edits of the motion repeated `count` times
+    pub fn to_reedline(&self, vi_state: &mut Vi, count: usize) -> Vec<ReedlineOption> {
+        let select_mode = vi_state.mode.is_visual();
+        match self {
+            Motion::MatchingBracket | Motion::TextObject(_) | Motion::Mark { .. } => {
+                self.to_reedline_selecting(vi_state, select_mode)
+            }
+            _ => self.to_reedline_repeated(vi_state, select_mode, count),
+        }
+    }
+
+    /// Edits selecting the text the motion moves over, `None` if the motion can't select
+    ///
+    /// Motions ending on the last character they cover (like `e` or `f`) select it as well.
+    pub fn to_selection(&self, vi_state: &mut Vi, count: usize) -> Option<Vec<ReedlineOption>> {
+        match self {
+            Motion::Left => Some(repeat(
+                vec![ReedlineOption::Edit(EditCommand::MoveLeft { select: true })],
+                count,
+            )),
+            Motion::Right => Some(repeat(
+                vec![ReedlineOption::Edit(EditCommand::MoveRight {
+                    select: true,
+                })],
+                count,
+            )),
+            Motion::Up
+            | Motion::Down
+            | Motion::Line
+            | Motion::TextObject(_)
+            | Motion::Mark { line: true, .. } => None,
+            _ => {
+                let mut options = self.to_reedline_repeated(vi_state, true, count);
+                if matches!(
+                    self,
+                    Motion::NextWordEnd
+                        | Motion::NextBigWordEnd
+                        | Motion::RightUntil(_)
+                        | Motion::RightBefore(_)
+                        | Motion::MatchingBracket
+                ) {
+                    options.push(ReedlineOption::Edit(EditCommand::MoveRight {
+                        select: true,
+                    }));
+                }
+                Some(options)
+            }
+        }
+    }
+
+    fn to_reedline_repeated(
+        &self,
+        vi_state: &mut Vi,
+        select_mode: bool,
+        count: usize,
+    ) -> Vec<ReedlineOption> {
+        // Repeating `t` would stay in front of the same character
+        let until = match self {
+            Motion::RightBefore(c) if count > 1 => Motion::RightUntil(*c),
+            Motion::LeftBefore(c) if count > 1 => Motion::LeftUntil(*c),
+            _ => return repeat(self.to_reedline_selecting(vi_state, select_mode), count),
+        };
+        let mut options = repeat(
+            until.to_reedline_selecting(vi_state, select_mode),
+            count - 1,
+        );
+        options.extend(self.to_reedline_selecting(vi_state, select_mode));
+        options
+    }
+
+    fn to_reedline_selecting(&self, vi_state: &mut Vi, select_mode: bool) -> Vec<ReedlineOption> {
         match self {
             Motion::Left => vec![ReedlineOption::Event(ReedlineEvent::UntilFound(vec![
                 ReedlineEvent::MenuLeft,
@@ -157,15 +286,26 @@ impl Motion {
                     select: select_mode,
                 }]),
             ]))],
+            // Selecting stays within the buffer instead of navigating the history
+            Motion::Up if select_mode => {
+                vec![ReedlineOption::Event(ReedlineEvent::UntilFound(vec![
+                    ReedlineEvent::MenuUp,
+                    ReedlineEvent::Edit(vec![EditCommand::MoveLineUp { select: true }]),
+                ]))]
+            }
+            Motion::Down if select_mode => {
+                vec![ReedlineOption::Event(ReedlineEvent::UntilFound(vec![
+                    ReedlineEvent::MenuDown,
+                    ReedlineEvent::Edit(vec![EditCommand::MoveLineDown { select: true }]),
+                ]))]
+            }
             Motion::Up => vec![ReedlineOption::Event(ReedlineEvent::UntilFound(vec![
                 ReedlineEvent::MenuUp,
                 ReedlineEvent::Up,
-                // todo: add EditCommand::MoveLineUp
             ]))],
             Motion::Down => vec![ReedlineOption::Event(ReedlineEvent::UntilFound(vec![
                 ReedlineEvent::MenuDown,
                 ReedlineEvent::Down,
-                // todo: add EditCommand::MoveLineDown
             ]))],
             Motion::NextWord => vec![ReedlineOption::Edit(EditCommand::MoveWordRightStart {
                 select: select_mode,
@@ -222,6 +362,27 @@ impl Motion {
                     select: select_mode,
                 })]
             }
+            Motion::MatchingBracket => {
+                vec![ReedlineOption::Edit(EditCommand::MoveToMatchingBracket {
+                    select: select_mode,
+                })]
+            }
+            Motion::TextObject(text_object) => {
+                vec![ReedlineOption::Edit(EditCommand::SelectTextObject(
+                    *text_object,
+                ))]
+            }
+            Motion::Mark { name, line } => vec![ReedlineOption::Edit(EditCommand::MoveToMark {
+                name: *name,
+                line: *line,
+                select: select_mode,
+            })],
+            Motion::SearchMatch { reverse } => {
+                vec![ReedlineOption::Edit(EditCommand::RepeatBufferSearch {
+                    reverse: *reverse,
+                    select: select_mode,
+                })]
+            }
             Motion::ReplayCharSearch => {
                 if let Some(char_search) = vi_state.last_char_search.as_ref() {
                     vec![ReedlineOption::Edit(char_search.to_move(select_mode))]
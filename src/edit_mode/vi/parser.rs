@@ -1,6 +1,4 @@
-use super::command::{
-    is_valid_change_inside_left, is_valid_change_inside_right, parse_command, Command,
-};
+use super::command::{parse_command, Command};
 use super::motion::{parse_motion, Motion};
 use crate::{edit_mode::vi::ViMode, EditCommand, ReedlineEvent, Vi};
 use std::iter::Peekable;
@@ -12,6 +10,12 @@ pub enum ReedlineOption {
     Incomplete,
 }
 
+/// The options repeated `count` times in a row
+pub fn repeat(options: Vec<ReedlineOption>, count: usize) -> Vec<ReedlineOption> {
+    let len = options.len();
+    options.into_iter().cycle().take(len * count).collect()
+}
+
 impl ReedlineOption {
     pub fn into_reedline_event(self) -> Option<ReedlineEvent> {
         match self {
@@ -22,7 +26,7 @@ impl ReedlineOption {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum ParseResult<T> {
     Valid(T),
     Incomplete,
@@ -39,27 +43,37 @@ impl<T> ParseResult<T> {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct ParsedViSequence {
     multiplier: Option<usize>,
+    register: Option<char>,
     command: Option<Command>,
     count: Option<usize>,
     motion: ParseResult<Motion>,
 }
 
 impl ParsedViSequence {
+    /// Sequence that ended before reaching the command
+    fn unfinished(multiplier: Option<usize>, motion: ParseResult<Motion>) -> Self {
+        ParsedViSequence {
+            multiplier,
+            register: None,
+            command: None,
+            count: None,
+            motion,
+        }
+    }
+
     pub fn is_valid(&self) -> bool {
         !self.motion.is_invalid()
     }
 
     pub fn is_complete(&self, mode: ViMode) -> bool {
-        assert!(mode == ViMode::Normal || mode == ViMode::Visual);
+        assert!(mode == ViMode::Normal || mode.is_visual());
         match (&self.command, &self.motion) {
             (None, ParseResult::Valid(_)) => true,
             (Some(Command::Incomplete), _) => false,
-            (Some(cmd), ParseResult::Incomplete)
-                if !cmd.requires_motion() || mode == ViMode::Visual =>
-            {
+            (Some(cmd), ParseResult::Incomplete) if !cmd.requires_motion() || mode.is_visual() => {
                 true
             }
             (Some(_), ParseResult::Valid(_)) => true,
@@ -79,11 +93,10 @@ impl ParsedViSequence {
         self.multiplier.unwrap_or(1) * self.count.unwrap_or(1)
     }
 
-    fn apply_multiplier(&self, raw_events: Option<Vec<ReedlineOption>>) -> ReedlineEvent {
+    fn to_event(raw_events: Option<Vec<ReedlineOption>>) -> ReedlineEvent {
         if let Some(raw_events) = raw_events {
-            let events = std::iter::repeat(raw_events)
-                .take(self.total_multiplier())
-                .flatten()
+            let events = raw_events
+                .into_iter()
                 .filter_map(ReedlineOption::into_reedline_event)
                 .collect::<Vec<ReedlineEvent>>();
 
@@ -98,7 +111,29 @@ impl ParsedViSequence {
         }
     }
 
-    pub fn changes_mode(&self) -> Option<ViMode> {
+    /// Select the register for every edit, as any of them may use it
+    fn with_register(
+        &self,
+        raw_events: Option<Vec<ReedlineOption>>,
+    ) -> Option<Vec<ReedlineOption>> {
+        match (self.register, raw_events) {
+            (Some(register), Some(raw_events)) => Some(
+                raw_events
+                    .into_iter()
+                    .flat_map(|option| match option {
+                        ReedlineOption::Edit(_) => vec![
+                            ReedlineOption::Edit(EditCommand::SelectRegister(register)),
+                            option,
+                        ],
+                        _ => vec![option],
+                    })
+                    .collect(),
+            ),
+            (_, raw_events) => raw_events,
+        }
+    }
+
+    pub fn changes_mode(&self, vi_state: &Vi) -> Option<ViMode> {
         match (&self.command, &self.motion) {
             (Some(Command::EnterViInsert), ParseResult::Incomplete)
             | (Some(Command::EnterViAppend), ParseResult::Incomplete)
@@ -107,43 +142,72 @@ impl ParsedViSequence {
             | (Some(Command::PrependToStart), ParseResult::Incomplete)
             | (Some(Command::RewriteCurrentLine), ParseResult::Incomplete)
             | (Some(Command::SubstituteCharWithInsert), ParseResult::Incomplete)
-            | (Some(Command::HistorySearch), ParseResult::Incomplete)
-            | (Some(Command::Change), ParseResult::Valid(_)) => Some(ViMode::Insert),
-            (Some(Command::ChangeInside(char)), ParseResult::Incomplete)
-                if is_valid_change_inside_left(char) || is_valid_change_inside_right(char) =>
-            {
+            | (Some(Command::Change), _) => Some(ViMode::Insert),
+            // The history search is typed in insert mode
+            (Some(Command::Search { .. }), ParseResult::Incomplete) if !vi_state.buffer_search => {
                 Some(ViMode::Insert)
             }
-            (Some(Command::Delete), ParseResult::Incomplete) => Some(ViMode::Normal),
+            (Some(Command::Search { .. } | Command::ExCommandLine), ParseResult::Incomplete) => {
+                Some(ViMode::CommandLine)
+            }
+            (Some(Command::EnterViReplace), ParseResult::Incomplete) => Some(ViMode::Replace),
+            // Operators on the visual selection end the visual mode
+            (
+                Some(
+                    Command::Delete
+                    | Command::Yank
+                    | Command::Indent
+                    | Command::Dedent
+                    | Command::JoinLines
+                    | Command::Switchcase
+                    | Command::ReplaceChar(_)
+                    | Command::Lowercase
+                    | Command::Uppercase
+                    | Command::Surround(_),
+                ),
+                ParseResult::Incomplete,
+            ) => Some(ViMode::Normal),
             _ => None,
         }
     }
 
     pub fn to_reedline_event(&self, vi_state: &mut Vi) -> ReedlineEvent {
-        match (&self.multiplier, &self.command, &self.count, &self.motion) {
-            (_, Some(command), None, ParseResult::Incomplete) => {
-                let events = self.apply_multiplier(Some(command.to_reedline(vi_state)));
-                match &events {
-                    ReedlineEvent::None => {}
-                    event => vi_state.previous = Some(event.clone()),
-                }
-                events
+        let count = self.total_multiplier();
+        let events = match (&self.command, &self.count, &self.motion) {
+            // A count given to `.` replaces the count of the repeated sequence
+            (Some(Command::RepeatLastAction), None, ParseResult::Incomplete) => {
+                return match vi_state.previous.clone() {
+                    Some(mut previous) => {
+                        if self.multiplier.is_some() {
+                            previous.multiplier = self.multiplier;
+                            previous.count = None;
+                        }
+                        previous.to_reedline_event(vi_state)
+                    }
+                    None => ReedlineEvent::None,
+                };
             }
-            // This case handles all combinations of commands and motions that could exist
-            (_, Some(command), _, ParseResult::Valid(motion)) => {
-                let events =
-                    self.apply_multiplier(command.to_reedline_with_motion(motion, vi_state));
-                match &events {
-                    ReedlineEvent::None => {}
-                    event => vi_state.previous = Some(event.clone()),
-                }
-                events
+            (Some(command), None, ParseResult::Incomplete) => {
+                Self::to_event(self.with_register(Some(command.to_reedline(vi_state, count))))
             }
-            (_, None, _, ParseResult::Valid(motion)) => {
-                self.apply_multiplier(Some(motion.to_reedline(vi_state)))
+            // This case handles all combinations of commands and motions that could exist
+            (Some(command), _, ParseResult::Valid(motion)) => Self::to_event(
+                self.with_register(command.to_reedline_with_motion(motion, vi_state, count)),
+            ),
+            (None, _, ParseResult::Valid(motion)) => {
+                return Self::to_event(Some(motion.to_reedline(vi_state, count)))
             }
-            _ => ReedlineEvent::None,
+            _ => return ReedlineEvent::None,
+        };
+        // `.` repeats the changes made by a macro, not the macro itself
+        let is_macro = matches!(
+            self.command,
+            Some(Command::RecordMacro(_) | Command::ReplayMacro(_))
+        );
+        if events != ReedlineEvent::None && !is_macro {
+            vi_state.previous = Some(self.clone());
         }
+        events
     }
 }
 
@@ -171,17 +235,58 @@ where
     }
 }
 
-pub fn parse<'iter, I>(input: &mut Peekable<I>) -> ParsedViSequence
+/// Parse the name of the register following `"`, lowercase letters or `+`
+fn parse_register<'iter, I>(input: &mut Peekable<I>) -> ParseResult<Option<char>>
+where
+    I: Iterator<Item = &'iter char>,
+{
+    match input.peek() {
+        Some('"') => {
+            let _ = input.next();
+            match input.next() {
+                Some(&c) if c.is_ascii_lowercase() || c == '+' => ParseResult::Valid(Some(c)),
+                Some(_) => ParseResult::Invalid,
+                None => ParseResult::Incomplete,
+            }
+        }
+        _ => ParseResult::Valid(None),
+    }
+}
+
+pub fn parse<'iter, I>(input: &mut Peekable<I>, mode: ViMode) -> ParsedViSequence
 where
     I: Iterator<Item = &'iter char>,
 {
     let multiplier = parse_number(input);
-    let command = parse_command(input);
+    let register = match parse_register(input) {
+        ParseResult::Valid(register) => register,
+        ParseResult::Incomplete => {
+            return ParsedViSequence::unfinished(multiplier, ParseResult::Incomplete)
+        }
+        ParseResult::Invalid => {
+            return ParsedViSequence::unfinished(multiplier, ParseResult::Invalid)
+        }
+    };
+    let multiplier = multiplier.or_else(|| parse_number(input));
+    // In visual mode `i` and `a` start a text object instead of entering insert mode
+    let command = match input.peek() {
+        Some('i' | 'a') if mode.is_visual() => None,
+        _ => parse_command(input, mode),
+    };
     let count = parse_number(input);
     let motion = parse_motion(input, command.as_ref().and_then(Command::whole_line_char));
+    // `ys` takes the character to surround with after the motion
+    let command = match (command, &motion) {
+        (Some(Command::Surround(None)), ParseResult::Valid(_)) => match input.next() {
+            Some(c) => Some(Command::Surround(Some(*c))),
+            None => Some(Command::Incomplete),
+        },
+        (command, _) => command,
+    };
 
     ParsedViSequence {
         multiplier,
+        register,
         command,
         count,
         motion,
@@ -191,11 +296,12 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{core_editor::Editor, TextObject, TextObjectKind, TextObjectScope, UndoBehavior};
     use pretty_assertions::assert_eq;
     use rstest::rstest;
 
     fn vi_parse(input: &[char]) -> ParsedViSequence {
-        parse(&mut input.iter().peekable())
+        parse(&mut input.iter().peekable(), ViMode::Normal)
     }
 
     #[test]
@@ -207,6 +313,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: None,
+                register: None,
                 command: Some(Command::Delete),
                 count: None,
                 motion: ParseResult::Incomplete,
@@ -226,6 +333,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: None,
+                register: None,
                 command: Some(Command::Delete),
                 count: None,
                 motion: ParseResult::Valid(Motion::NextWord),
@@ -245,6 +353,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: Some(2),
+                register: None,
                 command: Some(Command::Delete),
                 count: None,
                 motion: ParseResult::Incomplete,
@@ -266,6 +375,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: Some(2),
+                register: None,
                 command: Some(Command::Delete),
                 count: None,
                 motion: ParseResult::Valid(Motion::NextWord),
@@ -285,6 +395,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: Some(2),
+                register: None,
                 command: Some(Command::Delete),
                 count: Some(2),
                 motion: ParseResult::Valid(Motion::NextWord),
@@ -304,6 +415,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: Some(2),
+                register: None,
                 command: Some(Command::Delete),
                 count: Some(20),
                 motion: ParseResult::Valid(Motion::NextWord),
@@ -323,6 +435,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: Some(2),
+                register: None,
                 command: Some(Command::Delete),
                 count: None,
                 motion: ParseResult::Valid(Motion::Line),
@@ -342,6 +455,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: None,
+                register: None,
                 command: Some(Command::Delete),
                 count: None,
                 motion: ParseResult::Valid(Motion::RightBefore('d')),
@@ -361,6 +475,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: Some(2),
+                register: None,
                 command: Some(Command::Delete),
                 count: None,
                 motion: ParseResult::Invalid,
@@ -378,6 +493,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: None,
+                register: None,
                 command: Some(Command::Incomplete),
                 count: None,
                 motion: ParseResult::Incomplete,
@@ -398,6 +514,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: None,
+                register: None,
                 command: None,
                 count: None,
                 motion: ParseResult::Incomplete,
@@ -417,6 +534,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: None,
+                register: None,
                 command: Some(Command::ReplaceChar('k')),
                 count: None,
                 motion: ParseResult::Incomplete,
@@ -437,6 +555,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: Some(2),
+                register: None,
                 command: None,
                 count: None,
                 motion: ParseResult::Valid(Motion::RightUntil('f')),
@@ -447,6 +566,35 @@ mod tests {
         assert_eq!(output.is_complete(ViMode::Visual), true);
     }
 
+    #[rstest]
+    #[case(&['m'], true)]
+    #[case(&['`'], true)]
+    #[case(&['\'', 'A'], false)]
+    fn test_partial_mark(#[case] input: &[char], #[case] is_valid: bool) {
+        let output = vi_parse(input);
+
+        assert_eq!(output.is_valid(), is_valid);
+        assert_eq!(output.is_complete(ViMode::Normal), false);
+    }
+
+    #[rstest]
+    #[case(&['"'], true, false)]
+    #[case(&['"', 'a'], true, false)]
+    #[case(&['"', 'a', 'd'], true, false)]
+    #[case(&['"', 'a', 'd', 'd'], true, true)]
+    #[case(&['2', '"', 'a', 'p'], true, true)]
+    #[case(&['"', 'A'], false, false)]
+    fn test_register_prefix(
+        #[case] input: &[char],
+        #[case] is_valid: bool,
+        #[case] is_complete: bool,
+    ) {
+        let output = vi_parse(input);
+
+        assert_eq!(output.is_valid(), is_valid);
+        assert_eq!(output.is_complete(ViMode::Normal), is_complete);
+    }
+
     #[test]
     fn test_two_up() {
         let input = ['2', 'k'];
@@ -456,6 +604,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: Some(2),
+                register: None,
                 command: None,
                 count: None,
                 motion: ParseResult::Valid(Motion::Up),
@@ -510,6 +659,8 @@ mod tests {
         ReedlineEvent::Edit(vec![EditCommand::Undo]),
         ReedlineEvent::Edit(vec![EditCommand::Undo])
         ]))]
+    #[case(&['g', '-'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::UndoEarlier])]))]
+    #[case(&['g', '+'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::UndoLater])]))]
     #[case(&['d', 'd'], ReedlineEvent::Multiple(vec![
         ReedlineEvent::Edit(vec![EditCommand::CutCurrentLine])]))]
     #[case(&['d', 'w'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutWordRightToNext])]))]
@@ -526,9 +677,18 @@ mod tests {
     #[case(&['c', 'B'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutBigWordLeft]), ReedlineEvent::Repaint]))]
     #[case(&['d', 'h'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::Backspace])]))]
     #[case(&['d', 'l'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::Delete])]))]
-    #[case(&['2', 'd', 'd'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutCurrentLine]), ReedlineEvent::Edit(vec![EditCommand::CutCurrentLine])]))]
-    // #[case(&['d', 'j'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutCurrentLine]), ReedlineEvent::Edit(vec![EditCommand::CutCurrentLine])]))]
-    // #[case(&['d', 'k'], ReedlineEvent::Multiple(vec![ReedlineEvent::Up, ReedlineEvent::Edit(vec![EditCommand::CutCurrentLine]), ReedlineEvent::Edit(vec![EditCommand::CutCurrentLine])]))]
+    #[case(&['2', 'd', 'd'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::ToggleLineSelection]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveLineDown { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::CutSelection])]))]
+    #[case(&['d', 'j'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::ToggleLineSelection]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveLineDown { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::CutSelection])]))]
+    #[case(&['d', 'k'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::ToggleLineSelection]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveLineUp { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::CutSelection])]))]
     #[case(&['d', 'E'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutBigWordRight])]))]
     #[case(&['d', '0'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutFromLineStart])]))]
     #[case(&['d', '^'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutFromLineStart])]))]
@@ -545,6 +705,75 @@ mod tests {
     #[case(&['c', 't', 'a'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutRightBefore('a')]), ReedlineEvent::Repaint]))]
     #[case(&['c', 'F', 'a'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutLeftUntil('a')]), ReedlineEvent::Repaint]))]
     #[case(&['c', 'T', 'a'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutLeftBefore('a')]), ReedlineEvent::Repaint]))]
+    #[case(&['%'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToMatchingBracket{select: false}])]))]
+    #[case(&['d', '%'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutToMatchingBracket])]))]
+    #[case(&['c', '%'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutToMatchingBracket]), ReedlineEvent::Repaint]))]
+    #[case(&['d', 'i', 'w'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutTextObject(
+        TextObject { scope: TextObjectScope::Inner, kind: TextObjectKind::Word })])]))]
+    #[case(&['d', 'a', 'p'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutTextObject(
+        TextObject { scope: TextObjectScope::Around, kind: TextObjectKind::Paragraph })])]))]
+    #[case(&['c', 'a', '"'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutTextObject(
+        TextObject { scope: TextObjectScope::Around, kind: TextObjectKind::Quote('"') })]), ReedlineEvent::Repaint]))]
+    #[case(&['c', 'i', ')'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutTextObject(
+        TextObject { scope: TextObjectScope::Inner, kind: TextObjectKind::Bracket('(') })]), ReedlineEvent::Repaint]))]
+    #[case(&['c', 'i', 'a'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutTextObject(
+        TextObject { scope: TextObjectScope::Inner, kind: TextObjectKind::Argument })]), ReedlineEvent::Repaint]))]
+    #[case(&['y', 'i', 'W'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyTextObject(
+        TextObject { scope: TextObjectScope::Inner, kind: TextObjectKind::BigWord })])]))]
+    #[case(&['y', 'y'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyCurrentLine])]))]
+    #[case(&['Y'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyCurrentLine])]))]
+    #[case(&['y', 'w'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveWordRightStart { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::CopySelectionAndCollapse])]))]
+    #[case(&['y', 'e'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveWordRightEnd { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveRight { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::CopySelectionAndCollapse])]))]
+    #[case(&['y', 'h'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveLeft { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::CopySelectionAndCollapse])]))]
+    #[case(&['y', 'k'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::ToggleLineSelection]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveLineUp { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::CopySelectionAndCollapse])]))]
+    #[case(&['"', 'a', 'y', 'y'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::SelectRegister('a')]),
+        ReedlineEvent::Edit(vec![EditCommand::CopyCurrentLine])]))]
+    #[case(&['"', '+', 'p'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::SelectRegister('+')]),
+        ReedlineEvent::Edit(vec![EditCommand::PasteCutBufferAfter])]))]
+    #[case(&['>', '>'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::ToggleLineSelection]),
+        ReedlineEvent::Edit(vec![EditCommand::Indent])]))]
+    #[case(&['<', 'j'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::ToggleLineSelection]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveLineDown { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::Dedent])]))]
+    #[case(&['>', 'i', 'p'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::SelectTextObject(
+            TextObject { scope: TextObjectScope::Inner, kind: TextObjectKind::Paragraph })]),
+        ReedlineEvent::Edit(vec![EditCommand::Indent])]))]
+    #[case(&['J'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::JoinLines])]))]
+    #[case(&['3', 'J'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::JoinLines]),
+        ReedlineEvent::Edit(vec![EditCommand::JoinLines])]))]
+    #[case(&['o'], ReedlineEvent::None)]
+    #[case(&['m', 'a'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::SetMark('a')])]))]
+    #[case(&['`', 'a'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+        EditCommand::MoveToMark { name: 'a', line: false, select: false }])]))]
+    #[case(&['3', '\'', 'a'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+        EditCommand::MoveToMark { name: 'a', line: true, select: false }])]))]
+    #[case(&['d', '`', 'a'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveToMark { name: 'a', line: false, select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::CutSelection])]))]
+    #[case(&['y', '\'', 'a'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::ToggleLineSelection]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveToMark { name: 'a', line: true, select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::CopySelectionAndCollapse])]))]
+    #[case(&['"', 'b', 'c', 'w'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::SelectRegister('b')]),
+        ReedlineEvent::Edit(vec![EditCommand::CutWordRight]),
+        ReedlineEvent::Repaint]))]
     fn test_reedline_move(#[case] input: &[char], #[case] expected: ReedlineEvent) {
         let mut vi = Vi::default();
         let res = vi_parse(input);
@@ -592,14 +821,14 @@ mod tests {
     #[rstest]
     #[case(&['2', 'k'], ReedlineEvent::Multiple(vec![ReedlineEvent::UntilFound(vec![
                 ReedlineEvent::MenuUp,
-                ReedlineEvent::Up,
+                ReedlineEvent::Edit(vec![EditCommand::MoveLineUp { select: true }]),
             ]), ReedlineEvent::UntilFound(vec![
                 ReedlineEvent::MenuUp,
-                ReedlineEvent::Up,
+                ReedlineEvent::Edit(vec![EditCommand::MoveLineUp { select: true }]),
             ])]))]
-    #[case(&['k'], ReedlineEvent::Multiple(vec![ReedlineEvent::UntilFound(vec![
-                ReedlineEvent::MenuUp,
-                ReedlineEvent::Up,
+    #[case(&['j'], ReedlineEvent::Multiple(vec![ReedlineEvent::UntilFound(vec![
+                ReedlineEvent::MenuDown,
+                ReedlineEvent::Edit(vec![EditCommand::MoveLineDown { select: true }]),
             ])]))]
     #[case(&['w'],
         ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveWordRightStart{select:true}])]))]
@@ -622,27 +851,188 @@ mod tests {
             ])]))]
     #[case(&['0'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToLineStart{select:true}])]))]
     #[case(&['$'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToLineEnd{select:true}])]))]
-    #[case(&['i'], ReedlineEvent::Multiple(vec![ReedlineEvent::Repaint]))]
+    #[case(&['%'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToMatchingBracket{select: true}])]))]
+    #[case(&['i', 'w'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::SelectTextObject(
+        TextObject { scope: TextObjectScope::Inner, kind: TextObjectKind::Word })])]))]
+    #[case(&['a', 'b'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::SelectTextObject(
+        TextObject { scope: TextObjectScope::Around, kind: TextObjectKind::Bracket('(') })])]))]
     #[case(&['p'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::PasteCutBufferAfter])]))]
     #[case(&['2', 'p'], ReedlineEvent::Multiple(vec![
         ReedlineEvent::Edit(vec![EditCommand::PasteCutBufferAfter]),
         ReedlineEvent::Edit(vec![EditCommand::PasteCutBufferAfter])
         ]))]
-    #[case(&['u'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::Undo])]))]
-    #[case(&['2', 'u'], ReedlineEvent::Multiple(vec![
-        ReedlineEvent::Edit(vec![EditCommand::Undo]),
-        ReedlineEvent::Edit(vec![EditCommand::Undo])
-        ]))]
+    #[case(&['u'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::LowercaseSelection])]))]
+    #[case(&['U'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::UppercaseSelection])]))]
+    #[case(&['~'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::SwitchcaseSelection])]))]
+    #[case(&['2', '~'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::SwitchcaseSelection])]))]
+    #[case(&['r', 'x'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::ReplaceSelectedChars('x')])]))]
+    #[case(&['o'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::SwapSelectionEnds])]))]
+    #[case(&['J'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::JoinLines])]))]
+    #[case(&['3', 'J'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::JoinLines])]))]
+    #[case(&['>'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::Indent])]))]
+    #[case(&['<'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::Dedent])]))]
+    #[case(&['c'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::CutSelection]),
+        ReedlineEvent::Repaint]))]
     #[case(&['d'], ReedlineEvent::Multiple(vec![
         ReedlineEvent::Edit(vec![EditCommand::CutSelection])]))]
+    #[case(&['y'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::CopySelectionAndCollapse])]))]
+    #[case(&['"', 'a', 'y'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::SelectRegister('a')]),
+        ReedlineEvent::Edit(vec![EditCommand::CopySelectionAndCollapse])]))]
     fn test_reedline_move_in_visual_mode(#[case] input: &[char], #[case] expected: ReedlineEvent) {
         let mut vi = Vi {
             mode: ViMode::Visual,
             ..Default::default()
         };
-        let res = vi_parse(input);
+        let res = parse(&mut input.iter().peekable(), ViMode::Visual);
         let output = res.to_reedline_event(&mut vi);
 
         assert_eq!(output, expected);
     }
+
+    fn apply_event(editor: &mut Editor, event: ReedlineEvent) {
+        match event {
+            ReedlineEvent::Multiple(events) => {
+                for event in events {
+                    apply_event(editor, event);
+                }
+            }
+            ReedlineEvent::UntilFound(events) => {
+                if let Some(event) = events
+                    .into_iter()
+                    .find(|event| matches!(event, ReedlineEvent::Edit(_)))
+                {
+                    apply_event(editor, event);
+                }
+            }
+            ReedlineEvent::Edit(commands) => {
+                for command in commands {
+                    editor.run_edit_command(&command);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn run_keys(editor: &mut Editor, vi: &mut Vi, keys: &str) {
+        let input: Vec<char> = keys.chars().collect();
+        let event = vi_parse(&input).to_reedline_event(vi);
+        apply_event(editor, event);
+    }
+
+    #[rstest]
+    #[case("abcdef", 0, &["3x"], "def", 0)]
+    #[case("abcdef", 0, &["2x", "3."], "f", 0)]
+    #[case("abcdef", 0, &["2x", "."], "ef", 0)]
+    #[case("abcdef", 0, &["5~"], "ABCDEf", 5)]
+    #[case("abcdef", 0, &["3rx"], "xxxdef", 3)]
+    #[case("axbxcxd", 0, &["3fx", "x"], "axbxcd", 5)]
+    #[case("axbxcxd", 0, &["3tx", "x"], "axbxxd", 4)]
+    #[case("one two three four", 0, &["d3w"], "four", 0)]
+    #[case("one two three four five", 0, &["2d2w"], "five", 0)]
+    #[case("one two three", 0, &["c2w"], " three", 0)]
+    #[case("one two three", 0, &["y2w", "P"], "one two one two three", 8)]
+    #[case("ab", 0, &["x", "3p"], "baaa", 4)]
+    #[case("a\nb\nc\nd", 0, &["3dd"], "d", 0)]
+    #[case("a\nb\nc\nd", 2, &["dj"], "a\nd", 2)]
+    #[case("a\nb\nc\nd", 4, &["dk"], "a\nd", 2)]
+    #[case("a\nb\nc", 0, &["2yy", "P"], "a\nb\na\nb\nc", 4)]
+    #[case("ab\ncd\nef", 1, &["2D"], "a\nef", 1)]
+    fn test_counted_sequences_on_editor(
+        #[case] buffer: &str,
+        #[case] cursor: usize,
+        #[case] sequences: &[&str],
+        #[case] expected_buffer: &str,
+        #[case] expected_cursor: usize,
+    ) {
+        let mut editor = Editor::default();
+        editor.set_buffer(buffer.to_string(), UndoBehavior::CreateUndoPoint);
+        editor.run_edit_command(&EditCommand::MoveToPosition {
+            position: cursor,
+            select: false,
+        });
+        let mut vi = Vi::default();
+        for keys in sequences {
+            run_keys(&mut editor, &mut vi, keys);
+        }
+
+        assert_eq!(editor.get_buffer(), expected_buffer);
+        assert_eq!(editor.insertion_point(), expected_cursor);
+    }
+
+    #[rstest]
+    #[case("say \"hi\"", 6, &["ds\""], "say hi", 4)]
+    #[case("say \"hi\"", 6, &["cs\"'"], "say 'hi'", 4)]
+    #[case("f(a)", 2, &["cs)]"], "f[a]", 1)]
+    #[case("f(a)", 2, &["csb{"], "f{a}", 1)]
+    #[case("say \"hi\"", 6, &["cs\"b"], "say (hi)", 4)]
+    #[case("say hi", 4, &["ysiw)"], "say (hi)", 4)]
+    #[case("say hi", 4, &["ysiw("], "say (hi)", 4)]
+    #[case("say hi", 4, &["ysiwb"], "say (hi)", 4)]
+    #[case("say hi", 4, &["ysiwB"], "say {hi}", 4)]
+    #[case("say hi", 0, &["ys2w\""], "\"say hi\"", 0)]
+    #[case("say hi", 4, &["yss]"], "[say hi]", 0)]
+    #[case("a b", 2, &["yss)", "."], "((a b))", 0)]
+    fn test_surround_sequences_on_editor(
+        #[case] buffer: &str,
+        #[case] cursor: usize,
+        #[case] sequences: &[&str],
+        #[case] expected_buffer: &str,
+        #[case] expected_cursor: usize,
+    ) {
+        let mut editor = Editor::default();
+        editor.set_buffer(buffer.to_string(), UndoBehavior::CreateUndoPoint);
+        editor.run_edit_command(&EditCommand::MoveToPosition {
+            position: cursor,
+            select: false,
+        });
+        let mut vi = Vi::default();
+        for keys in sequences {
+            run_keys(&mut editor, &mut vi, keys);
+        }
+
+        assert_eq!(editor.get_buffer(), expected_buffer);
+        assert_eq!(editor.insertion_point(), expected_cursor);
+    }
+
+    #[rstest]
+    #[case(&['d', 's'], true)]
+    #[case(&['c', 's', '"'], true)]
+    #[case(&['y', 's', 'i', 'w'], true)]
+    #[case(&['y', 's', 'i', 'w', ')'], false)]
+    #[case(&['d', 's', ')'], false)]
+    fn test_surround_waits_for_characters(#[case] input: &[char], #[case] waiting: bool) {
+        let output = vi_parse(input);
+        assert_eq!(!output.is_complete(ViMode::Normal), waiting);
+    }
+
+    #[rstest]
+    #[case(&['d', 's', 'x'])]
+    #[case(&['c', 's', 'w', ')'])]
+    fn test_surround_rejects_other_text_objects(#[case] input: &[char]) {
+        assert_eq!(vi_parse(input).command, None);
+    }
+
+    #[test]
+    fn test_surround_selection_in_visual_mode() {
+        let input = ['S', '('];
+        let mut vi = Vi {
+            mode: ViMode::Visual,
+            ..Default::default()
+        };
+        let output = parse(&mut input.iter().peekable(), ViMode::Visual);
+
+        assert_eq!(output.changes_mode(&vi), Some(ViMode::Normal));
+        assert_eq!(
+            output.to_reedline_event(&mut vi),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+                EditCommand::SurroundSelection {
+                    left: '(',
+                    right: ')'
+                }
+            ])])
+        );
+    }
 }
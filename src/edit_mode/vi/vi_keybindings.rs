@@ -12,6 +12,11 @@ use crate::{
 };
 
 /// Default Vi normal keybindings
+///
+/// `Ctrl-o` and `Ctrl-i` navigate the jump list like in Vim, so unlike in insert mode
+/// `Ctrl-o` does not open the external editor. Most terminals send `Ctrl-i` as `Tab`
+/// unless they report enhanced keyboard events, bind `Tab` to
+/// [`EditCommand::MoveToNewerJump`] for those.
 pub fn default_vi_normal_keybindings() -> Keybindings {
     let mut kb = Keybindings::new();
     use EditCommand as EC;
@@ -28,6 +33,9 @@ pub fn default_vi_normal_keybindings() -> Keybindings {
         edit_bind(EC::MoveLeft { select: false }),
     );
     kb.add_binding(KM::NONE, KC::Delete, edit_bind(EC::Delete));
+    // Jump list navigation, replacing Ctrl-o opening the editor
+    kb.add_binding(KM::CONTROL, KC::Char('o'), edit_bind(EC::MoveToOlderJump));
+    kb.add_binding(KM::CONTROL, KC::Char('i'), edit_bind(EC::MoveToNewerJump));
 
     kb
 }
@@ -43,3 +51,26 @@ pub fn default_vi_insert_keybindings() -> Keybindings {
 
     kb
 }
+
+/// Default Vi replace keybindings
+pub fn default_vi_replace_keybindings() -> Keybindings {
+    replace_keybindings_from(default_vi_insert_keybindings())
+}
+
+/// The insert keybindings with Backspace restoring the overwritten text
+pub(crate) fn replace_keybindings_from(insert_keybindings: Keybindings) -> Keybindings {
+    let mut kb = insert_keybindings;
+    use EditCommand as EC;
+    use KeyCode as KC;
+    use KeyModifiers as KM;
+
+    // Backspace restores the text overwritten since entering the replace mode
+    kb.add_binding(KM::NONE, KC::Backspace, edit_bind(EC::BackspaceOverwrite));
+    kb.add_binding(
+        KM::CONTROL,
+        KC::Char('h'),
+        edit_bind(EC::BackspaceOverwrite),
+    );
+
+    kb
+}
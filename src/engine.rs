@@ -18,9 +18,10 @@ use {
 use {
     crate::{
         completion::{Completer, DefaultCompleter},
-        core_editor::Editor,
+        core_editor::{AutoPair, ClipboardMode, Editor, WordDefinition},
         edit_mode::{EditMode, Emacs},
         enums::{EventStatus, ReedlineEvent},
+        ex_command::{builtin_ex_command, split_ex_command, ExCommand},
         highlighter::SimpleMatchHighlighter,
         hinter::Hinter,
         history::{
@@ -32,8 +33,9 @@ use {
         result::{ReedlineError, ReedlineErrorVariants},
         terminal_extensions::{bracketed_paste::BracketedPasteGuard, kitty::KittyProtocolGuard},
         utils::text_manipulation,
-        EditCommand, ExampleHighlighter, Highlighter, LineBuffer, Menu, MenuEvent, Prompt,
-        PromptHistorySearch, ReedlineMenu, Signal, UndoBehavior, ValidationResult, Validator,
+        EditCommand, ExampleHighlighter, Highlighter, Indenter, LineBuffer, Menu, MenuEvent,
+        Prompt, PromptHistorySearch, ReedlineMenu, Signal, StyledText, UndoBehavior, UndoStep,
+        UndoTree, ValidationResult, Validator,
     },
     crossterm::{
         cursor::{SetCursorStyle, Show},
@@ -42,8 +44,10 @@ use {
         terminal, QueueableCommand,
     },
     std::{
-        fs::File, io, io::Result, io::Write, process::Command, time::Duration, time::SystemTime,
+        collections::HashMap, fs::File, io, io::Result, io::Write, process::Command,
+        time::Duration, time::SystemTime,
     },
+    unicode_segmentation::UnicodeSegmentation,
 };
 
 // The POLL_WAIT is used to specify for how long the POLL should wait for
@@ -56,6 +60,9 @@ const POLL_WAIT: Duration = Duration::from_millis(100);
 // time, we specify how many events should be in the `crossterm_events` vector
 // before it is considered a paste. 10 events is conservative enough.
 const EVENTS_THRESHOLD: usize = 10;
+// How many lines apart highlighted matching brackets may be. More than fit on a
+// screen, while the search on every repaint stays cheap in large buffers.
+const MATCHING_BRACKET_LINES: usize = 100;
 
 /// Maximum time Reedline will block on input before yielding control to
 /// external printers.
@@ -78,6 +85,15 @@ enum InputMode {
     /// Either bash style up/down history or fish style prefix search,
     /// Edits directly switch to [`InputMode::Regular`]
     HistoryTraversal,
+    /// Typing the pattern of a search in the buffer into the command line painted under the
+    /// buffer, confirming it moves to the next match
+    BufferSearch {
+        /// Search towards the start of the buffer
+        backward: bool,
+    },
+    /// Typing into the Vi `:` command line painted under the buffer, confirming it runs the
+    /// [`ExCommand`]
+    ExCommand,
 }
 
 /// Line editor engine
@@ -113,6 +129,13 @@ pub struct Reedline {
     history_cursor_on_excluded: bool,
     input_mode: InputMode,
 
+    // Name of the macro being recorded and the raw events recorded so far
+    macro_recording: Option<(char, Vec<ReedlineRawEvent>)>,
+    // Number of recorded events belonging to the key sequence the edit mode is still parsing
+    macro_pending_events: usize,
+    // Names of the macros being replayed, to stop macros from replaying themselves
+    replaying_macros: Vec<char>,
+
     // State of the painter after a `ReedlineEvent::ExecuteHostCommand` was requested, used after
     // execution to decide if we can re-use the previous prompt or paint a new one.
     suspended_state: Option<PainterSuspendedState>,
@@ -139,6 +162,25 @@ pub struct Reedline {
     // Style used for visual selection
     visual_selection_style: Style,
 
+    // Style used for the cursors besides the terminal cursor
+    secondary_cursor_style: Style,
+
+    // Style used for the bracket pair at the cursor, no highlighting if `None`
+    matching_bracket_style: Option<Style>,
+
+    // Style used for the matches of a buffer search
+    search_match_style: Style,
+
+    // Text typed so far into the command line of `InputMode::BufferSearch` and
+    // `InputMode::ExCommand`
+    command_line: String,
+
+    // Commands of the Vi `:` command line added by the host
+    ex_commands: HashMap<String, ExCommand>,
+
+    // Error of the last `:` command, shown in place of the command line until the next event
+    ex_command_error: Option<String>,
+
     // Showcase hints based on various strategies (history, language-completion, spellcheck, etc)
     hinter: Option<Box<dyn Hinter>>,
     hide_hints: bool,
@@ -200,14 +242,18 @@ impl Reedline {
         let painter = Painter::new(std::io::BufWriter::new(std::io::stderr()));
         let buffer_highlighter = Box::<ExampleHighlighter>::default();
         let visual_selection_style = Style::new().on(Color::LightGray);
+        let secondary_cursor_style = Style::new().reverse();
+        let search_match_style = Style::new().on(Color::Yellow);
         let completer = Box::<DefaultCompleter>::default();
         let hinter = None;
         let validator = None;
         let edit_mode = Box::<Emacs>::default();
         let hist_session_id = None;
+        let mut editor = Editor::default();
+        editor.set_join_kills(edit_mode.joins_kills());
 
         Reedline {
-            editor: Editor::default(),
+            editor,
             history,
             history_cursor: HistoryCursor::new(
                 HistoryNavigationQuery::Normal(LineBuffer::default()),
@@ -219,6 +265,9 @@ impl Reedline {
             history_excluded_item: None,
             history_cursor_on_excluded: false,
             input_mode: InputMode::Regular,
+            macro_recording: None,
+            macro_pending_events: 0,
+            replaying_macros: Vec::new(),
             suspended_state: None,
             painter,
             transient_prompt: None,
@@ -228,6 +277,12 @@ impl Reedline {
             partial_completions: false,
             highlighter: buffer_highlighter,
             visual_selection_style,
+            secondary_cursor_style,
+            matching_bracket_style: None,
+            search_match_style,
+            command_line: String::new(),
+            ex_command_error: None,
+            ex_commands: HashMap::new(),
             hinter,
             hide_hints: false,
             validator,
@@ -405,6 +460,73 @@ impl Reedline {
         self
     }
 
+    /// A builder that configures the style used to draw the additional cursors of
+    /// multi-cursor editing
+    #[must_use]
+    pub fn with_secondary_cursor_style(mut self, style: Style) -> Self {
+        self.secondary_cursor_style = style;
+        self
+    }
+
+    /// A builder that highlights the bracket at the cursor and its matching bracket
+    ///
+    /// The bracket right of the cursor takes precedence over the one left of it. Brackets
+    /// match across lines, but only up to a hundred lines apart, keeping the cost of
+    /// repainting independent of the size of the buffer.
+    #[must_use]
+    pub fn with_matching_bracket_style(mut self, style: Style) -> Self {
+        self.matching_bracket_style = Some(style);
+        self
+    }
+
+    /// A builder that configures the style used for the matches of a buffer search
+    ///
+    /// The matches are highlighted while typing the pattern and after moving to one of them.
+    #[must_use]
+    pub fn with_search_match_style(mut self, style: Style) -> Self {
+        self.search_match_style = style;
+        self
+    }
+
+    /// A builder that adds a command to the Vi `:` command line
+    ///
+    /// Typing `:name arguments` runs the command. Commands added by the host take precedence
+    /// over the built-in `:s`, `:w` and `:e`.
+    #[must_use]
+    pub fn with_ex_command(mut self, name: &str, command: ExCommand) -> Self {
+        self.ex_commands.insert(name.to_string(), command);
+        self
+    }
+
+    /// A builder that configures how many cut entries are kept in the kill ring
+    ///
+    /// Older entries can be recalled with [`EditCommand::YankPop`] directly after pasting
+    /// the cut buffer. A size of `1` only keeps the most recent cut.
+    #[must_use]
+    pub fn with_kill_ring_size(mut self, size: usize) -> Self {
+        self.editor.set_kill_ring_size(size);
+        self
+    }
+
+    /// A builder that enables inserting the matching closer when typing brackets and quotes
+    ///
+    /// See [`AutoPair`] for the rules. Bracketed pastes are never paired.
+    #[must_use]
+    pub fn with_auto_pair(mut self, auto_pair: AutoPair) -> Self {
+        self.editor.set_auto_pair(Some(auto_pair));
+        self
+    }
+
+    /// A builder that configures which characters separate words
+    ///
+    /// Used by all word based motions and cuts, e.g. [`WordDefinition::Separators`] with
+    /// `"/-.="` to edit paths component by component.
+    #[must_use]
+    pub fn with_word_definition(mut self, word_definition: WordDefinition) -> Self {
+        self.editor.set_word_definition(word_definition);
+        self
+    }
+
     /// A builder which configures the history for your instance of the Reedline engine
     /// # Example
     /// ```rust,no_run
@@ -462,6 +584,23 @@ impl Reedline {
         self
     }
 
+    /// A builder that configures the indenter for new lines of multiline input
+    /// # Example
+    /// ```rust
+    /// // Create a reedline object that indents the lines inside of brackets
+    ///
+    /// use reedline::{DefaultIndenter, DefaultValidator, Reedline};
+    ///
+    /// let mut line_editor = Reedline::create()
+    ///     .with_validator(Box::new(DefaultValidator))
+    ///     .with_indenter(Box::new(DefaultIndenter));
+    /// ```
+    #[must_use]
+    pub fn with_indenter(mut self, indenter: Box<dyn Indenter>) -> Self {
+        self.editor.set_indenter(Some(indenter));
+        self
+    }
+
     /// A builder that configures the alternate text editor used to edit the line buffer
     ///
     /// You are responsible for providing a file path that is unique to this reedline session
@@ -502,6 +641,13 @@ impl Reedline {
         self
     }
 
+    /// Remove the current [`Indenter`]
+    #[must_use]
+    pub fn disable_indenter(mut self) -> Self {
+        self.editor.set_indenter(None);
+        self
+    }
+
     /// Set a different prompt to be used after submitting each line
     #[must_use]
     pub fn with_transient_prompt(mut self, transient_prompt: Box<dyn Prompt>) -> Self {
@@ -511,7 +657,9 @@ impl Reedline {
 
     /// A builder which configures the edit mode for your instance of the Reedline engine
     #[must_use]
-    pub fn with_edit_mode(mut self, edit_mode: Box<dyn EditMode>) -> Self {
+    pub fn with_edit_mode(mut self, mut edit_mode: Box<dyn EditMode>) -> Self {
+        self.editor.set_join_kills(edit_mode.joins_kills());
+        edit_mode.set_macro_recording(self.macro_recording.is_some());
         self.edit_mode = edit_mode;
         self
     }
@@ -665,6 +813,51 @@ impl Reedline {
         self.editor.get_buffer()
     }
 
+    /// Returns the undo history recorded for the current input buffer.
+    ///
+    /// Every branch created by editing after an undo is kept and can be inspected here.
+    pub fn undo_tree(&self) -> &UndoTree<UndoStep> {
+        self.editor.undo_tree()
+    }
+
+    /// Returns the content of the named register, e.g. `'a'` for the Vi register `"a`.
+    ///
+    /// Registers holding whole lines end with a line break. With the `system_clipboard`
+    /// feature the register `'+'` reads the system clipboard.
+    pub fn register(&mut self, name: char) -> Option<String> {
+        self.editor.register(name).map(|(mut content, mode)| {
+            if matches!(mode, ClipboardMode::Lines) && !content.ends_with('\n') {
+                content.push('\n');
+            }
+            content
+        })
+    }
+
+    /// Replaces the content of the named register.
+    ///
+    /// Content ending with a line break is pasted as whole lines.
+    pub fn set_register(&mut self, name: char, content: &str) {
+        let mode = if content.ends_with('\n') {
+            ClipboardMode::Lines
+        } else {
+            ClipboardMode::Normal
+        };
+        self.editor.set_register(name, content, mode);
+    }
+
+    /// Returns the raw events recorded as the named macro, e.g. with the Vi `qa`.
+    ///
+    /// The events can be serialized to keep the macros between sessions, see
+    /// [`Reedline::set_macro`].
+    pub fn macro_events(&self, name: char) -> Option<&[ReedlineRawEvent]> {
+        self.editor.macro_events(name)
+    }
+
+    /// Replaces the raw events of the named macro, replayed e.g. with the Vi `@a`.
+    pub fn set_macro(&mut self, name: char, events: Vec<ReedlineRawEvent>) {
+        self.editor.set_macro(name, events);
+    }
+
     /// Writes `msg` to the terminal with a following carriage return and newline
     fn print_line(&mut self, msg: &str) -> Result<()> {
         self.painter.paint_line(msg)
@@ -734,15 +927,28 @@ impl Reedline {
 
             let mut events: Vec<Event> = vec![];
 
+            // A key sequence that is bound but may still be continued takes effect
+            // once no further key arrives in time
+            let mut expired_sequence = None;
+            if let Some(timeout) = self.edit_mode.pending_sequence_timeout() {
+                if !event::poll(timeout)? {
+                    let event = self.edit_mode.expire_pending_sequence();
+                    self.finish_macro_key_sequence(&event);
+                    expired_sequence = Some(event);
+                }
+            }
+
             // If the `external_printer` feature is enabled, we need to
             // periodically yield so that external printers get a chance to
             // print. Otherwise, we can just block until we receive an event.
             #[cfg(feature = "external_printer")]
-            if event::poll(EXTERNAL_PRINTER_WAIT)? {
+            if expired_sequence.is_none() && event::poll(EXTERNAL_PRINTER_WAIT)? {
                 events.push(crossterm::event::read()?);
             }
             #[cfg(not(feature = "external_printer"))]
-            events.push(crossterm::event::read()?);
+            if expired_sequence.is_none() {
+                events.push(crossterm::event::read()?);
+            }
 
             // Receive all events in the queue without blocking. Will stop when
             // a line of input is completed.
@@ -763,12 +969,12 @@ impl Reedline {
             // Convert `Event` into `ReedlineEvent`. Also, fuse consecutive
             // `ReedlineEvent::EditCommand` into one. Also, if there're multiple
             // `ReedlineEvent::Resize`, only keep the last one.
-            let mut reedline_events: Vec<ReedlineEvent> = vec![];
+            let mut reedline_events: Vec<ReedlineEvent> = expired_sequence.into_iter().collect();
             let mut edits = vec![];
             let mut resize = None;
             for event in events {
                 if let Ok(event) = ReedlineRawEvent::try_from(event) {
-                    match self.edit_mode.parse_event(event) {
+                    match self.parse_raw_event(event) {
                         ReedlineEvent::Edit(edit) => edits.extend(edit),
                         ReedlineEvent::Resize(x, y) => resize = Some((x, y)),
                         event => {
@@ -813,10 +1019,111 @@ impl Reedline {
     }
 
     fn handle_event(&mut self, prompt: &dyn Prompt, event: ReedlineEvent) -> Result<EventStatus> {
-        if self.input_mode == InputMode::HistorySearch {
-            self.handle_history_search_event(event)
-        } else {
-            self.handle_editor_event(prompt, event)
+        self.ex_command_error = None;
+        match self.input_mode {
+            InputMode::HistorySearch => self.handle_history_search_event(event),
+            InputMode::BufferSearch { .. } | InputMode::ExCommand => {
+                self.handle_command_line_event(prompt, event)
+            }
+            _ => self.handle_editor_event(prompt, event),
+        }
+    }
+
+    fn handle_command_line_event(
+        &mut self,
+        prompt: &dyn Prompt,
+        event: ReedlineEvent,
+    ) -> Result<EventStatus> {
+        match event {
+            ReedlineEvent::UntilFound(events) => {
+                for event in events {
+                    match self.handle_command_line_event(prompt, event)? {
+                        EventStatus::Inapplicable => {
+                            // Try again with the next event handler
+                        }
+                        success => {
+                            return Ok(success);
+                        }
+                    }
+                }
+                Ok(EventStatus::Inapplicable)
+            }
+            ReedlineEvent::Multiple(events) => {
+                let mut latest_signal = EventStatus::Inapplicable;
+                for event in events {
+                    match self.handle_command_line_event(prompt, event)? {
+                        EventStatus::Handled => {
+                            latest_signal = EventStatus::Handled;
+                        }
+                        EventStatus::Inapplicable => {
+                            // NO OP
+                        }
+                        EventStatus::Exits(signal) => {
+                            // TODO: Check if we want to allow execution to
+                            // proceed if there are more events after the
+                            // terminating
+                            return Ok(EventStatus::Exits(signal));
+                        }
+                    }
+                }
+                Ok(latest_signal)
+            }
+            ReedlineEvent::Edit(commands) => {
+                for command in commands {
+                    match command {
+                        EditCommand::InsertChar(c) => self.command_line.push(c),
+                        EditCommand::Backspace | EditCommand::BackspaceOverwrite => {
+                            self.command_line.pop();
+                        }
+                        _ => {}
+                    }
+                }
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::Enter | ReedlineEvent::Submit | ReedlineEvent::SubmitOrNewline => {
+                let input_mode = std::mem::replace(&mut self.input_mode, InputMode::Regular);
+                let line = std::mem::take(&mut self.command_line);
+                match input_mode {
+                    InputMode::BufferSearch { backward } => {
+                        let select = self.editor.get_selection().is_some();
+                        self.run_edit_commands(&[EditCommand::SearchBuffer {
+                            pattern: line,
+                            backward,
+                            select,
+                        }]);
+                        Ok(EventStatus::Handled)
+                    }
+                    _ => self.run_ex_command(prompt, &line),
+                }
+            }
+            ReedlineEvent::Esc | ReedlineEvent::CtrlC => {
+                self.input_mode = InputMode::Regular;
+                self.command_line.clear();
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::Resize(width, height) => {
+                self.painter.handle_resize(width, height);
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::Repaint => Ok(EventStatus::Handled),
+            _ => Ok(EventStatus::Inapplicable),
+        }
+    }
+
+    /// Runs the command typed into the Vi `:` command line, commands added by the host take
+    /// precedence over the built-in ones
+    fn run_ex_command(&mut self, prompt: &dyn Prompt, line: &str) -> Result<EventStatus> {
+        let (name, args) = split_ex_command(line);
+        let event = match self.ex_commands.get_mut(name) {
+            Some(command) => Some(command.run(args)),
+            None => builtin_ex_command(name, args),
+        };
+        match event {
+            Some(event) => self.handle_editor_event(prompt, event),
+            None => {
+                self.ex_command_error = Some(format!("Not an editor command: {}", line.trim()));
+                Ok(EventStatus::Handled)
+            }
         }
     }
 
@@ -916,6 +1223,12 @@ impl Reedline {
             | ReedlineEvent::None
             | ReedlineEvent::HistoryHintWordComplete
             | ReedlineEvent::OpenEditor
+            | ReedlineEvent::SearchBuffer { .. }
+            | ReedlineEvent::ExCommandLine
+            | ReedlineEvent::RecordMacro(_)
+            | ReedlineEvent::StopMacroRecording
+            | ReedlineEvent::ReplayMacro(_)
+            | ReedlineEvent::UniversalArgument
             | ReedlineEvent::Menu(_)
             | ReedlineEvent::MenuNext
             | ReedlineEvent::MenuPrevious
@@ -936,6 +1249,9 @@ impl Reedline {
         match event {
             ReedlineEvent::Menu(name) => {
                 if self.active_menu().is_none() {
+                    if self.menus.iter().any(|menu| menu.name() == name) {
+                        self.end_history_traversal();
+                    }
                     if let Some(menu) = self.menus.iter_mut().find(|menu| menu.name() == name) {
                         menu.menu_event(MenuEvent::Activate(self.quick_completions));
 
@@ -1066,6 +1382,7 @@ impl Reedline {
             }
             ReedlineEvent::Esc => {
                 self.deactivate_menus();
+                self.editor.clear_secondary_cursors();
                 Ok(EventStatus::Handled)
             }
             ReedlineEvent::CtrlD => {
@@ -1096,6 +1413,7 @@ impl Reedline {
             ReedlineEvent::Enter | ReedlineEvent::Submit | ReedlineEvent::SubmitOrNewline
                 if self.menus.iter().any(|menu| menu.is_active()) =>
             {
+                self.end_history_traversal();
                 for menu in self.menus.iter_mut() {
                     if menu.is_active() {
                         menu.replace_in_buffer(&mut self.editor);
@@ -1153,6 +1471,21 @@ impl Reedline {
                 self.suspended_state = Some(self.painter.state_before_suspension());
                 Ok(EventStatus::Exits(Signal::Success(host_command)))
             }
+            ReedlineEvent::RecordMacro(name) => {
+                self.macro_recording = Some((name, Vec::new()));
+                self.macro_pending_events = 0;
+                self.edit_mode.set_macro_recording(true);
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::StopMacroRecording => {
+                // Typed keys stopping the recording already saved it without them
+                if let Some((name, recorded)) = self.macro_recording.take() {
+                    self.editor.set_macro(name, recorded);
+                }
+                self.edit_mode.set_macro_recording(false);
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::ReplayMacro(name) => self.replay_macro(prompt, name),
             ReedlineEvent::Edit(commands) => {
                 self.run_edit_commands(&commands);
                 if let Some(menu) = self.menus.iter_mut().find(|men| men.is_active()) {
@@ -1233,6 +1566,16 @@ impl Reedline {
                 self.enter_history_search();
                 Ok(EventStatus::Handled)
             }
+            ReedlineEvent::SearchBuffer { backward } => {
+                self.command_line.clear();
+                self.input_mode = InputMode::BufferSearch { backward };
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::ExCommandLine => {
+                self.command_line.clear();
+                self.input_mode = InputMode::ExCommand;
+                Ok(EventStatus::Handled)
+            }
             ReedlineEvent::Multiple(events) => {
                 let mut latest_signal = EventStatus::Inapplicable;
                 for event in events {
@@ -1268,6 +1611,8 @@ impl Reedline {
                 // Exhausting the event handlers is still considered handled
                 Ok(EventStatus::Inapplicable)
             }
+            // Taken up by the edit mode
+            ReedlineEvent::UniversalArgument => Ok(EventStatus::Inapplicable),
             ReedlineEvent::None | ReedlineEvent::Mouse => Ok(EventStatus::Inapplicable),
         }
     }
@@ -1367,6 +1712,61 @@ impl Reedline {
         }
     }
 
+    /// Parses a raw event typed by the user, recording it if a macro is being recorded
+    fn parse_raw_event(&mut self, event: ReedlineRawEvent) -> ReedlineEvent {
+        if let Some((_, recorded)) = &mut self.macro_recording {
+            recorded.push(event.clone());
+            self.macro_pending_events += 1;
+        }
+        let event = self.edit_mode.parse_event(event);
+        self.finish_macro_key_sequence(&event);
+        event
+    }
+
+    /// Keeps the recorded events of a completed key sequence, or saves the macro
+    /// without them if the sequence stops the recording
+    fn finish_macro_key_sequence(&mut self, event: &ReedlineEvent) {
+        match event {
+            ReedlineEvent::None => {}
+            ReedlineEvent::StopMacroRecording => {
+                // Keys typed after the stopping ones in the same batch are not recorded
+                if let Some((name, mut recorded)) = self.macro_recording.take() {
+                    recorded.truncate(recorded.len().saturating_sub(self.macro_pending_events));
+                    self.editor.set_macro(name, recorded);
+                }
+                self.macro_pending_events = 0;
+            }
+            _ => self.macro_pending_events = 0,
+        }
+    }
+
+    /// Handles the raw events of the named macro as if they were typed again
+    fn replay_macro(&mut self, prompt: &dyn Prompt, name: char) -> Result<EventStatus> {
+        if self.replaying_macros.contains(&name) {
+            return Ok(EventStatus::Inapplicable);
+        }
+        let events = match self.editor.macro_events(name) {
+            Some(events) => events.to_vec(),
+            None => return Ok(EventStatus::Inapplicable),
+        };
+
+        self.replaying_macros.push(name);
+        let mut latest_signal = Ok(EventStatus::Inapplicable);
+        for event in events {
+            let event = self.edit_mode.parse_event(event);
+            match self.handle_event(prompt, event) {
+                Ok(EventStatus::Inapplicable) => {}
+                Ok(EventStatus::Handled) => latest_signal = Ok(EventStatus::Handled),
+                exit => {
+                    latest_signal = exit;
+                    break;
+                }
+            }
+        }
+        self.replaying_macros.pop();
+        latest_signal
+    }
+
     /// Switch into reverse history search mode
     ///
     /// This mode uses a separate prompt and handles keybindings slightly differently!
@@ -1461,8 +1861,11 @@ impl Reedline {
         }
     }
 
-    /// Executes [`EditCommand`] actions by modifying the internal state appropriately. Does not output itself.
-    pub fn run_edit_commands(&mut self, commands: &[EditCommand]) {
+    /// Leave the history traversal, keeping the currently shown history entry in the buffer
+    ///
+    /// Has to be called before the buffer is changed by anything but the traversal itself,
+    /// otherwise the change would be overwritten by the history entry on the next edit.
+    fn end_history_traversal(&mut self) {
         if self.input_mode == InputMode::HistoryTraversal {
             if matches!(
                 self.history_cursor.get_navigation(),
@@ -1475,6 +1878,11 @@ impl Reedline {
             }
             self.input_mode = InputMode::Regular;
         }
+    }
+
+    /// Executes [`EditCommand`] actions by modifying the internal state appropriately. Does not output itself.
+    pub fn run_edit_commands(&mut self, commands: &[EditCommand]) {
+        self.end_history_traversal();
 
         // Run the commands over the edit buffer
         for command in commands {
@@ -1507,6 +1915,16 @@ impl Reedline {
         !self.hide_hints && matches!(self.input_mode, InputMode::Regular)
     }
 
+    /// The character in front of the command line painted under the buffer, if it is shown
+    fn command_line_indicator(&self) -> Option<char> {
+        match self.input_mode {
+            InputMode::BufferSearch { backward: false } => Some('/'),
+            InputMode::BufferSearch { backward: true } => Some('?'),
+            InputMode::ExCommand => Some(':'),
+            _ => None,
+        }
+    }
+
     /// Repaint of either the buffer or the parts for reverse history search
     fn repaint(&mut self, prompt: &dyn Prompt) -> io::Result<()> {
         // Repainting
@@ -1666,6 +2084,7 @@ impl Reedline {
     }
 
     fn open_editor(&mut self) -> Result<()> {
+        self.end_history_traversal();
         match &mut self.buffer_editor {
             Some(BufferEditor {
                 ref mut command,
@@ -1741,6 +2160,43 @@ impl Reedline {
         Ok(())
     }
 
+    /// Style the selections and cursors besides the primary cursor
+    ///
+    /// A cursor at the end of a line gets a styled space to be visible. Returns the position
+    /// of the primary cursor in the styled text, which is moved by those spaces.
+    fn paint_secondary_cursors(
+        &self,
+        styled_text: &mut StyledText,
+        cursor_position_in_buffer: usize,
+    ) -> usize {
+        let buffer = self.editor.get_buffer();
+        let mut cursor_position = cursor_position_in_buffer;
+        for cursor in self.editor.secondary_cursors() {
+            if let Some((from, to)) = cursor.selection() {
+                styled_text.style_range(from, to, self.visual_selection_style);
+            }
+        }
+        for cursor in self.editor.secondary_cursors().iter().rev() {
+            let position = cursor.insertion_point;
+            match buffer[position..].graphemes(true).next() {
+                Some(grapheme) if grapheme != "\n" && grapheme != "\r\n" => {
+                    styled_text.style_range(
+                        position,
+                        position + grapheme.len(),
+                        self.secondary_cursor_style,
+                    );
+                }
+                _ => {
+                    styled_text.insert(position, (self.secondary_cursor_style, " ".into()));
+                    if position < cursor_position {
+                        cursor_position += 1;
+                    }
+                }
+            }
+        }
+        cursor_position
+    }
+
     /// Triggers a full repaint including the prompt parts
     ///
     /// Includes the highlighting and hinting calls.
@@ -1751,12 +2207,45 @@ impl Reedline {
         let mut styled_text = self
             .highlighter
             .highlight(buffer_to_paint, cursor_position_in_buffer);
-        if let Some((from, to)) = self.editor.get_selection() {
+        if let Some(ranges) = self.editor.get_block_selection() {
+            for (from, to) in ranges {
+                styled_text.style_range(from, to, self.visual_selection_style);
+            }
+        } else if let Some((from, to)) = self.editor.get_selection() {
             styled_text.style_range(from, to, self.visual_selection_style);
         }
+        let search_pattern = match self.input_mode {
+            InputMode::BufferSearch { .. } => Some(self.command_line.as_str()),
+            _ => self.editor.search_highlight(),
+        };
+        if let Some(pattern) = search_pattern.filter(|pattern| !pattern.is_empty()) {
+            for (from, matched) in buffer_to_paint.match_indices(pattern) {
+                styled_text.style_range(from, from + matched.len(), self.search_match_style);
+            }
+        }
+        if let Some(style) = self.matching_bracket_style {
+            let line_buffer = self.editor.line_buffer();
+            let pair = [
+                Some(cursor_position_in_buffer),
+                cursor_position_in_buffer.checked_sub(1),
+            ]
+            .into_iter()
+            .flatten()
+            .find_map(|offset| {
+                line_buffer
+                    .matching_bracket_within_lines(offset, MATCHING_BRACKET_LINES)
+                    .map(|matching| (offset, matching))
+            });
+            if let Some((bracket, matching)) = pair {
+                styled_text.style_range(bracket, bracket + 1, style);
+                styled_text.style_range(matching, matching + 1, style);
+            }
+        }
+        let cursor_position_in_styled_text =
+            self.paint_secondary_cursors(&mut styled_text, cursor_position_in_buffer);
 
         let (before_cursor, after_cursor) = styled_text.render_around_insertion_point(
-            cursor_position_in_buffer,
+            cursor_position_in_styled_text,
             prompt,
             self.use_ansi_coloring,
         );
@@ -1780,6 +2269,13 @@ impl Reedline {
             String::new()
         };
 
+        let command_line = match (self.command_line_indicator(), &self.ex_command_error) {
+            (Some(indicator), _) => format!("{indicator}{}", self.command_line),
+            (None, Some(error)) if self.use_ansi_coloring => Color::Red.paint(error).to_string(),
+            (None, Some(error)) => error.clone(),
+            (None, None) => String::new(),
+        };
+
         // Needs to add return carriage to newlines because when not in raw mode
         // some OS don't fully return the carriage
 
@@ -1790,7 +2286,8 @@ impl Reedline {
             &before_cursor,
             &after_cursor,
             &hint,
-        );
+        )
+        .with_command_line(&command_line);
 
         // Updating the working details of the active menu
         for menu in self.menus.iter_mut() {
@@ -1896,3 +2393,209 @@ fn thread_safe() {
     fn f<S: Send>(_: S) {}
     f(Reedline::create());
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ColumnarMenu, MenuBuilder};
+    use pretty_assertions::assert_eq;
+
+    fn reedline_with_history(entries: &[&str]) -> Reedline {
+        let mut reedline = Reedline::create();
+        for entry in entries {
+            reedline
+                .history
+                .save(HistoryItem::from_command_line(*entry))
+                .unwrap();
+        }
+        reedline
+    }
+
+    fn type_str(reedline: &mut Reedline, input: &str) {
+        for c in input.chars() {
+            reedline.run_edit_commands(&[EditCommand::InsertChar(c)]);
+        }
+    }
+
+    #[test]
+    fn undo_after_history_navigation_restores_typed_line() {
+        let mut reedline = reedline_with_history(&["git log", "git status"]);
+        type_str(&mut reedline, "git");
+
+        reedline.previous_history();
+        reedline.previous_history();
+        reedline.run_edit_commands(&[EditCommand::InsertChar('!')]);
+        assert_eq!(reedline.current_buffer_contents(), "git log!");
+
+        reedline.run_edit_commands(&[EditCommand::Undo]);
+        assert_eq!(reedline.current_buffer_contents(), "git log");
+        reedline.run_edit_commands(&[EditCommand::Undo]);
+        assert_eq!(reedline.current_buffer_contents(), "git");
+        reedline.run_edit_commands(&[EditCommand::Redo]);
+        assert_eq!(reedline.current_buffer_contents(), "git log");
+    }
+
+    #[test]
+    fn completion_during_history_traversal_is_kept_and_undoable() {
+        let mut reedline = reedline_with_history(&["ls gi"])
+            .with_completer(Box::new(DefaultCompleter::new(vec!["git".into()])))
+            .with_quick_completions(true)
+            .with_menu(ReedlineMenu::EngineCompleter(Box::new(
+                ColumnarMenu::default().with_name("completion_menu"),
+            )));
+        let prompt = crate::DefaultPrompt::default();
+
+        reedline.previous_history();
+        reedline
+            .handle_editor_event(&prompt, ReedlineEvent::Menu("completion_menu".into()))
+            .unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "ls git");
+
+        reedline.run_edit_commands(&[EditCommand::InsertChar(' ')]);
+        assert_eq!(reedline.current_buffer_contents(), "ls git ");
+
+        reedline.run_edit_commands(&[EditCommand::Undo]);
+        assert_eq!(reedline.current_buffer_contents(), "ls git");
+        reedline.run_edit_commands(&[EditCommand::Undo]);
+        assert_eq!(reedline.current_buffer_contents(), "ls gi");
+    }
+
+    #[test]
+    fn macros_replay_their_raw_events() {
+        let mut reedline = Reedline::create().with_edit_mode(Box::<crate::Vi>::default());
+        let prompt = crate::DefaultPrompt::default();
+        let key = |c| {
+            ReedlineRawEvent::try_from(Event::Key(KeyEvent::new(
+                KeyCode::Char(c),
+                KeyModifiers::NONE,
+            )))
+            .unwrap()
+        };
+        reedline.set_macro('a', vec![key('a'), key('b')]);
+        assert_eq!(reedline.macro_events('a').map(<[_]>::len), Some(2));
+
+        reedline
+            .handle_event(&prompt, ReedlineEvent::ReplayMacro('a'))
+            .unwrap();
+        reedline
+            .handle_event(&prompt, ReedlineEvent::ReplayMacro('a'))
+            .unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "abab");
+
+        // A macro replaying itself stops instead of running forever
+        let esc =
+            ReedlineRawEvent::try_from(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
+                .unwrap();
+        reedline.set_macro('b', vec![key('x'), esc, key('@'), key('b')]);
+        reedline
+            .handle_event(&prompt, ReedlineEvent::ReplayMacro('b'))
+            .unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "ababx");
+    }
+
+    #[test]
+    fn ending_a_macro_drops_its_whole_key_sequence() {
+        let inputrc = crate::Inputrc::parse(
+            "\"\\C-x(\": start-kbd-macro\n\"\\C-x)\": end-kbd-macro\n",
+            "Bash",
+        );
+        assert!(inputrc.issues.is_empty());
+        let mut reedline = Reedline::create().with_edit_mode(inputrc.edit_mode());
+        let prompt = crate::DefaultPrompt::default();
+        let type_keys = |reedline: &mut Reedline, keys: &[(KeyCode, KeyModifiers)]| {
+            for &(code, modifiers) in keys {
+                let raw =
+                    ReedlineRawEvent::try_from(Event::Key(KeyEvent::new(code, modifiers))).unwrap();
+                let event = reedline.parse_raw_event(raw);
+                reedline.handle_event(&prompt, event).unwrap();
+            }
+        };
+
+        type_keys(
+            &mut reedline,
+            &[
+                (KeyCode::Char('x'), KeyModifiers::CONTROL),
+                (KeyCode::Char('('), KeyModifiers::NONE),
+                (KeyCode::Char('a'), KeyModifiers::NONE),
+                (KeyCode::Char('b'), KeyModifiers::NONE),
+                (KeyCode::Char('x'), KeyModifiers::CONTROL),
+                (KeyCode::Char(')'), KeyModifiers::NONE),
+            ],
+        );
+        assert_eq!(reedline.current_buffer_contents(), "ab");
+        assert_eq!(reedline.macro_events('0').map(<[_]>::len), Some(2));
+
+        reedline
+            .handle_event(&prompt, ReedlineEvent::ReplayMacro('0'))
+            .unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "abab");
+    }
+
+    #[test]
+    fn vi_keeps_consecutive_cuts_apart() {
+        let mut reedline = Reedline::create().with_edit_mode(Box::<crate::Vi>::default());
+        let prompt = crate::DefaultPrompt::default();
+        let key = |code, modifiers| {
+            ReedlineRawEvent::try_from(Event::Key(KeyEvent::new(code, modifiers))).unwrap()
+        };
+        reedline.run_edit_commands(&[
+            EditCommand::InsertString("foo bar baz".into()),
+            EditCommand::MoveToStart { select: false },
+        ]);
+        let keys = vec![
+            key(KeyCode::Esc, KeyModifiers::NONE),
+            key(KeyCode::Char('d'), KeyModifiers::NONE),
+            key(KeyCode::Char('w'), KeyModifiers::NONE),
+            key(KeyCode::Char('d'), KeyModifiers::NONE),
+            key(KeyCode::Char('w'), KeyModifiers::NONE),
+            key(KeyCode::Char('P'), KeyModifiers::SHIFT),
+        ];
+        reedline.set_macro('a', keys);
+
+        reedline
+            .handle_event(&prompt, ReedlineEvent::ReplayMacro('a'))
+            .unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "bar baz");
+    }
+
+    #[test]
+    fn ex_commands_run_from_the_command_line() {
+        let mut reedline = Reedline::create().with_ex_command(
+            "greet",
+            ExCommand::Callback(Box::new(|args| {
+                ReedlineEvent::Edit(vec![EditCommand::InsertString(format!("hello {args}"))])
+            })),
+        );
+        let prompt = crate::DefaultPrompt::default();
+        let run = |reedline: &mut Reedline, line: &str| {
+            reedline
+                .handle_event(&prompt, ReedlineEvent::ExCommandLine)
+                .unwrap();
+            let typed = line.chars().map(EditCommand::InsertChar).collect();
+            reedline
+                .handle_event(&prompt, ReedlineEvent::Edit(typed))
+                .unwrap();
+            reedline
+                .handle_event(&prompt, ReedlineEvent::Enter)
+                .unwrap()
+        };
+        type_str(&mut reedline, "a a\na a");
+
+        run(&mut reedline, "s/a/b/g");
+        assert_eq!(reedline.current_buffer_contents(), "a a\nb b");
+        run(&mut reedline, "%s/a/c/");
+        assert_eq!(reedline.current_buffer_contents(), "c a\nb b");
+        run(&mut reedline, "greet world");
+        assert_eq!(reedline.current_buffer_contents(), "hello worldc a\nb b");
+        run(&mut reedline, "unknown");
+        assert_eq!(reedline.current_buffer_contents(), "hello worldc a\nb b");
+        assert_eq!(
+            reedline.ex_command_error.as_deref(),
+            Some("Not an editor command: unknown")
+        );
+        reedline
+            .handle_event(&prompt, ReedlineEvent::Repaint)
+            .unwrap();
+        assert_eq!(reedline.ex_command_error, None);
+    }
+}
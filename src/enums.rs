@@ -1,5 +1,5 @@
 use crossterm::event::{Event, KeyEvent, KeyEventKind};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::fmt::{Display, Formatter};
 use strum_macros::EnumIter;
 
@@ -56,6 +56,18 @@ pub enum EditCommand {
         select: bool,
     },
 
+    /// Move to the same column on the line above, if there is one
+    MoveLineUp {
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
+    /// Move to the same column on the line below, if there is one
+    MoveLineDown {
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
     /// Move one word to the left
     MoveWordLeft {
         /// Select the text between the current cursor position and destination
@@ -124,6 +136,14 @@ pub enum EditCommand {
     /// Replace characters with string
     ReplaceChars(usize, String),
 
+    /// Replace the character under the cursor with the given one and move right, like the
+    /// Vi replace mode. Appends at the end of a line
+    OverwriteChar(char),
+
+    /// Move left over the last character typed with [`EditCommand::OverwriteChar`],
+    /// restoring the character it replaced
+    BackspaceOverwrite,
+
     /// Backspace delete from the current insertion point
     Backspace,
 
@@ -151,6 +171,13 @@ pub enum EditCommand {
     /// Cut the current line
     CutCurrentLine,
 
+    /// Cut the current line with its line ending as text, or with the line ending in
+    /// front of it on the last line (Emacs `C-S-<backspace>`)
+    KillWholeLine,
+
+    /// Copy the current line to the local buffer, pasted again as a whole line
+    CopyCurrentLine,
+
     /// Cut from the start of the buffer to the insertion point
     CutFromStart,
 
@@ -187,6 +214,10 @@ pub enum EditCommand {
     /// Paste the cut buffer in front of the insertion point (vi `p`)
     PasteCutBufferAfter,
 
+    /// Replace the text inserted by the preceding paste of the cut buffer with the
+    /// next older entry of the kill ring (Emacs `M-y`)
+    YankPop,
+
     /// Upper case the current word
     UppercaseWord,
 
@@ -196,21 +227,168 @@ pub enum EditCommand {
     /// Capitalize the current character
     CapitalizeChar,
 
+    /// Upper case the first character of the current word and lower case the rest, then
+    /// move behind the word (Emacs `M-c`)
+    CapitalizeWord,
+
     /// Switch the case of the current character
     SwitchcaseChar,
 
+    /// Upper case the selected text and move to its start
+    UppercaseSelection,
+
+    /// Lower case the selected text and move to its start
+    LowercaseSelection,
+
+    /// Switch the case of the selected text and move to its start
+    SwitchcaseSelection,
+
+    /// Replace every selected character but line endings with the given character
+    ReplaceSelectedChars(char),
+
+    /// Join the lines touched by the selection, or the current line with the next one,
+    /// replacing each line break and the indentation behind it with a single space
+    JoinLines,
+
+    /// Replace the occurrences of the pattern in the current line, like Vi `:s/a/b/`
+    Substitute {
+        /// The text to replace
+        pattern: String,
+        /// The text replacing it
+        replacement: String,
+        /// Substitute in every line of the buffer instead of the current one
+        all_lines: bool,
+        /// Replace every occurrence in a line instead of only the first one
+        global: bool,
+    },
+
     /// Swap the current word with the word to the right
     SwapWords,
 
     /// Swap the current grapheme/character with the one to the right
     SwapGraphemes,
 
+    /// Swap the current line with the one above, or below on the first line, and move
+    /// behind both lines (Emacs `C-x C-t`)
+    TransposeLines,
+
+    /// Delete the spaces and tabs around the cursor (Emacs `M-\`)
+    DeleteHorizontalSpace,
+
+    /// Replace the spaces and tabs around the cursor with a single space (Emacs `M-SPC`)
+    JustOneSpace,
+
+    /// Delete the blank lines around the current blank line but one, or the blank lines
+    /// following the current line if it is not blank (Emacs `C-x C-o`)
+    DeleteBlankLines,
+
+    /// Move to the bracket matching the first bracket at or behind the cursor on the line
+    MoveToMatchingBracket {
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
+    /// Cut from the cursor up to and including the matching bracket, see
+    /// [`EditCommand::MoveToMatchingBracket`]
+    CutToMatchingBracket,
+
+    /// Remember the cursor position as the named mark, see [`EditCommand::MoveToMark`]
+    SetMark(char),
+
+    /// Move to the named mark. Marks stay on their text when text in front of them changes
+    MoveToMark {
+        /// Name of the mark
+        name: char,
+        /// Move to the first non-blank character of the line of the mark instead
+        line: bool,
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
+    /// Move back to the position before the last jump, like Vi `Ctrl-o`.
+    /// Moves to a mark or to the matching bracket are jumps
+    MoveToOlderJump,
+
+    /// Move forward in the jump list again, like Vi `Ctrl-i`
+    MoveToNewerJump,
+
+    /// Move to the next occurrence of the pattern in the buffer, wrapping around at its end.
+    /// The search is remembered for [`EditCommand::RepeatBufferSearch`]
+    SearchBuffer {
+        /// The text to search for
+        pattern: String,
+        /// Search towards the start of the buffer
+        backward: bool,
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
+    /// Repeat the last [`EditCommand::SearchBuffer`], like Vi `n` and `N`
+    RepeatBufferSearch {
+        /// Search in the opposite direction of the last search
+        reverse: bool,
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
+    /// Cut the text object around the cursor to the local buffer
+    CutTextObject(TextObject),
+
+    /// Copy the text object around the cursor to the local buffer and move to its start
+    CopyTextObject(TextObject),
+
+    /// Select the text object around the cursor
+    SelectTextObject(TextObject),
+
+    /// Put the characters before and after the selection, moving to the first one
+    SurroundSelection {
+        /// Character put before the selection
+        left: char,
+        /// Character put after the selection
+        right: char,
+    },
+
+    /// Delete the quotes or brackets of the given kind around the cursor
+    DeleteSurrounding(TextObjectKind),
+
+    /// Replace the quotes or brackets of the given kind around the cursor
+    ChangeSurrounding {
+        /// The kind of quotes or brackets to replace
+        kind: TextObjectKind,
+        /// Character replacing the opening one
+        left: char,
+        /// Character replacing the closing one
+        right: char,
+    },
+
+    /// Indent the current line or all lines of the selection by one level
+    Indent,
+
+    /// Remove one level of indentation from the current line or all lines of the selection
+    Dedent,
+
     /// Undo the previous edit command
     Undo,
 
     /// Redo an edit command from the undo history
     Redo,
 
+    /// Go to the previous state of the undo history in the order the states were
+    /// created, regardless of the undo branch (vi `g-`)
+    UndoEarlier,
+
+    /// Go to the next state of the undo history in the order the states were
+    /// created, regardless of the undo branch (vi `g+`)
+    UndoLater,
+
+    /// Go back in the undo history to the state the buffer had the given number of seconds
+    /// before the current state (vi `:earlier {N}s`)
+    UndoEarlierBySeconds(u64),
+
+    /// Go forward in the undo history to the state the buffer had the given number of seconds
+    /// after the current state (vi `:later {N}s`)
+    UndoLaterBySeconds(u64),
+
     /// CutUntil right until char
     CutRightUntil(char),
 
@@ -258,12 +436,44 @@ pub enum EditCommand {
     /// Select whole input buffer
     SelectAll,
 
+    /// Switch between a rectangular block selection and a regular selection.
+    /// Starts a selection at the cursor if there is none
+    ToggleBlockSelection,
+
+    /// Switch between selecting whole lines and a regular selection.
+    /// Starts a selection at the cursor if there is none
+    ToggleLineSelection,
+
+    /// Move the cursor to the other end of the selection, keeping the selected text
+    SwapSelectionEnds,
+
+    /// Add a cursor on the line above the topmost cursor
+    AddCursorAbove,
+
+    /// Add a cursor on the line below the bottommost cursor
+    AddCursorBelow,
+
+    /// Add a cursor selecting the next occurrence of the selected text.
+    /// Without a selection select the word under the cursor
+    AddCursorAtNextMatch,
+
+    /// Remove all cursors but the primary cursor
+    ClearSecondaryCursors,
+
     /// Cut selection to local buffer
     CutSelection,
 
     /// Copy selection to local buffer
     CopySelection,
 
+    /// Copy selection to local buffer and move to its start, like the Vi yank
+    CopySelectionAndCollapse,
+
+    /// Use the named register instead of the local buffer for the next edit command
+    ///
+    /// With the `system_clipboard` feature the register `+` is the system clipboard.
+    SelectRegister(char),
+
     /// Paste content from local buffer at the current cursor position
     Paste,
 
@@ -280,6 +490,44 @@ pub enum EditCommand {
     PasteSystem,
 }
 
+/// A range of text around the cursor, like the text objects of Vi (`iw`, `a"`, ...)
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+pub struct TextObject {
+    /// Whether the surrounding delimiters or whitespace are included
+    pub scope: TextObjectScope,
+    /// The kind of text the object spans
+    pub kind: TextObjectKind,
+}
+
+/// Whether a [`TextObject`] includes its surroundings
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+pub enum TextObjectScope {
+    /// Only the content, e.g. the text between two quotes (Vi `i`)
+    #[default]
+    Inner,
+    /// The content together with its delimiters or the adjacent whitespace (Vi `a`)
+    Around,
+}
+
+/// The kind of text spanned by a [`TextObject`]
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+pub enum TextObjectKind {
+    /// A word or a run of whitespace, split according to the word definition
+    #[default]
+    Word,
+    /// A WORD, i.e. any run of non-whitespace characters, or a run of whitespace
+    BigWord,
+    /// Text between two of the given quote characters on the current line
+    Quote(char),
+    /// Text between the given opening bracket and its closing partner, across lines.
+    /// One of `(`, `[`, `{` or `<`
+    Bracket(char),
+    /// Consecutive non-blank lines, or consecutive blank lines
+    Paragraph,
+    /// A comma separated argument inside of the closest enclosing brackets
+    Argument,
+}
+
 impl Display for EditCommand {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         match self {
@@ -293,6 +541,10 @@ impl Display for EditCommand {
             }
             EditCommand::MoveLeft { .. } => write!(f, "MoveLeft Optional[select: <bool>]"),
             EditCommand::MoveRight { .. } => write!(f, "MoveRight Optional[select: <bool>]"),
+            EditCommand::MoveLineUp { .. } => write!(f, "MoveLineUp Optional[select: <bool>]"),
+            EditCommand::MoveLineDown { .. } => {
+                write!(f, "MoveLineDown Optional[select: <bool>]")
+            }
             EditCommand::MoveWordLeft { .. } => write!(f, "MoveWordLeft Optional[select: <bool>]"),
             EditCommand::MoveBigWordLeft { .. } => {
                 write!(f, "MoveBigWordLeft Optional[select: <bool>]")
@@ -326,6 +578,8 @@ impl Display for EditCommand {
             EditCommand::InsertNewline => write!(f, "InsertNewline"),
             EditCommand::ReplaceChar(_) => write!(f, "ReplaceChar <char>"),
             EditCommand::ReplaceChars(_, _) => write!(f, "ReplaceChars <int> <string>"),
+            EditCommand::OverwriteChar(_) => write!(f, "OverwriteChar Value: <char>"),
+            EditCommand::BackspaceOverwrite => write!(f, "BackspaceOverwrite"),
             EditCommand::Backspace => write!(f, "Backspace"),
             EditCommand::Delete => write!(f, "Delete"),
             EditCommand::CutChar => write!(f, "CutChar"),
@@ -335,6 +589,8 @@ impl Display for EditCommand {
             EditCommand::ClearToLineEnd => write!(f, "ClearToLineEnd"),
             EditCommand::Complete => write!(f, "Complete"),
             EditCommand::CutCurrentLine => write!(f, "CutCurrentLine"),
+            EditCommand::KillWholeLine => write!(f, "KillWholeLine"),
+            EditCommand::CopyCurrentLine => write!(f, "CopyCurrentLine"),
             EditCommand::CutFromStart => write!(f, "CutFromStart"),
             EditCommand::CutFromLineStart => write!(f, "CutFromLineStart"),
             EditCommand::CutToEnd => write!(f, "CutToEnd"),
@@ -347,14 +603,71 @@ impl Display for EditCommand {
             EditCommand::CutBigWordRightToNext => write!(f, "CutBigWordRightToNext"),
             EditCommand::PasteCutBufferBefore => write!(f, "PasteCutBufferBefore"),
             EditCommand::PasteCutBufferAfter => write!(f, "PasteCutBufferAfter"),
+            EditCommand::YankPop => write!(f, "YankPop"),
             EditCommand::UppercaseWord => write!(f, "UppercaseWord"),
             EditCommand::LowercaseWord => write!(f, "LowercaseWord"),
             EditCommand::SwitchcaseChar => write!(f, "SwitchcaseChar"),
+            EditCommand::UppercaseSelection => write!(f, "UppercaseSelection"),
+            EditCommand::LowercaseSelection => write!(f, "LowercaseSelection"),
+            EditCommand::SwitchcaseSelection => write!(f, "SwitchcaseSelection"),
+            EditCommand::ReplaceSelectedChars(_) => {
+                write!(f, "ReplaceSelectedChars Value: <char>")
+            }
+            EditCommand::JoinLines => write!(f, "JoinLines"),
+            EditCommand::Substitute { .. } => write!(
+                f,
+                "Substitute Value: <string>, <string>, Optional[all_lines: <bool>, global: <bool>]"
+            ),
             EditCommand::CapitalizeChar => write!(f, "CapitalizeChar"),
+            EditCommand::CapitalizeWord => write!(f, "CapitalizeWord"),
             EditCommand::SwapWords => write!(f, "SwapWords"),
             EditCommand::SwapGraphemes => write!(f, "SwapGraphemes"),
+            EditCommand::TransposeLines => write!(f, "TransposeLines"),
+            EditCommand::DeleteHorizontalSpace => write!(f, "DeleteHorizontalSpace"),
+            EditCommand::JustOneSpace => write!(f, "JustOneSpace"),
+            EditCommand::DeleteBlankLines => write!(f, "DeleteBlankLines"),
+            EditCommand::MoveToMatchingBracket { .. } => {
+                write!(f, "MoveToMatchingBracket Optional[select: <bool>]")
+            }
+            EditCommand::CutToMatchingBracket => write!(f, "CutToMatchingBracket"),
+            EditCommand::SetMark(_) => write!(f, "SetMark Value: <char>"),
+            EditCommand::MoveToMark { .. } => write!(
+                f,
+                "MoveToMark Value: <char>, Optional[line: <bool>, select: <bool>]"
+            ),
+            EditCommand::MoveToOlderJump => write!(f, "MoveToOlderJump"),
+            EditCommand::MoveToNewerJump => write!(f, "MoveToNewerJump"),
+            EditCommand::SearchBuffer { .. } => write!(
+                f,
+                "SearchBuffer Value: <string>, Optional[backward: <bool>, select: <bool>]"
+            ),
+            EditCommand::RepeatBufferSearch { .. } => write!(
+                f,
+                "RepeatBufferSearch Optional[reverse: <bool>, select: <bool>]"
+            ),
+            EditCommand::CutTextObject(_) => write!(f, "CutTextObject Value: <TextObject>"),
+            EditCommand::SurroundSelection { .. } => {
+                write!(f, "SurroundSelection Value: <char>, <char>")
+            }
+            EditCommand::DeleteSurrounding(_) => {
+                write!(f, "DeleteSurrounding Value: <TextObjectKind>")
+            }
+            EditCommand::ChangeSurrounding { .. } => write!(
+                f,
+                "ChangeSurrounding Value: <TextObjectKind>, <char>, <char>"
+            ),
+            EditCommand::CopyTextObject(_) => write!(f, "CopyTextObject Value: <TextObject>"),
+            EditCommand::SelectTextObject(_) => {
+                write!(f, "SelectTextObject Value: <TextObject>")
+            }
+            EditCommand::Indent => write!(f, "Indent"),
+            EditCommand::Dedent => write!(f, "Dedent"),
             EditCommand::Undo => write!(f, "Undo"),
             EditCommand::Redo => write!(f, "Redo"),
+            EditCommand::UndoEarlier => write!(f, "UndoEarlier"),
+            EditCommand::UndoLater => write!(f, "UndoLater"),
+            EditCommand::UndoEarlierBySeconds(_) => write!(f, "UndoEarlierBySeconds Value: <int>"),
+            EditCommand::UndoLaterBySeconds(_) => write!(f, "UndoLaterBySeconds Value: <int>"),
             EditCommand::CutRightUntil(_) => write!(f, "CutRightUntil Value: <char>"),
             EditCommand::CutRightBefore(_) => write!(f, "CutRightBefore Value: <char>"),
             EditCommand::MoveRightUntil { .. } => write!(f, "MoveRightUntil Value: <char>"),
@@ -362,8 +675,17 @@ impl Display for EditCommand {
             EditCommand::CutLeftUntil(_) => write!(f, "CutLeftUntil Value: <char>"),
             EditCommand::CutLeftBefore(_) => write!(f, "CutLeftBefore Value: <char>"),
             EditCommand::SelectAll => write!(f, "SelectAll"),
+            EditCommand::ToggleBlockSelection => write!(f, "ToggleBlockSelection"),
+            EditCommand::ToggleLineSelection => write!(f, "ToggleLineSelection"),
+            EditCommand::SwapSelectionEnds => write!(f, "SwapSelectionEnds"),
+            EditCommand::AddCursorAbove => write!(f, "AddCursorAbove"),
+            EditCommand::AddCursorBelow => write!(f, "AddCursorBelow"),
+            EditCommand::AddCursorAtNextMatch => write!(f, "AddCursorAtNextMatch"),
+            EditCommand::ClearSecondaryCursors => write!(f, "ClearSecondaryCursors"),
             EditCommand::CutSelection => write!(f, "CutSelection"),
             EditCommand::CopySelection => write!(f, "CopySelection"),
+            EditCommand::CopySelectionAndCollapse => write!(f, "CopySelectionAndCollapse"),
+            EditCommand::SelectRegister(_) => write!(f, "SelectRegister Value: <char>"),
             EditCommand::Paste => write!(f, "Paste"),
             #[cfg(feature = "system_clipboard")]
             EditCommand::CutSelectionSystem => write!(f, "CutSelectionSystem"),
@@ -388,6 +710,8 @@ impl EditCommand {
             | EditCommand::MoveToPosition { select, .. }
             | EditCommand::MoveLeft { select, .. }
             | EditCommand::MoveRight { select, .. }
+            | EditCommand::MoveLineUp { select, .. }
+            | EditCommand::MoveLineDown { select, .. }
             | EditCommand::MoveWordLeft { select, .. }
             | EditCommand::MoveBigWordLeft { select, .. }
             | EditCommand::MoveWordRight { select, .. }
@@ -398,11 +722,27 @@ impl EditCommand {
             | EditCommand::MoveRightUntil { select, .. }
             | EditCommand::MoveRightBefore { select, .. }
             | EditCommand::MoveLeftUntil { select, .. }
-            | EditCommand::MoveLeftBefore { select, .. } => {
+            | EditCommand::MoveLeftBefore { select, .. }
+            | EditCommand::MoveToMark { select, .. }
+            | EditCommand::SearchBuffer { select, .. }
+            | EditCommand::RepeatBufferSearch { select, .. }
+            | EditCommand::MoveToMatchingBracket { select } => {
                 EditType::MoveCursor { select: *select }
             }
 
-            EditCommand::SelectAll => EditType::MoveCursor { select: true },
+            EditCommand::SelectAll
+            | EditCommand::ToggleBlockSelection
+            | EditCommand::ToggleLineSelection
+            | EditCommand::SwapSelectionEnds
+            | EditCommand::AddCursorAbove
+            | EditCommand::AddCursorBelow
+            | EditCommand::AddCursorAtNextMatch
+            | EditCommand::SelectTextObject(_) => EditType::MoveCursor { select: true },
+            EditCommand::ClearSecondaryCursors
+            | EditCommand::MoveToOlderJump
+            | EditCommand::MoveToNewerJump
+            | EditCommand::CopyTextObject(_)
+            | EditCommand::CopySelectionAndCollapse => EditType::MoveCursor { select: false },
             // Text edits
             EditCommand::InsertChar(_)
             | EditCommand::Backspace
@@ -412,12 +752,15 @@ impl EditCommand {
             | EditCommand::InsertNewline
             | EditCommand::ReplaceChar(_)
             | EditCommand::ReplaceChars(_, _)
+            | EditCommand::OverwriteChar(_)
+            | EditCommand::BackspaceOverwrite
             | EditCommand::BackspaceWord
             | EditCommand::DeleteWord
             | EditCommand::Clear
             | EditCommand::ClearToLineEnd
             | EditCommand::Complete
             | EditCommand::CutCurrentLine
+            | EditCommand::KillWholeLine
             | EditCommand::CutFromStart
             | EditCommand::CutFromLineStart
             | EditCommand::CutToLineEnd
@@ -430,12 +773,31 @@ impl EditCommand {
             | EditCommand::CutBigWordRightToNext
             | EditCommand::PasteCutBufferBefore
             | EditCommand::PasteCutBufferAfter
+            | EditCommand::YankPop
             | EditCommand::UppercaseWord
             | EditCommand::LowercaseWord
             | EditCommand::SwitchcaseChar
+            | EditCommand::UppercaseSelection
+            | EditCommand::LowercaseSelection
+            | EditCommand::SwitchcaseSelection
+            | EditCommand::ReplaceSelectedChars(_)
+            | EditCommand::JoinLines
+            | EditCommand::Substitute { .. }
             | EditCommand::CapitalizeChar
+            | EditCommand::CapitalizeWord
             | EditCommand::SwapWords
             | EditCommand::SwapGraphemes
+            | EditCommand::TransposeLines
+            | EditCommand::DeleteHorizontalSpace
+            | EditCommand::JustOneSpace
+            | EditCommand::DeleteBlankLines
+            | EditCommand::CutToMatchingBracket
+            | EditCommand::CutTextObject(_)
+            | EditCommand::SurroundSelection { .. }
+            | EditCommand::DeleteSurrounding(_)
+            | EditCommand::ChangeSurrounding { .. }
+            | EditCommand::Indent
+            | EditCommand::Dedent
             | EditCommand::CutRightUntil(_)
             | EditCommand::CutRightBefore(_)
             | EditCommand::CutLeftUntil(_)
@@ -446,9 +808,17 @@ impl EditCommand {
             #[cfg(feature = "system_clipboard")] // Sadly cfg attributes in patterns don't work
             EditCommand::CutSelectionSystem | EditCommand::PasteSystem => EditType::EditText,
 
-            EditCommand::Undo | EditCommand::Redo => EditType::UndoRedo,
-
-            EditCommand::CopySelection => EditType::NoOp,
+            EditCommand::Undo
+            | EditCommand::Redo
+            | EditCommand::UndoEarlier
+            | EditCommand::UndoLater
+            | EditCommand::UndoEarlierBySeconds(_)
+            | EditCommand::UndoLaterBySeconds(_) => EditType::UndoRedo,
+
+            EditCommand::CopySelection
+            | EditCommand::CopyCurrentLine
+            | EditCommand::SelectRegister(_)
+            | EditCommand::SetMark(_) => EditType::NoOp,
             #[cfg(feature = "system_clipboard")]
             EditCommand::CopySelectionSystem => EditType::NoOp,
         }
@@ -470,7 +840,7 @@ pub enum EditType {
 }
 
 /// Every line change should come with an `UndoBehavior` tag, which can be used to
-/// calculate how the change should be reflected on the undo stack
+/// calculate how the change should be reflected on the undo tree
 #[derive(Debug)]
 pub enum UndoBehavior {
     /// Character insertion, tracking the character inserted
@@ -605,6 +975,15 @@ pub enum ReedlineEvent {
     /// Search the history for a string
     SearchHistory,
 
+    /// Type a pattern to search the buffer for, see [`EditCommand::SearchBuffer`]
+    SearchBuffer {
+        /// Search towards the start of the buffer
+        backward: bool,
+    },
+
+    /// Type a command into the Vi `:` command line, see [`crate::ExCommand`]
+    ExCommandLine,
+
     /// In vi mode multiple reedline events can be chained while parsing the
     /// command or movement characters
     Multiple(Vec<ReedlineEvent>),
@@ -644,6 +1023,21 @@ pub enum ReedlineEvent {
 
     /// Open text editor
     OpenEditor,
+
+    /// Record the following raw events as the named macro, like Vi `qa`
+    RecordMacro(char),
+
+    /// Stop recording the macro. The event stopping the recording is not part of it
+    StopMacroRecording,
+
+    /// Replay the raw events of the named macro
+    ReplayMacro(char),
+
+    /// Start the numeric argument of the Emacs mode, or multiply it by four, like Emacs
+    /// `C-u`. Digits typed afterwards set the argument
+    ///
+    /// Bound to `Ctrl-u` in the default Emacs keybindings
+    UniversalArgument,
 }
 
 impl Display for ReedlineEvent {
@@ -674,6 +1068,10 @@ impl Display for ReedlineEvent {
             ReedlineEvent::Left => write!(f, "Left"),
             ReedlineEvent::NextHistory => write!(f, "NextHistory"),
             ReedlineEvent::SearchHistory => write!(f, "SearchHistory"),
+            ReedlineEvent::SearchBuffer { .. } => {
+                write!(f, "SearchBuffer Optional[backward: <bool>]")
+            }
+            ReedlineEvent::ExCommandLine => write!(f, "ExCommandLine"),
             ReedlineEvent::Multiple(_) => write!(f, "Multiple[ {{ ReedLineEvents, }} ]"),
             ReedlineEvent::UntilFound(_) => write!(f, "UntilFound [ {{ ReedLineEvents, }} ]"),
             ReedlineEvent::Menu(_) => write!(f, "Menu Name: <string>"),
@@ -687,6 +1085,10 @@ impl Display for ReedlineEvent {
             ReedlineEvent::MenuPagePrevious => write!(f, "MenuPagePrevious"),
             ReedlineEvent::ExecuteHostCommand(_) => write!(f, "ExecuteHostCommand"),
             ReedlineEvent::OpenEditor => write!(f, "OpenEditor"),
+            ReedlineEvent::RecordMacro(_) => write!(f, "RecordMacro Value: <char>"),
+            ReedlineEvent::StopMacroRecording => write!(f, "StopMacroRecording"),
+            ReedlineEvent::ReplayMacro(_) => write!(f, "ReplayMacro Value: <char>"),
+            ReedlineEvent::UniversalArgument => write!(f, "UniversalArgument"),
         }
     }
 }
@@ -702,8 +1104,23 @@ pub(crate) enum EventStatus {
 /// It ensures that the given event doesn't contain [KeyEventKind::Release]
 /// (which is rejected) or [KeyEventKind::Repeat] (which is converted to
 /// [KeyEventKind::Press]).
+///
+/// Raw events can be serialized, e.g. to keep recorded macros between sessions.
+/// Deserializing applies the same checks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct ReedlineRawEvent(Event);
 
+impl<'de> Deserialize<'de> for ReedlineRawEvent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let event = Event::deserialize(deserializer)?;
+        Self::try_from(event)
+            .map_err(|()| serde::de::Error::custom("key release events are not raw events"))
+    }
+}
+
 impl TryFrom<Event> for ReedlineRawEvent {
     type Error = ();
 
@@ -734,3 +1151,31 @@ impl From<ReedlineRawEvent> for Event {
         event.0
     }
 }
+
+// Needs serde_json, which comes with the SQLite history
+#[cfg(all(test, any(feature = "sqlite", feature = "sqlite-dynlib")))]
+mod test {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn deserializing_raw_events_rejects_key_releases() {
+        let key = |kind| {
+            Event::Key(KeyEvent::new_with_kind(
+                KeyCode::Char('a'),
+                KeyModifiers::NONE,
+                kind,
+            ))
+        };
+        let press = serde_json::to_string(&key(KeyEventKind::Press)).unwrap();
+        let repeat = serde_json::to_string(&key(KeyEventKind::Repeat)).unwrap();
+        let release = serde_json::to_string(&key(KeyEventKind::Release)).unwrap();
+
+        let raw: ReedlineRawEvent = serde_json::from_str(&press).unwrap();
+        assert_eq!(Event::from(raw), key(KeyEventKind::Press));
+        let raw: ReedlineRawEvent = serde_json::from_str(&repeat).unwrap();
+        assert_eq!(Event::from(raw), key(KeyEventKind::Press));
+        assert!(serde_json::from_str::<ReedlineRawEvent>(&release).is_err());
+    }
+}
@@ -0,0 +1,140 @@
+//! Commands of the Vi `:` command line
+//!
+//! Besides the built-in commands the host can register its own with
+//! [`crate::Reedline::with_ex_command`].
+use crate::{EditCommand, ReedlineEvent};
+
+/// A command run from the Vi `:` command line, e.g. `:name arguments`
+pub enum ExCommand {
+    /// Handle the event, like a key bound to it
+    Event(ReedlineEvent),
+    /// Call the function with the arguments following the name of the command and handle
+    /// the returned event
+    Callback(Box<dyn FnMut(&str) -> ReedlineEvent + Send>),
+}
+
+impl ExCommand {
+    pub(crate) fn run(&mut self, args: &str) -> ReedlineEvent {
+        match self {
+            ExCommand::Event(event) => event.clone(),
+            ExCommand::Callback(callback) => callback(args),
+        }
+    }
+}
+
+/// Split the command line into the name of the command and its arguments
+///
+/// Names consist of letters and may start with the `%` range, like in `%s/a/b/`.
+pub(crate) fn split_ex_command(line: &str) -> (&str, &str) {
+    let line = line.trim();
+    let range = usize::from(line.starts_with('%'));
+    let name_len = line[range..]
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(line.len() - range);
+    let (name, args) = line.split_at(range + name_len);
+    (name, args.trim_start())
+}
+
+/// The event of a built-in command, `None` for unknown commands or invalid arguments
+///
+/// - `:s/pattern/replacement/g` substitutes in the current line, `:%s` in the whole buffer
+/// - `:w` submits the buffer
+/// - `:e` opens the buffer in the external editor
+pub(crate) fn builtin_ex_command(name: &str, args: &str) -> Option<ReedlineEvent> {
+    match name {
+        "w" | "write" if args.is_empty() => Some(ReedlineEvent::Submit),
+        "e" | "edit" if args.is_empty() => Some(ReedlineEvent::OpenEditor),
+        "s" | "substitute" | "%s" | "%substitute" => {
+            let (pattern, replacement, global) = parse_substitute(args)?;
+            Some(ReedlineEvent::Edit(vec![EditCommand::Substitute {
+                pattern,
+                replacement,
+                all_lines: name.starts_with('%'),
+                global,
+            }]))
+        }
+        _ => None,
+    }
+}
+
+/// Parse the `/pattern/replacement/flags` of a substitution
+///
+/// Any punctuation can separate the parts, a backslash escapes it.
+fn parse_substitute(args: &str) -> Option<(String, String, bool)> {
+    let mut chars = args.chars();
+    let separator = chars.next().filter(char::is_ascii_punctuation)?;
+    if separator == '\\' {
+        return None;
+    }
+
+    let mut parts = vec![String::new()];
+    let mut escaped = false;
+    for c in chars {
+        if !escaped && c == separator && parts.len() < 3 {
+            parts.push(String::new());
+            continue;
+        }
+        let part = parts.last_mut()?;
+        if escaped {
+            if c != separator {
+                part.push('\\');
+            }
+            part.push(c);
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else {
+            part.push(c);
+        }
+    }
+    if escaped {
+        parts.last_mut()?.push('\\');
+    }
+
+    let mut parts = parts.into_iter();
+    let pattern = parts.next().filter(|pattern| !pattern.is_empty())?;
+    let replacement = parts.next().unwrap_or_default();
+    let global = match parts.next().as_deref() {
+        None | Some("") => false,
+        Some("g") => true,
+        Some(_) => return None,
+    };
+    Some((pattern, replacement, global))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("w", ("w", ""))]
+    #[case(" s/a/b/g ", ("s", "/a/b/g"))]
+    #[case("%s#a#b#", ("%s", "#a#b#"))]
+    #[case("greet  world", ("greet", "world"))]
+    #[case("", ("", ""))]
+    fn test_split_ex_command(#[case] line: &str, #[case] expected: (&str, &str)) {
+        assert_eq!(split_ex_command(line), expected);
+    }
+
+    #[rstest]
+    #[case("/foo/bar/g", Some(("foo", "bar", true)))]
+    #[case("/foo/bar", Some(("foo", "bar", false)))]
+    #[case("/foo", Some(("foo", "", false)))]
+    #[case("#a/b#c#", Some(("a/b", "c", false)))]
+    #[case("/a\\/b/c\\d/", Some(("a/b", "c\\d", false)))]
+    #[case("//bar/", None)]
+    #[case("/foo/bar/x", None)]
+    #[case("foo", None)]
+    fn test_parse_substitute(#[case] args: &str, #[case] expected: Option<(&str, &str, bool)>) {
+        assert_eq!(
+            parse_substitute(args),
+            expected.map(|(pattern, replacement, global)| (
+                pattern.to_string(),
+                replacement.to_string(),
+                global
+            ))
+        );
+    }
+}
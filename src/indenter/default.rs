@@ -0,0 +1,106 @@
+use crate::Indenter;
+
+/// A default indenter which indents the lines inside of brackets by four spaces
+///
+/// New lines keep the indentation of the line before, one level deeper behind an opening
+/// bracket. Typing a closing bracket at the start of a line aligns it with the line of the
+/// matching opening bracket.
+pub struct DefaultIndenter;
+
+impl Indenter for DefaultIndenter {
+    fn indent_for_newline(&self, line: &str, insertion_point: usize) -> String {
+        let before = &line[line_start(line, insertion_point)..insertion_point];
+        let mut indentation = leading_whitespace(before).to_string();
+        if before.trim_end().ends_with(['(', '[', '{']) {
+            indentation.push_str(self.indent_unit());
+        }
+        indentation
+    }
+
+    fn indent_after_typing(
+        &self,
+        line: &str,
+        insertion_point: usize,
+        typed: char,
+    ) -> Option<String> {
+        if !matches!(typed, ')' | ']' | '}') {
+            return None;
+        }
+        let typed_at = insertion_point.checked_sub(typed.len_utf8())?;
+        let current_line_start = line_start(line, typed_at);
+        if !line[current_line_start..typed_at].trim().is_empty() {
+            return None;
+        }
+        let opener = matching_opener(&line[..typed_at])?;
+        let opener_line_start = line_start(line, opener);
+        Some(leading_whitespace(&line[opener_line_start..]).to_string())
+    }
+}
+
+fn line_start(line: &str, offset: usize) -> usize {
+    line[..offset].rfind('\n').map_or(0, |i| i + 1)
+}
+
+fn leading_whitespace(line: &str) -> &str {
+    let end = line
+        .find(|c: char| c != ' ' && c != '\t')
+        .unwrap_or(line.len());
+    &line[..end]
+}
+
+/// Offset of the unclosed bracket nearest to the end of `text`
+fn matching_opener(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, c) in text.char_indices().rev() {
+        match c {
+            ')' | ']' | '}' => depth += 1,
+            '(' | '[' | '{' if depth == 0 => return Some(i),
+            '(' | '[' | '{' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("ls", 2, "")]
+    #[case("  ls", 4, "  ")]
+    #[case("def f() {", 9, "    ")]
+    #[case("a {\n    b [ ", 12, "        ")]
+    #[case("a {\n    b [ ", 6, "  ")]
+    #[case("{}", 1, "    ")]
+    fn test_indent_for_newline(
+        #[case] line: &str,
+        #[case] insertion_point: usize,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(
+            DefaultIndenter.indent_for_newline(line, insertion_point),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case("  a {\n      }", 13, '}', Some("  "))]
+    #[case("a {\n  [\n    ]", 13, ']', Some("  "))]
+    #[case("a {\n  b }", 9, '}', None)]
+    #[case("a {\n  b", 7, 'b', None)]
+    #[case("    )", 5, ')', None)]
+    fn test_indent_after_typing(
+        #[case] line: &str,
+        #[case] insertion_point: usize,
+        #[case] typed: char,
+        #[case] expected: Option<&str>,
+    ) {
+        assert_eq!(
+            DefaultIndenter.indent_after_typing(line, insertion_point, typed),
+            expected.map(str::to_string)
+        );
+    }
+}
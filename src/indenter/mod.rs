@@ -0,0 +1,28 @@
+mod default;
+pub use default::DefaultIndenter;
+
+/// The indentation trait. Implementers of this trait decide how the continuation lines of
+/// multiline input are indented
+pub trait Indenter: Send {
+    /// The indentation of the new line started by a line break inserted at `insertion_point`
+    fn indent_for_newline(&self, line: &str, insertion_point: usize) -> String;
+
+    /// The indentation of the current line after `typed` was inserted in front of
+    /// `insertion_point`, e.g. to dedent a line once its closing bracket is typed.
+    ///
+    /// `None` leaves the line as it is.
+    fn indent_after_typing(
+        &self,
+        _line: &str,
+        _insertion_point: usize,
+        _typed: char,
+    ) -> Option<String> {
+        None
+    }
+
+    /// The indentation added by [`EditCommand::Indent`](crate::EditCommand::Indent) and
+    /// removed by [`EditCommand::Dedent`](crate::EditCommand::Dedent)
+    fn indent_unit(&self) -> &str {
+        "    "
+    }
+}
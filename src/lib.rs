@@ -226,11 +226,15 @@
 #![warn(missing_docs)]
 // #![deny(warnings)]
 mod core_editor;
-pub use core_editor::Editor;
 pub use core_editor::LineBuffer;
+pub use core_editor::{AutoPair, AutoPairRule, Editor};
+pub use core_editor::{UndoNode, UndoStateId, UndoStep, UndoTree, WordDefinition};
 
 mod enums;
-pub use enums::{EditCommand, ReedlineEvent, ReedlineRawEvent, Signal, UndoBehavior};
+pub use enums::{
+    EditCommand, ReedlineEvent, ReedlineRawEvent, Signal, TextObject, TextObjectKind,
+    TextObjectScope, UndoBehavior,
+};
 
 mod painting;
 pub use painting::{Painter, StyledText};
@@ -238,6 +242,9 @@ pub use painting::{Painter, StyledText};
 mod engine;
 pub use engine::Reedline;
 
+mod ex_command;
+pub use ex_command::ExCommand;
+
 mod result;
 pub use result::{ReedlineError, ReedlineErrorVariants, Result};
 
@@ -259,7 +266,8 @@ pub use prompt::{
 mod edit_mode;
 pub use edit_mode::{
     default_emacs_keybindings, default_vi_insert_keybindings, default_vi_normal_keybindings,
-    CursorConfig, EditMode, Emacs, Keybindings, Vi,
+    default_vi_replace_keybindings, CursorConfig, EditMode, Emacs, Inputrc, InputrcEditingMode,
+    KeyCombination, Keybindings, KeymapError, Vi,
 };
 
 mod highlighter;
@@ -275,6 +283,9 @@ pub use hinter::{DefaultHinter, Hinter};
 mod validator;
 pub use validator::{DefaultValidator, ValidationResult, Validator};
 
+mod indenter;
+pub use indenter::{DefaultIndenter, Indenter};
+
 mod menu;
 pub use menu::{
     menu_functions, ColumnarMenu, DescriptionMenu, DescriptionMode, IdeMenu, ListMenu, Menu,
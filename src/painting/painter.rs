@@ -260,7 +260,10 @@ impl Painter {
             let shape = match &prompt_mode {
                 PromptEditMode::Emacs => shapes.emacs,
                 PromptEditMode::Vi(PromptViMode::Insert) => shapes.vi_insert,
-                PromptEditMode::Vi(PromptViMode::Normal) => shapes.vi_normal,
+                PromptEditMode::Vi(PromptViMode::Replace) => shapes.vi_replace,
+                PromptEditMode::Vi(
+                    PromptViMode::Normal | PromptViMode::Visual | PromptViMode::VisualLine,
+                ) => shapes.vi_normal,
                 _ => None,
             };
             if let Some(shape) = shape {
@@ -369,7 +372,9 @@ impl Painter {
         if let Some(menu) = menu {
             self.print_menu(menu, lines, use_ansi_coloring)?;
         } else {
-            self.stdout.queue(Print(&lines.hint))?;
+            self.stdout
+                .queue(Print(&lines.hint))?
+                .queue(Print(&lines.command_line))?;
         }
 
         Ok(())
@@ -477,6 +482,8 @@ impl Painter {
             // Hint lines
             let hint_skipped = skip_buffer_lines(&lines.hint, 0, Some(offset));
             self.stdout.queue(Print(hint_skipped))?;
+            let command_line_skipped = skip_buffer_lines(&lines.command_line, 0, Some(offset));
+            self.stdout.queue(Print(command_line_skipped))?;
         }
 
         Ok(())
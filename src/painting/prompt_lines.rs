@@ -15,6 +15,8 @@ pub(crate) struct PromptLines<'prompt> {
     pub(crate) before_cursor: Cow<'prompt, str>,
     pub(crate) after_cursor: Cow<'prompt, str>,
     pub(crate) hint: Cow<'prompt, str>,
+    // Painted on a line of its own below the buffer and the hint, empty if not shown
+    pub(crate) command_line: Cow<'prompt, str>,
     pub(crate) right_prompt_on_last_line: bool,
 }
 
@@ -50,10 +52,19 @@ impl<'prompt> PromptLines<'prompt> {
             before_cursor,
             after_cursor,
             hint,
+            command_line: Cow::Borrowed(""),
             right_prompt_on_last_line,
         }
     }
 
+    /// Show the command line of a search or of `:`, or a message in its place
+    pub(crate) fn with_command_line(mut self, command_line: &str) -> Self {
+        if !command_line.is_empty() {
+            self.command_line = Cow::Owned(coerce_crlf(&format!("\n{command_line}")).into_owned());
+        }
+        self
+    }
+
     /// The required lines to paint the buffer are calculated by counting the
     /// number of newlines in all the strings that form the prompt and buffer.
     /// The plus 1 is to indicate that there should be at least one line.
@@ -64,6 +75,7 @@ impl<'prompt> PromptLines<'prompt> {
                 + &self.before_cursor
                 + &self.after_cursor
                 + &self.hint
+                + &self.command_line
         } else {
             self.prompt_str_left.to_string()
                 + &self.prompt_indicator
@@ -245,6 +257,7 @@ mod tests {
             before_cursor: Cow::Borrowed(before_cursor),
             after_cursor: Cow::Borrowed(""),
             hint: Cow::Borrowed(""),
+            command_line: Cow::Borrowed(""),
             right_prompt_on_last_line: false,
         };
 
@@ -252,4 +265,15 @@ mod tests {
 
         assert_eq!(pos, expected);
     }
+
+    #[test]
+    fn command_line_takes_its_own_line() {
+        let prompt = crate::DefaultPrompt::default();
+        let lines = PromptLines::new(&prompt, PromptEditMode::Default, None, "ls", "", " -la");
+        assert_eq!(lines.required_lines(100, None), 1);
+
+        let lines = lines.with_command_line(":w");
+        assert_eq!(lines.command_line, "\r\n:w");
+        assert_eq!(lines.required_lines(100, None), 2);
+    }
 }
@@ -28,6 +28,27 @@ impl StyledText {
         self.buffer.push(styled_string);
     }
 
+    /// Insert a styled string at the given byte offset of the text
+    pub fn insert(&mut self, index: usize, styled_string: (Style, String)) {
+        let mut current_idx = 0;
+        for pair_idx in 0..self.buffer.len() {
+            let pair = &mut self.buffer[pair_idx];
+            if index == current_idx {
+                self.buffer.insert(pair_idx, styled_string);
+                return;
+            }
+            if index < current_idx + pair.1.len() {
+                let after_range = pair.1.split_off(index - current_idx);
+                let after_range = (pair.0, after_range);
+                self.buffer.insert(pair_idx + 1, styled_string);
+                self.buffer.insert(pair_idx + 2, after_range);
+                return;
+            }
+            current_idx += pair.1.len();
+        }
+        self.buffer.push(styled_string);
+    }
+
     /// Style range with the provided style
     pub fn style_range(&mut self, from: usize, to: usize, new_style: Style) {
         let (from, to) = if from > to { (to, from) } else { (from, to) };
@@ -256,6 +277,21 @@ mod test {
         assert_eq!(styled_text.buffer[2], (before_style, "f".into()));
     }
     #[test]
+    fn insert_splits_part() {
+        let (styled_text_template, before_style, after_style) = get_styled_text_template();
+        let mut styled_text = styled_text_template;
+        styled_text.insert(4, (after_style, " ".into()));
+        styled_text.insert(3, (after_style, "|".into()));
+        styled_text.insert(11, (after_style, "$".into()));
+        assert_eq!(styled_text.buffer[0], (before_style, "aaa".into()));
+        assert_eq!(styled_text.buffer[1], (after_style, "|".into()));
+        assert_eq!(styled_text.buffer[2], (before_style, "b".into()));
+        assert_eq!(styled_text.buffer[3], (after_style, " ".into()));
+        assert_eq!(styled_text.buffer[4], (before_style, "bb".into()));
+        assert_eq!(styled_text.buffer[5], (before_style, "ccc".into()));
+        assert_eq!(styled_text.buffer[6], (after_style, "$".into()));
+    }
+    #[test]
     fn regression_style_range_cargo_run() {
         let (_, before_style, after_style) = get_styled_text_template();
         let mut styled_text = StyledText {
@@ -5,7 +5,7 @@ use unicode_width::UnicodeWidthStr;
 ///
 /// Needed for correct output in raw mode.
 /// Only replaces solitary LF with CRLF.
-pub(crate) fn coerce_crlf(input: &str) -> Cow<str> {
+pub(crate) fn coerce_crlf(input: &str) -> Cow<'_, str> {
     let mut result = Cow::Borrowed(input);
     let mut cursor: usize = 0;
     for (idx, _) in input.match_indices('\n') {
@@ -19,15 +19,16 @@ pub(crate) fn coerce_crlf(input: &str) -> Cow<str> {
                 owned.push_str(&input[cursor..idx]);
                 result = Cow::Owned(owned);
             } else {
-                result += &input[cursor..idx];
+                result.to_mut().push_str(&input[cursor..idx]);
             }
-            result += "\r\n";
+            // Not `+=`, which would turn the still empty result into a borrowed one
+            result.to_mut().push_str("\r\n");
             // Advance beyond the matched LF char (single byte)
             cursor = idx + 1;
         }
     }
     if let Cow::Owned(_) = result {
-        result += &input[cursor..input.len()];
+        result.to_mut().push_str(&input[cursor..input.len()]);
     }
     result
 }
@@ -83,6 +84,7 @@ mod test {
     #[case("😇\nsentence", "😇\r\nsentence")]
     #[case("sentence\n😇", "sentence\r\n😇")]
     #[case("\n", "\r\n")]
+    #[case("\nsentence\nsentence", "\r\nsentence\r\nsentence")]
     #[case("", "")]
     fn test_coerce_crlf(#[case] input: &str, #[case] expected: &str) {
         let result = coerce_crlf(input);
@@ -6,3 +6,4 @@ pub use query::{
     get_reedline_keybinding_modifiers, get_reedline_keycodes, get_reedline_prompt_edit_modes,
     get_reedline_reedline_events,
 };
+pub(crate) use query::{key_code_from_name, key_code_name};
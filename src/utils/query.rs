@@ -71,6 +71,36 @@ impl Display for ReedLineCrossTermKeyCode {
     }
 }
 
+/// The `KeyCode` named like in [`get_reedline_keycodes`], ignoring case
+///
+/// `F1` to `F24` name the function keys and any other single character its key.
+pub(crate) fn key_code_from_name(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    if let Some(number) = name.strip_prefix(['F', 'f']) {
+        if let Ok(number @ 1..=24) = number.parse() {
+            return Some(KeyCode::F(number));
+        }
+    }
+    ReedLineCrossTermKeyCode::iterator()
+        .find(|key_code| key_code.to_string().eq_ignore_ascii_case(name))
+        .map(|key_code| key_code.0)
+}
+
+/// The name of the `KeyCode` read back by [`key_code_from_name`], if it has one
+pub(crate) fn key_code_name(key_code: KeyCode) -> Option<String> {
+    match key_code {
+        KeyCode::Char(' ') => Some("Space".to_string()),
+        KeyCode::Char(c) => Some(c.to_string()),
+        KeyCode::F(number) => Some(format!("F{number}")),
+        _ => ReedLineCrossTermKeyCode::iterator()
+            .find(|known| known.0 == key_code)
+            .map(ToString::to_string),
+    }
+}
+
 /// Return a `Vec` of the Reedline Keybinding Modifiers
 pub fn get_reedline_keybinding_modifiers() -> Vec<String> {
     vec![
@@ -114,6 +144,9 @@ pub fn get_reedline_edit_commands() -> Vec<String> {
 /// Get the default keybindings and return a `Vec<(String, String, String, String)>`
 /// where String 1 is `mode`, String 2 is `key_modifiers`, String 3 is `key_code`, and
 /// Sting 4 is `event`
+///
+/// The modifiers and key codes of a key sequence are separated by `, `, which does not
+/// appear within a single modifier or key code.
 pub fn get_reedline_default_keybindings() -> Vec<(String, String, String, String)> {
     let options = vec![
         ("emacs", default_emacs_keybindings()),
@@ -132,13 +165,21 @@ fn get_keybinding_strings(
     keybindings: &Keybindings,
 ) -> Vec<(String, String, String, String)> {
     let mut data: Vec<(String, String, String, String)> = keybindings
-        .get_keybindings()
-        .iter()
-        .map(|(combination, event)| {
+        .get_keybinding_sequences()
+        .into_iter()
+        .map(|(sequence, event)| {
+            let modifiers: Vec<_> = sequence
+                .iter()
+                .map(|combination| format!("{:?}", combination.modifier))
+                .collect();
+            let key_codes: Vec<_> = sequence
+                .iter()
+                .map(|combination| format!("{:?}", combination.key_code))
+                .collect();
             (
                 mode.to_string(),
-                format!("{:?}", combination.modifier),
-                format!("{:?}", combination.key_code),
+                modifiers.join(", "),
+                key_codes.join(", "),
                 format!("{event:?}"),
             )
         })
@@ -148,3 +189,39 @@ fn get_keybinding_strings(
 
     data
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::KeyCombination;
+    use crossterm::event::KeyModifiers;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn key_sequence_steps_are_told_apart() {
+        let mut keybindings = Keybindings::new();
+        keybindings.add_sequence_binding(
+            &[
+                KeyCombination {
+                    modifier: KeyModifiers::CONTROL | KeyModifiers::ALT,
+                    key_code: KeyCode::Char(' '),
+                },
+                KeyCombination {
+                    modifier: KeyModifiers::NONE,
+                    key_code: KeyCode::Char('a'),
+                },
+            ],
+            ReedlineEvent::Repaint,
+        );
+
+        assert_eq!(
+            get_keybinding_strings("emacs", &keybindings),
+            vec![(
+                "emacs".to_string(),
+                "KeyModifiers(CONTROL | ALT), KeyModifiers(0x0)".to_string(),
+                "Char(' '), Char('a')".to_string(),
+                "Repaint".to_string(),
+            )]
+        );
+    }
+}